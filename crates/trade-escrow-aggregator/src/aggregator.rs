@@ -0,0 +1,205 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use trade_escrow::state::Config;
+use trade_escrow_client::{instructions, messages};
+use trade_escrow_oracle::{Receipt, ReceiptKind};
+
+use crate::chain::{Chain, OpenEscrow};
+use crate::error::AggregatorError;
+use crate::oracle::OracleEndpoint;
+
+/// Exponential backoff between attempts on the same escrow
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next attempt after `attempts` unsuccessful ones
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = 1u32 << attempts.saturating_sub(1).min(16);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Outcome of one pass over the open escrows
+#[derive(Debug, Default)]
+pub struct TickReport {
+    /// Escrows that can still settle
+    pub open: usize,
+    /// Settlements confirmed this pass
+    pub submitted: Vec<(Pubkey, Signature)>,
+    /// Escrows still short of receipts or backing off
+    pub waiting: usize,
+    /// Oracle and submission errors, per escrow
+    pub errors: Vec<(Pubkey, AggregatorError)>,
+}
+
+/// Receipts gathered so far for one escrow
+#[derive(Default)]
+struct Pending {
+    receipts: HashMap<Pubkey, Receipt>,
+    attempts: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Pending {
+    fn back_off(&mut self, now: Instant, retry: &RetryPolicy) {
+        self.attempts += 1;
+        self.retry_at = Some(now + retry.delay(self.attempts));
+    }
+}
+
+pub struct Aggregator<C> {
+    chain: C,
+    oracles: Vec<Box<dyn OracleEndpoint>>,
+    threshold: usize,
+    retry: RetryPolicy,
+    pending: HashMap<Pubkey, Pending>,
+    /// Escrows settled by us, kept until the chain stops listing them as open
+    settled: HashMap<Pubkey, Signature>,
+}
+
+impl<C: Chain> Aggregator<C> {
+    pub fn new(chain: C, oracles: Vec<Box<dyn OracleEndpoint>>, threshold: usize) -> Self {
+        Self {
+            chain,
+            oracles,
+            threshold,
+            retry: RetryPolicy::default(),
+            pending: HashMap::new(),
+            settled: HashMap::new(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn chain(&self) -> &C {
+        &self.chain
+    }
+
+    /// Collect receipts for every open escrow and settle those that have enough.
+    ///
+    /// Escrows are never submitted twice: one settled here is skipped until
+    /// the chain no longer reports it as open.
+    pub fn tick(&mut self, now: Instant) -> Result<TickReport, AggregatorError> {
+        let mut report = TickReport::default();
+        let config = self.chain.config()?;
+        if config.paused {
+            return Ok(report);
+        }
+
+        let chain_time = self.chain.unix_timestamp()?;
+        let open = self.chain.open_escrows()?;
+        let listed: HashSet<Pubkey> = open.iter().map(|open| open.address).collect();
+        self.settled.retain(|address, _| listed.contains(address));
+
+        let open: Vec<OpenEscrow> = open
+            .into_iter()
+            .filter(|open| open.escrow.deadline >= chain_time)
+            .filter(|open| !self.settled.contains_key(&open.address))
+            .collect();
+        let settleable: HashSet<Pubkey> = open.iter().map(|open| open.address).collect();
+        self.pending
+            .retain(|address, _| settleable.contains(address));
+        report.open = open.len();
+
+        for escrow in open {
+            let pending = self.pending.entry(escrow.address).or_default();
+            if pending.retry_at.is_some_and(|retry_at| now < retry_at) {
+                report.waiting += 1;
+                continue;
+            }
+
+            for oracle in self.oracles.iter() {
+                match oracle.receipt(&escrow.address) {
+                    Ok(Some(receipt)) if accepts(&config, &escrow, &receipt) => {
+                        pending.receipts.insert(receipt.oracle, receipt);
+                    }
+                    Ok(_) => {}
+                    Err(e) => report.errors.push((escrow.address, e)),
+                }
+            }
+
+            // Program order of the oracles, so retries build the same transaction
+            let receipts: Vec<Receipt> = config
+                .oracle_pubkeys
+                .iter()
+                .filter_map(|oracle| pending.receipts.get(oracle).cloned())
+                .take(self.threshold)
+                .collect();
+            if receipts.len() < self.threshold {
+                pending.back_off(now, &self.retry);
+                report.waiting += 1;
+                continue;
+            }
+
+            match self.chain.submit(&settle_instructions(&escrow, &receipts)) {
+                Ok(signature) => {
+                    self.pending.remove(&escrow.address);
+                    self.settled.insert(escrow.address, signature);
+                    report.submitted.push((escrow.address, signature));
+                }
+                Err(e) => {
+                    pending.back_off(now, &self.retry);
+                    report.errors.push((escrow.address, e));
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Whether a receipt is a valid settlement attestation by a configured oracle
+fn accepts(config: &Config, open: &OpenEscrow, receipt: &Receipt) -> bool {
+    receipt.kind == ReceiptKind::Settlement
+        && receipt.target == open.address
+        && config.oracle_pubkeys.contains(&receipt.oracle)
+        && receipt.message == messages::settlement_message(&open.address, &open.escrow)
+        && receipt.verify()
+}
+
+/// Ed25519 verify instructions for each receipt followed by `settle`.
+///
+/// Proceeds go to the seller's associated token account, and the referral
+/// share to the referrer's.
+pub fn settle_instructions(open: &OpenEscrow, receipts: &[Receipt]) -> Vec<Instruction> {
+    let mut ixs: Vec<Instruction> = receipts
+        .iter()
+        .map(|receipt| {
+            messages::ed25519_verify_instruction(
+                &receipt.oracle,
+                &receipt.signature_bytes(),
+                receipt.message.as_bytes(),
+            )
+        })
+        .collect();
+    ixs.push(instructions::settle(
+        &open.address,
+        &open.escrow,
+        &open.mint,
+        &get_associated_token_address(&open.escrow.seller, &open.mint),
+        open.escrow
+            .referrer
+            .map(|referrer| get_associated_token_address(&referrer, &open.mint)),
+        receipts.iter().map(Receipt::signature_bytes).collect(),
+    ));
+    ixs
+}
\ No newline at end of file
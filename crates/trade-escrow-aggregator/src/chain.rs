@@ -0,0 +1,132 @@
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_spl::token::TokenAccount;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::account::from_account;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::sysvar;
+use solana_sdk::transaction::Transaction;
+use trade_escrow::state::{Config, Escrow};
+use trade_escrow_client::{accounts, pda, PROGRAM_ID};
+
+use crate::error::AggregatorError;
+
+/// An unsettled escrow together with the mint of its vault
+#[derive(Clone)]
+pub struct OpenEscrow {
+    pub address: Pubkey,
+    pub escrow: Escrow,
+    pub mint: Pubkey,
+}
+
+/// What the aggregator needs from the cluster
+pub trait Chain {
+    fn config(&self) -> Result<Config, AggregatorError>;
+
+    /// Cluster time, which decides whether an escrow can still settle
+    fn unix_timestamp(&self) -> Result<i64, AggregatorError>;
+
+    /// Escrows not yet settled, including expired ones
+    fn open_escrows(&self) -> Result<Vec<OpenEscrow>, AggregatorError>;
+
+    /// Send a transaction and wait for it to be confirmed
+    fn submit(&self, instructions: &[Instruction]) -> Result<Signature, AggregatorError>;
+}
+
+/// Chain backed by a JSON-RPC node, paying fees from `payer`
+pub struct RpcChain {
+    client: RpcClient,
+    payer: Keypair,
+}
+
+impl RpcChain {
+    pub fn new(url: impl ToString, payer: Keypair) -> Self {
+        Self {
+            client: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+            payer,
+        }
+    }
+}
+
+/// `getProgramAccounts` config selecting escrow accounts not yet settled
+pub fn open_escrows_config() -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Escrow::DISCRIMINATOR)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(Escrow::SETTLED_OFFSET, &[0])),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+impl Chain for RpcChain {
+    fn config(&self) -> Result<Config, AggregatorError> {
+        let address = pda::config();
+        let account = self.client.get_account(&address)?;
+        accounts::config(&account.data).map_err(|_| AggregatorError::Decode(address))
+    }
+
+    fn unix_timestamp(&self) -> Result<i64, AggregatorError> {
+        let account = self.client.get_account(&sysvar::clock::id())?;
+        from_account::<Clock, _>(&account)
+            .map(|clock| clock.unix_timestamp)
+            .ok_or(AggregatorError::Decode(sysvar::clock::id()))
+    }
+
+    fn open_escrows(&self) -> Result<Vec<OpenEscrow>, AggregatorError> {
+        let escrows = self
+            .client
+            .get_program_accounts_with_config(&PROGRAM_ID, open_escrows_config())?
+            .into_iter()
+            .map(|(address, account)| {
+                accounts::escrow(&account.data)
+                    .map(|escrow| (address, escrow))
+                    .map_err(|_| AggregatorError::Decode(address))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let vaults: Vec<Pubkey> = escrows
+            .iter()
+            .map(|(address, _)| pda::escrow_vault(address))
+            .collect();
+        let mut open = Vec::with_capacity(escrows.len());
+        for (chunk, vault_chunk) in escrows.chunks(100).zip(vaults.chunks(100)) {
+            let vault_accounts = self.client.get_multiple_accounts(vault_chunk)?;
+            for ((address, escrow), (vault, account)) in
+                chunk.iter().zip(vault_chunk.iter().zip(vault_accounts))
+            {
+                // A vault missing between the two reads belongs to an escrow closed meanwhile
+                let Some(account) = account else { continue };
+                let vault = TokenAccount::try_deserialize(&mut &account.data[..])
+                    .map_err(|_| AggregatorError::Decode(*vault))?;
+                open.push(OpenEscrow {
+                    address: *address,
+                    escrow: escrow.clone(),
+                    mint: vault.mint,
+                });
+            }
+        }
+        Ok(open)
+    }
+
+    fn submit(&self, instructions: &[Instruction]) -> Result<Signature, AggregatorError> {
+        let blockhash = self.client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        Ok(self.client.send_and_confirm_transaction(&transaction)?)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,23 @@
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AggregatorError {
+    #[error("rpc error: {0}")]
+    Rpc(String),
+
+    #[error("oracle {url} unavailable: {reason}")]
+    Oracle { url: String, reason: String },
+
+    #[error("failed to decode account {0}")]
+    Decode(Pubkey),
+
+    #[error("failed to load keypair: {0}")]
+    Keypair(String),
+}
+
+impl From<solana_client::client_error::ClientError> for AggregatorError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        AggregatorError::Rpc(error.to_string())
+    }
+}
\ No newline at end of file
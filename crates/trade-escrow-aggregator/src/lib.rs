@@ -0,0 +1,15 @@
+//! Attestation aggregator for trade-escrow.
+//!
+//! Polls open escrows, collects receipts from the configured oracle
+//! endpoints and, once enough distinct oracles have attested delivery,
+//! submits `settle` preceded by one Ed25519 verify instruction per receipt.
+
+pub mod aggregator;
+pub mod chain;
+pub mod error;
+pub mod oracle;
+
+pub use aggregator::{settle_instructions, Aggregator, RetryPolicy, TickReport};
+pub use chain::{Chain, OpenEscrow, RpcChain};
+pub use error::AggregatorError;
+pub use oracle::{HttpOracle, OracleEndpoint};
\ No newline at end of file
@@ -0,0 +1,75 @@
+use clap::Parser;
+use solana_sdk::signature::read_keypair_file;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+use trade_escrow_aggregator::{
+    Aggregator, AggregatorError, HttpOracle, OracleEndpoint, RetryPolicy, RpcChain,
+};
+
+/// Collect oracle receipts for open escrows and submit their settlements
+#[derive(Parser)]
+#[command(name = "trade-escrow-aggregator", version)]
+struct Args {
+    /// JSON-RPC endpoint of the cluster
+    #[arg(long, default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// Keypair paying transaction fees (Solana CLI JSON format)
+    #[arg(long)]
+    payer: PathBuf,
+
+    /// Base URL of an oracle's receipt endpoint; repeat for each oracle
+    #[arg(long = "oracle", required = true)]
+    oracles: Vec<String>,
+
+    /// Receipts required before settling
+    #[arg(long, default_value_t = 2)]
+    threshold: usize,
+
+    /// How often to poll open escrows, in milliseconds
+    #[arg(long, default_value_t = 5000)]
+    poll_interval_ms: u64,
+
+    /// First retry delay for an escrow, in milliseconds; doubles per attempt
+    #[arg(long, default_value_t = 2000)]
+    retry_base_ms: u64,
+
+    /// Longest retry delay, in milliseconds
+    #[arg(long, default_value_t = 60000)]
+    retry_max_ms: u64,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    let payer =
+        read_keypair_file(&args.payer).map_err(|e| AggregatorError::Keypair(e.to_string()))?;
+    let oracles: Vec<Box<dyn OracleEndpoint>> = args
+        .oracles
+        .iter()
+        .map(|url| Box::new(HttpOracle::new(url)) as Box<dyn OracleEndpoint>)
+        .collect();
+    let mut aggregator =
+        Aggregator::new(RpcChain::new(&args.rpc_url, payer), oracles, args.threshold).with_retry(
+            RetryPolicy {
+                base_delay: Duration::from_millis(args.retry_base_ms),
+                max_delay: Duration::from_millis(args.retry_max_ms),
+            },
+        );
+
+    loop {
+        match aggregator.tick(Instant::now()) {
+            Ok(report) => {
+                for (escrow, signature) in report.submitted.iter() {
+                    println!("settled {} in {}", escrow, signature);
+                }
+                for (escrow, error) in report.errors.iter() {
+                    eprintln!("{}: {}", escrow, error);
+                }
+            }
+            Err(e) => eprintln!("failed to poll escrows: {}", e),
+        }
+        thread::sleep(Duration::from_millis(args.poll_interval_ms));
+    }
+}
\ No newline at end of file
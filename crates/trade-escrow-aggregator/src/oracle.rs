@@ -0,0 +1,61 @@
+use solana_sdk::pubkey::Pubkey;
+use std::time::Duration;
+use trade_escrow_oracle::Receipt;
+
+use crate::error::AggregatorError;
+
+/// An oracle the aggregator can ask for receipts
+pub trait OracleEndpoint {
+    /// Where the oracle is reached, for error reports
+    fn url(&self) -> &str;
+
+    /// The oracle's latest receipt for an escrow, if it has one
+    fn receipt(&self, target: &Pubkey) -> Result<Option<Receipt>, AggregatorError>;
+}
+
+/// Oracle served by `trade-escrow-oracle` over HTTP
+pub struct HttpOracle {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpOracle {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_timeout(url, Duration::from_secs(5))
+    }
+
+    pub fn with_timeout(url: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+        }
+    }
+
+    fn unavailable(&self, reason: impl ToString) -> AggregatorError {
+        AggregatorError::Oracle {
+            url: self.url.clone(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl OracleEndpoint for HttpOracle {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn receipt(&self, target: &Pubkey) -> Result<Option<Receipt>, AggregatorError> {
+        match self
+            .agent
+            .get(&format!("{}/receipts/{}", self.url, target))
+            .call()
+        {
+            Ok(response) => response
+                .into_json()
+                .map(Some)
+                .map_err(|e| self.unavailable(e)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(self.unavailable(e)),
+        }
+    }
+}
\ No newline at end of file
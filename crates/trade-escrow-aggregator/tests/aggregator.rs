@@ -0,0 +1,325 @@
+use anchor_lang::AccountSerialize;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::ed25519_program;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+use trade_escrow::state::{Config, Escrow, WearBounds};
+use trade_escrow_aggregator::chain::open_escrows_config;
+use trade_escrow_aggregator::{
+    Aggregator, AggregatorError, Chain, HttpOracle, OpenEscrow, OracleEndpoint, RetryPolicy,
+};
+use trade_escrow_oracle::observation::item_class;
+use trade_escrow_oracle::{
+    ItemCriteria, OracleSigner, ReceiptServer, ReceiptStore, TradeObservation,
+};
+
+/// In-memory cluster recording submitted transactions
+struct MockChain {
+    config: Config,
+    escrows: RefCell<Vec<OpenEscrow>>,
+    submitted: RefCell<Vec<Vec<Instruction>>>,
+    failures: Cell<u32>,
+    /// Keep settled escrows listed as open, like a lagging RPC node
+    lagging: bool,
+}
+
+impl Chain for MockChain {
+    fn config(&self) -> Result<Config, AggregatorError> {
+        Ok(self.config.clone())
+    }
+
+    fn unix_timestamp(&self) -> Result<i64, AggregatorError> {
+        Ok(1_000)
+    }
+
+    fn open_escrows(&self) -> Result<Vec<OpenEscrow>, AggregatorError> {
+        Ok(self.escrows.borrow().clone())
+    }
+
+    fn submit(&self, instructions: &[Instruction]) -> Result<Signature, AggregatorError> {
+        if self.failures.get() > 0 {
+            self.failures.set(self.failures.get() - 1);
+            return Err(AggregatorError::Rpc("blockhash not found".to_string()));
+        }
+        self.submitted.borrow_mut().push(instructions.to_vec());
+        if !self.lagging {
+            self.escrows.borrow_mut().clear();
+        }
+        Ok(Signature::new_unique())
+    }
+}
+
+/// A running oracle service and its store
+struct MockOracle {
+    signer: OracleSigner,
+    store: ReceiptStore,
+    url: String,
+}
+
+impl MockOracle {
+    fn start() -> Self {
+        let signer = OracleSigner::new(Keypair::new());
+        let store = ReceiptStore::new();
+        let server = ReceiptServer::bind("127.0.0.1:0", signer.pubkey(), store.clone()).unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        server.spawn();
+        Self { signer, store, url }
+    }
+
+    fn observe(&self, observation: &TradeObservation) {
+        self.store.insert(self.signer.sign(observation));
+    }
+}
+
+struct Harness {
+    oracles: Vec<MockOracle>,
+    escrow: OpenEscrow,
+    delivered: TradeObservation,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let escrow = OpenEscrow {
+            address: Pubkey::new_unique(),
+            escrow: Escrow {
+                buyer: Pubkey::new_unique(),
+                seller: Pubkey::new_unique(),
+                asset_id: 42,
+                amount: 1_000_000,
+                deadline: 2_000,
+                ..Default::default()
+            },
+            mint: Pubkey::new_unique(),
+        };
+        let delivered = TradeObservation::ItemDelivered {
+            escrow: escrow.address,
+            asset_id: 42,
+            buyer: escrow.escrow.buyer,
+            criteria: None,
+        };
+        Self {
+            oracles: (0..3).map(|_| MockOracle::start()).collect(),
+            escrow,
+            delivered,
+        }
+    }
+
+    fn aggregator(&self, failures: u32, lagging: bool) -> Aggregator<MockChain> {
+        let mut config = Config::default();
+        for (key, oracle) in config.oracle_pubkeys.iter_mut().zip(self.oracles.iter()) {
+            *key = oracle.signer.pubkey();
+        }
+        let chain = MockChain {
+            config,
+            escrows: RefCell::new(vec![self.escrow.clone()]),
+            submitted: RefCell::new(vec![]),
+            failures: Cell::new(failures),
+            lagging,
+        };
+        let mut endpoints: Vec<Box<dyn OracleEndpoint>> = self
+            .oracles
+            .iter()
+            .map(|oracle| Box::new(HttpOracle::new(&oracle.url)) as Box<dyn OracleEndpoint>)
+            .collect();
+        // An oracle that is down must not hold up the others
+        endpoints.push(Box::new(HttpOracle::with_timeout(
+            "http://127.0.0.1:1",
+            Duration::from_millis(200),
+        )));
+        Aggregator::new(chain, endpoints, 2).with_retry(RetryPolicy {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(4),
+        })
+    }
+}
+
+#[test]
+fn settles_once_threshold_is_met_and_only_once() {
+    let harness = Harness::new();
+    let mut aggregator = harness.aggregator(0, true);
+    let start = Instant::now();
+
+    harness.oracles[0].observe(&harness.delivered);
+    let report = aggregator.tick(start).unwrap();
+    assert!(report.submitted.is_empty());
+    assert_eq!(report.waiting, 1);
+    assert_eq!(report.errors.len(), 1);
+
+    // Backing off: the second receipt is not picked up before the retry delay
+    harness.oracles[2].observe(&harness.delivered);
+    assert!(aggregator.tick(start).unwrap().submitted.is_empty());
+
+    let report = aggregator.tick(start + Duration::from_secs(1)).unwrap();
+    assert_eq!(report.submitted.len(), 1);
+
+    let submitted = aggregator.chain().submitted.borrow();
+    let ixs = &submitted[0];
+    assert_eq!(ixs.len(), 3);
+    let message = harness
+        .escrow
+        .escrow
+        .settlement_message(&harness.escrow.address);
+    for (ix, oracle) in ixs[..2]
+        .iter()
+        .zip([&harness.oracles[0], &harness.oracles[2]])
+    {
+        assert_eq!(ix.program_id, ed25519_program::ID);
+        assert_eq!(&ix.data[16..48], oracle.signer.pubkey().as_ref());
+        assert!(ix.data.ends_with(message.as_bytes()));
+    }
+    assert_eq!(ixs[2].program_id, trade_escrow::ID);
+    drop(submitted);
+
+    // The node still lists the escrow as open; it must not be settled again
+    harness.oracles[1].observe(&harness.delivered);
+    let report = aggregator.tick(start + Duration::from_secs(60)).unwrap();
+    assert!(report.submitted.is_empty());
+    assert_eq!(aggregator.chain().submitted.borrow().len(), 1);
+}
+
+#[test]
+fn retries_failed_submissions_with_backoff() {
+    let harness = Harness::new();
+    let mut aggregator = harness.aggregator(2, false);
+    let start = Instant::now();
+    harness.oracles[0].observe(&harness.delivered);
+    harness.oracles[1].observe(&harness.delivered);
+
+    let report = aggregator.tick(start).unwrap();
+    assert!(report.submitted.is_empty());
+    assert!(report
+        .errors
+        .iter()
+        .any(|(_, e)| matches!(e, AggregatorError::Rpc(_))));
+
+    // Second failure doubles the delay
+    assert!(aggregator
+        .tick(start + Duration::from_secs(1))
+        .unwrap()
+        .submitted
+        .is_empty());
+    assert!(aggregator
+        .tick(start + Duration::from_secs(2))
+        .unwrap()
+        .submitted
+        .is_empty());
+    assert_eq!(
+        aggregator
+            .tick(start + Duration::from_secs(3))
+            .unwrap()
+            .submitted
+            .len(),
+        1
+    );
+    assert!(aggregator.chain().escrows.borrow().is_empty());
+}
+
+#[test]
+fn ignores_receipts_that_would_not_verify() {
+    let harness = Harness::new();
+    let mut aggregator = harness.aggregator(0, false);
+
+    // An endpoint serving a receipt from an unconfigured key, and one for another item
+    let rogue = OracleSigner::new(Keypair::new());
+    harness.oracles[2]
+        .store
+        .insert(rogue.sign(&harness.delivered));
+    harness.oracles[0].observe(&harness.delivered);
+    harness.oracles[1].observe(&TradeObservation::ItemDelivered {
+        escrow: harness.escrow.address,
+        asset_id: 7,
+        buyer: harness.escrow.escrow.buyer,
+        criteria: None,
+    });
+
+    let report = aggregator.tick(Instant::now()).unwrap();
+    assert!(report.submitted.is_empty());
+    assert_eq!(report.waiting, 1);
+}
+
+#[test]
+fn buy_order_fills_need_receipts_for_the_ordered_item() {
+    let mut harness = Harness::new();
+    harness.escrow.escrow.item_class = Some(item_class(730, "AK-47 | Redline (Field-Tested)"));
+    harness.escrow.escrow.wear_bounds = Some(WearBounds {
+        min: 0,
+        max: 200_000_000,
+    });
+    let mut aggregator = harness.aggregator(0, false);
+    let start = Instant::now();
+    let delivered = |market_hash_name: &str, wear_bounds| TradeObservation::ItemDelivered {
+        escrow: harness.escrow.address,
+        asset_id: 42,
+        buyer: harness.escrow.escrow.buyer,
+        criteria: Some(ItemCriteria {
+            appid: 730,
+            market_hash_name: market_hash_name.to_string(),
+            wear_bounds,
+        }),
+    };
+
+    // Attesting delivery alone, another class, or another wear range is not enough
+    harness.oracles[0].observe(&harness.delivered);
+    harness.oracles[1].observe(&delivered(
+        "AK-47 | Redline (Battle-Scarred)",
+        Some((0, 200_000_000)),
+    ));
+    harness.oracles[2].observe(&delivered("AK-47 | Redline (Field-Tested)", None));
+    let report = aggregator.tick(start).unwrap();
+    assert!(report.submitted.is_empty());
+    assert_eq!(report.waiting, 1);
+
+    for oracle in &harness.oracles[..2] {
+        oracle.observe(&delivered(
+            "AK-47 | Redline (Field-Tested)",
+            Some((0, 200_000_000)),
+        ));
+    }
+    let report = aggregator.tick(start + Duration::from_secs(60)).unwrap();
+    assert_eq!(report.submitted.len(), 1);
+    let message = harness
+        .escrow
+        .escrow
+        .settlement_message(&harness.escrow.address);
+    assert!(message.ends_with(":0-200000000"));
+    let submitted = aggregator.chain().submitted.borrow();
+    assert!(submitted[0][0].data.ends_with(message.as_bytes()));
+}
+#[test]
+fn open_escrows_filter_reads_the_settled_flag() {
+    let matches = |escrow: &Escrow| {
+        let mut data = Vec::new();
+        escrow.try_serialize(&mut data).unwrap();
+        open_escrows_config()
+            .filters
+            .unwrap()
+            .iter()
+            .all(|filter| match filter {
+                RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&data),
+                _ => true,
+            })
+    };
+    // A lock time whose low byte is not zero, so only `settled` decides
+    let escrow = Escrow {
+        buyer: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        asset_id: 42,
+        amount: 1_000,
+        deadline: 2_000,
+        locked_at: 1_001,
+        ..Default::default()
+    };
+
+    assert!(matches(&escrow));
+    let settled = Escrow {
+        settled: true,
+        ..escrow
+    };
+    assert!(!matches(&settled));
+    let mut data = Vec::new();
+    settled.try_serialize(&mut data).unwrap();
+    assert_eq!(data[Escrow::SETTLED_OFFSET], 1);
+}
@@ -0,0 +1,84 @@
+use anchor_lang::Discriminator;
+use clap::ValueEnum;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use trade_escrow::state::Escrow;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EscrowStatus {
+    /// Waiting for delivery; can still settle
+    Open,
+    /// Past the deadline and not yet refunded
+    Expired,
+    /// Settled or refunded; the account does not record which
+    Closed,
+}
+
+impl EscrowStatus {
+    pub fn of(escrow: &Escrow, now: i64) -> Self {
+        if escrow.settled {
+            EscrowStatus::Closed
+        } else if now > escrow.deadline {
+            EscrowStatus::Expired
+        } else {
+            EscrowStatus::Open
+        }
+    }
+}
+
+/// Which escrows `list-escrows` shows
+#[derive(Clone, Debug, Default)]
+pub struct EscrowFilter {
+    pub buyer: Option<Pubkey>,
+    pub seller: Option<Pubkey>,
+    pub status: Option<EscrowStatus>,
+}
+
+impl EscrowFilter {
+    /// `getProgramAccounts` config narrowing the scan on the node.
+    ///
+    /// The deadline depends on cluster time, so open and expired escrows
+    /// are told apart afterwards with [`EscrowFilter::matches`].
+    pub fn rpc_config(&self) -> RpcProgramAccountsConfig {
+        let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            &Escrow::DISCRIMINATOR,
+        ))];
+        if let Some(buyer) = self.buyer {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                Escrow::BUYER_OFFSET,
+                buyer.as_ref(),
+            )));
+        }
+        if let Some(seller) = self.seller {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                Escrow::SELLER_OFFSET,
+                seller.as_ref(),
+            )));
+        }
+        if let Some(status) = self.status {
+            let settled = status == EscrowStatus::Closed;
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                Escrow::SETTLED_OFFSET,
+                &[settled as u8],
+            )));
+        }
+
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        }
+    }
+
+    pub fn matches(&self, escrow: &Escrow, now: i64) -> bool {
+        (self.buyer.is_none() || self.buyer == Some(escrow.buyer))
+            && (self.seller.is_none() || self.seller == Some(escrow.seller))
+            && (self.status.is_none() || self.status == Some(EscrowStatus::of(escrow, now)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,5 @@
+//! Building blocks of the `trade-escrow-cli` admin tool: escrow queries
+//! and transaction encoding for dry runs and offline signing.
+
+pub mod escrows;
+pub mod transaction;
\ No newline at end of file
@@ -0,0 +1,348 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::from_account;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::sysvar;
+use std::error::Error;
+use std::path::PathBuf;
+use trade_escrow::state::{Config, Escrow, FeePayer};
+use trade_escrow_cli::escrows::{EscrowFilter, EscrowStatus};
+use trade_escrow_cli::transaction;
+use trade_escrow_client::{accounts, instructions, pda, PROGRAM_ID};
+
+/// Manage the trade-escrow program's configuration and inspect escrows
+#[derive(Parser)]
+#[command(name = "trade-escrow-cli", version)]
+struct Cli {
+    /// JSON-RPC endpoint of the cluster
+    #[arg(long, global = true, default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Keypair of the admin or guardian [default: ~/.config/solana/id.json]
+    #[arg(long, global = true)]
+    keypair: Option<PathBuf>,
+
+    /// Keypair paying transaction fees [default: --keypair]
+    #[arg(long, global = true)]
+    fee_payer: Option<PathBuf>,
+
+    /// Print the unsigned transaction instead of sending it
+    #[arg(long, global = true, conflicts_with = "sign_only")]
+    dry_run: bool,
+
+    /// Sign without contacting the cluster and print the transaction for `send`
+    #[arg(long, global = true, requires = "blockhash")]
+    sign_only: bool,
+
+    /// Recent blockhash to use instead of fetching one
+    #[arg(long, global = true)]
+    blockhash: Option<Hash>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the program config with the keypair as admin
+    Init {
+        #[arg(long)]
+        guardian: Pubkey,
+        #[arg(long)]
+        fee_recipient: Pubkey,
+        #[arg(long, num_args = 3, required = true)]
+        oracles: Vec<Pubkey>,
+    },
+    /// Print the program config
+    ShowConfig,
+    /// Halt trading (guardian)
+    Pause,
+    /// Resume trading (guardian)
+    Unpause,
+    /// Replace the three oracle keys (admin)
+    SetOracles {
+        #[arg(num_args = 3, required = true)]
+        oracles: Vec<Pubkey>,
+    },
+    /// Change the protocol fee (admin); unset options keep their current value
+    SetFee {
+        #[arg(long)]
+        fee_bps: u16,
+        #[arg(long)]
+        referral_share_bps: Option<u16>,
+        #[arg(long, value_enum)]
+        fee_payer: Option<FeePayerArg>,
+    },
+    /// Print one escrow
+    InspectEscrow { address: Pubkey },
+    /// Print escrows matching the filters
+    ListEscrows {
+        #[arg(long)]
+        buyer: Option<Pubkey>,
+        #[arg(long)]
+        seller: Option<Pubkey>,
+        #[arg(long, value_enum)]
+        status: Option<EscrowStatus>,
+    },
+    /// Submit a transaction produced with --sign-only
+    Send { transaction: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FeePayerArg {
+    Buyer,
+    Seller,
+    Split,
+}
+
+impl From<FeePayerArg> for FeePayer {
+    fn from(arg: FeePayerArg) -> Self {
+        match arg {
+            FeePayerArg::Buyer => FeePayer::Buyer,
+            FeePayerArg::Seller => FeePayer::Seller,
+            FeePayerArg::Split => FeePayer::Split,
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+
+    match &cli.command {
+        Command::Init {
+            guardian,
+            fee_recipient,
+            oracles,
+        } => {
+            let admin = load_keypair(&cli.keypair)?;
+            let ix = instructions::initialize(
+                &admin.pubkey(),
+                guardian,
+                fee_recipient,
+                oracle_keys(oracles),
+            );
+            execute(&cli, &rpc, &admin, ix)
+        }
+        Command::ShowConfig => {
+            print_config(&fetch_config(&rpc)?);
+            Ok(())
+        }
+        Command::Pause => {
+            let guardian = load_keypair(&cli.keypair)?;
+            execute(
+                &cli,
+                &rpc,
+                &guardian,
+                instructions::pause(&guardian.pubkey()),
+            )
+        }
+        Command::Unpause => {
+            let guardian = load_keypair(&cli.keypair)?;
+            execute(
+                &cli,
+                &rpc,
+                &guardian,
+                instructions::unpause(&guardian.pubkey()),
+            )
+        }
+        Command::SetOracles { oracles } => {
+            let admin = load_keypair(&cli.keypair)?;
+            let ix = instructions::update_oracles(&admin.pubkey(), oracle_keys(oracles));
+            execute(&cli, &rpc, &admin, ix)
+        }
+        Command::SetFee {
+            fee_bps,
+            referral_share_bps,
+            fee_payer,
+        } => {
+            let admin = load_keypair(&cli.keypair)?;
+            let (referral_share_bps, fee_payer) = match (referral_share_bps, fee_payer) {
+                (Some(share), Some(payer)) => (*share, FeePayer::from(*payer)),
+                _ if cli.sign_only => {
+                    return Err(
+                        "--referral-share-bps and --fee-payer are required with --sign-only".into(),
+                    )
+                }
+                _ => {
+                    let config = fetch_config(&rpc)?;
+                    (
+                        referral_share_bps.unwrap_or(config.referral_share_bps),
+                        fee_payer.map_or(config.fee_payer, FeePayer::from),
+                    )
+                }
+            };
+            let ix =
+                instructions::update_fees(&admin.pubkey(), *fee_bps, referral_share_bps, fee_payer);
+            execute(&cli, &rpc, &admin, ix)
+        }
+        Command::InspectEscrow { address } => {
+            let account = rpc.get_account(address)?;
+            let escrow = accounts::escrow(&account.data)?;
+            print_escrow(address, &escrow, cluster_time(&rpc)?);
+            Ok(())
+        }
+        Command::ListEscrows {
+            buyer,
+            seller,
+            status,
+        } => {
+            let filter = EscrowFilter {
+                buyer: *buyer,
+                seller: *seller,
+                status: *status,
+            };
+            let now = cluster_time(&rpc)?;
+            let mut escrows = vec![];
+            for (address, account) in
+                rpc.get_program_accounts_with_config(&PROGRAM_ID, filter.rpc_config())?
+            {
+                let escrow = accounts::escrow(&account.data)?;
+                if filter.matches(&escrow, now) {
+                    escrows.push((address, escrow));
+                }
+            }
+            escrows.sort_by_key(|(_, escrow)| escrow.deadline);
+            for (address, escrow) in escrows.iter() {
+                print_escrow(address, escrow, now);
+                println!();
+            }
+            println!("{} escrow(s)", escrows.len());
+            Ok(())
+        }
+        Command::Send { transaction } => {
+            let transaction = transaction::decode(transaction)?;
+            println!("{}", rpc.send_and_confirm_transaction(&transaction)?);
+            Ok(())
+        }
+    }
+}
+
+/// Dry-run, sign offline, or send a single-instruction transaction
+fn execute(
+    cli: &Cli,
+    rpc: &RpcClient,
+    authority: &Keypair,
+    ix: Instruction,
+) -> Result<(), Box<dyn Error>> {
+    let fee_payer = match &cli.fee_payer {
+        Some(path) => Some(load_keypair(&Some(path.clone()))?),
+        None => None,
+    };
+    let payer = fee_payer.as_ref().unwrap_or(authority);
+    let blockhash = match cli.blockhash {
+        Some(blockhash) => blockhash,
+        None => rpc.get_latest_blockhash()?,
+    };
+
+    let mut tx = transaction::unsigned(&[ix], &payer.pubkey(), blockhash);
+    if cli.dry_run {
+        print!("{}", transaction::describe(&tx));
+        println!("{}", transaction::encode(&tx));
+        return Ok(());
+    }
+
+    let mut signers = vec![payer];
+    if authority.pubkey() != payer.pubkey() {
+        signers.push(authority);
+    }
+    tx.try_sign(&signers, blockhash)?;
+
+    if cli.sign_only {
+        println!("{}", transaction::encode(&tx));
+    } else {
+        println!("{}", rpc.send_and_confirm_transaction(&tx)?);
+    }
+    Ok(())
+}
+
+fn load_keypair(path: &Option<PathBuf>) -> Result<Keypair, Box<dyn Error>> {
+    let path = match path {
+        Some(path) => path.clone(),
+        None => PathBuf::from(std::env::var("HOME")?).join(".config/solana/id.json"),
+    };
+    read_keypair_file(&path)
+        .map_err(|e| format!("failed to read keypair {}: {}", path.display(), e).into())
+}
+
+fn oracle_keys(oracles: &[Pubkey]) -> [Pubkey; 3] {
+    [oracles[0], oracles[1], oracles[2]]
+}
+
+fn fetch_config(rpc: &RpcClient) -> Result<Config, Box<dyn Error>> {
+    let account = rpc.get_account(&pda::config())?;
+    Ok(accounts::config(&account.data)?)
+}
+
+fn cluster_time(rpc: &RpcClient) -> Result<i64, Box<dyn Error>> {
+    let account = rpc.get_account(&sysvar::clock::id())?;
+    let clock: Clock = from_account(&account).ok_or("failed to decode clock sysvar")?;
+    Ok(clock.unix_timestamp)
+}
+
+fn print_config(config: &Config) {
+    println!("config: {}", pda::config());
+    println!("paused: {}", config.paused);
+    println!("admin: {}", config.admin);
+    println!("guardian: {}", config.guardian);
+    for (index, oracle) in config.oracle_pubkeys.iter().enumerate() {
+        println!("oracle {}: {}", index, oracle);
+    }
+    println!("fee recipient: {}", config.fee_recipient);
+    println!(
+        "fee: {} bps, paid by {:?}",
+        config.fee_bps, config.fee_payer
+    );
+    println!("referral share: {} bps", config.referral_share_bps);
+    for tier in config.price_tiers.iter().filter(|tier| tier.min_amount > 0) {
+        println!("  from {}: {} bps", tier.min_amount, tier.fee_bps);
+    }
+    for tier in config
+        .volume_tiers
+        .iter()
+        .filter(|tier| tier.min_volume > 0)
+    {
+        println!(
+            "  30d volume from {}: -{} bps",
+            tier.min_volume, tier.discount_bps
+        );
+    }
+    println!("max tvl: {}", config.max_tvl);
+    println!("max trade amount: {}", config.max_trade_amount);
+    println!(
+        "per-user limit: {} every {}s",
+        config.user_limit_amount, config.user_limit_window
+    );
+    println!("price publisher: {}", config.price_publisher);
+    println!("price band: {} bps", config.price_band_bps);
+    println!("max price staleness: {}s", config.max_price_staleness);
+    println!("max price confidence: {} bps", config.max_confidence_bps);
+    println!("max reference price age: {}s", config.max_reference_age);
+    println!("total locked: {}", config.total_locked);
+}
+
+fn print_escrow(address: &Pubkey, escrow: &Escrow, now: i64) {
+    println!("escrow: {}", address);
+    println!("status: {:?}", EscrowStatus::of(escrow, now));
+    println!("buyer: {}", escrow.buyer);
+    println!("seller: {}", escrow.seller);
+    println!("asset id: {}", escrow.asset_id);
+    println!("amount: {}", escrow.amount);
+    println!(
+        "fee: {} ({} bps, paid by {:?})",
+        escrow.fee_amount, escrow.fee_bps, escrow.fee_payer
+    );
+    if let Some(referrer) = escrow.referrer {
+        println!("referrer: {}", referrer);
+    }
+    if let Some(usd_rate) = escrow.usd_rate {
+        println!("usd rate: {}", usd_rate);
+    }
+    println!("deadline: {}", escrow.deadline);
+    println!("nonce: {}", escrow.nonce);
+}
\ No newline at end of file
@@ -0,0 +1,60 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use std::error::Error;
+use std::fmt::Write;
+
+/// Unsigned transaction paying fees from `payer`
+pub fn unsigned(instructions: &[Instruction], payer: &Pubkey, blockhash: Hash) -> Transaction {
+    let mut transaction = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    transaction.message.recent_blockhash = blockhash;
+    transaction
+}
+
+/// Wire format, base64 encoded, as accepted by `sendTransaction`
+pub fn encode(transaction: &Transaction) -> String {
+    STANDARD.encode(bincode::serialize(transaction).expect("transactions serialize"))
+}
+
+pub fn decode(encoded: &str) -> Result<Transaction, Box<dyn Error>> {
+    Ok(bincode::deserialize(&STANDARD.decode(encoded.trim())?)?)
+}
+
+/// Human-readable listing of a transaction's instructions
+pub fn describe(transaction: &Transaction) -> String {
+    let message = &transaction.message;
+    let mut out = String::new();
+    let _ = writeln!(out, "fee payer: {}", message.account_keys[0]);
+    let _ = writeln!(out, "blockhash: {}", message.recent_blockhash);
+    for (index, instruction) in message.instructions.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "instruction {}: program {}",
+            index, message.account_keys[instruction.program_id_index as usize]
+        );
+        for &account in instruction.accounts.iter() {
+            let account = account as usize;
+            let _ = writeln!(
+                out,
+                "  {}{}{}",
+                message.account_keys[account],
+                if message.is_signer(account) {
+                    " signer"
+                } else {
+                    ""
+                },
+                if message.is_writable(account) {
+                    " writable"
+                } else {
+                    ""
+                },
+            );
+        }
+        let _ = writeln!(out, "  data: {}", STANDARD.encode(&instruction.data));
+    }
+    out
+}
\ No newline at end of file
@@ -0,0 +1,169 @@
+use anchor_lang::AccountSerialize;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{write_keypair_file, Keypair, Signer};
+use std::process::Command;
+use trade_escrow::state::Escrow;
+use trade_escrow_cli::escrows::{EscrowFilter, EscrowStatus};
+use trade_escrow_cli::transaction;
+use trade_escrow_client::instructions;
+
+fn cli(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_trade-escrow-cli"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn signs_offline_without_a_cluster() {
+    let dir = tempfile::tempdir().unwrap();
+    let guardian = Keypair::new();
+    let payer = Keypair::new();
+    let guardian_path = dir.path().join("guardian.json");
+    let payer_path = dir.path().join("payer.json");
+    write_keypair_file(&guardian, &guardian_path).unwrap();
+    write_keypair_file(&payer, &payer_path).unwrap();
+    let blockhash = Hash::new_unique();
+
+    // Nothing listens on this port, so any RPC call would fail the command
+    let output = cli(&[
+        "pause",
+        "--url",
+        "http://127.0.0.1:1",
+        "--keypair",
+        guardian_path.to_str().unwrap(),
+        "--fee-payer",
+        payer_path.to_str().unwrap(),
+        "--sign-only",
+        "--blockhash",
+        &blockhash.to_string(),
+    ]);
+
+    let tx = transaction::decode(&output).unwrap();
+    tx.verify().unwrap();
+    assert_eq!(tx.message.recent_blockhash, blockhash);
+    assert_eq!(tx.message.account_keys[0], payer.pubkey());
+    assert!(tx.message.account_keys.contains(&guardian.pubkey()));
+    assert_eq!(
+        tx.message.instructions[0].data,
+        instructions::pause(&guardian.pubkey()).data
+    );
+}
+
+#[test]
+fn dry_run_prints_unsigned_transaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let admin = Keypair::new();
+    let admin_path = dir.path().join("admin.json");
+    write_keypair_file(&admin, &admin_path).unwrap();
+    let oracles: Vec<String> = (0..3).map(|_| Pubkey::new_unique().to_string()).collect();
+
+    let output = cli(&[
+        "set-oracles",
+        &oracles[0],
+        &oracles[1],
+        &oracles[2],
+        "--keypair",
+        admin_path.to_str().unwrap(),
+        "--dry-run",
+        "--blockhash",
+        &Hash::new_unique().to_string(),
+    ]);
+
+    assert!(output.contains(&format!("fee payer: {}", admin.pubkey())));
+    let tx = transaction::decode(output.lines().last().unwrap()).unwrap();
+    assert!(tx
+        .signatures
+        .iter()
+        .all(|signature| *signature == Default::default()));
+    assert!(tx.verify().is_err());
+}
+
+#[test]
+fn filters_escrows_by_party_and_status() {
+    let buyer = Pubkey::new_unique();
+    let escrow = Escrow {
+        buyer,
+        seller: Pubkey::new_unique(),
+        deadline: 100,
+        ..Default::default()
+    };
+    assert_eq!(EscrowStatus::of(&escrow, 100), EscrowStatus::Open);
+    assert_eq!(EscrowStatus::of(&escrow, 101), EscrowStatus::Expired);
+
+    let filter = EscrowFilter {
+        buyer: Some(buyer),
+        status: Some(EscrowStatus::Expired),
+        ..Default::default()
+    };
+    assert!(filter.matches(&escrow, 101));
+    assert!(!filter.matches(&escrow, 100));
+    assert!(!EscrowFilter {
+        seller: Some(buyer),
+        ..Default::default()
+    }
+    .matches(&escrow, 100));
+    assert_eq!(filter.rpc_config().filters.unwrap().len(), 3);
+}
+
+#[test]
+fn rpc_filters_read_the_escrow_fields() {
+    let buyer = Pubkey::new_unique();
+    let seller = Pubkey::new_unique();
+    // A lock time whose low byte is not zero, so only `settled` decides the status
+    let escrow = Escrow {
+        buyer,
+        seller,
+        asset_id: 42,
+        amount: 1_000,
+        deadline: 2_000,
+        locked_at: 1_001,
+        ..Default::default()
+    };
+    let settled = Escrow {
+        settled: true,
+        ..escrow.clone()
+    };
+    let data = |escrow: &Escrow| {
+        let mut data = Vec::new();
+        escrow.try_serialize(&mut data).unwrap();
+        data
+    };
+    let matches = |filter: EscrowFilter, escrow: &Escrow| {
+        let data = data(escrow);
+        filter.rpc_config().filters.unwrap().iter().all(|filter| match filter {
+            RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&data),
+            _ => true,
+        })
+    };
+    assert_eq!(data(&settled)[Escrow::SETTLED_OFFSET], 1);
+
+    let filter = EscrowFilter {
+        buyer: Some(buyer),
+        seller: Some(seller),
+        status: Some(EscrowStatus::Open),
+    };
+    assert!(matches(filter.clone(), &escrow));
+    assert!(!matches(filter, &settled));
+    let closed = EscrowFilter {
+        status: Some(EscrowStatus::Closed),
+        ..Default::default()
+    };
+    assert!(!matches(closed.clone(), &escrow));
+    assert!(matches(closed, &settled));
+    assert!(!matches(
+        EscrowFilter {
+            buyer: Some(seller),
+            ..Default::default()
+        },
+        &escrow
+    ));
+}
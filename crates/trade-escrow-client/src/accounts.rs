@@ -0,0 +1,60 @@
+//! Decoding of program accounts from raw account data.
+
+use anchor_lang::{AccountDeserialize, Result};
+use trade_escrow::state::{
+    Auction, BuyOrder, CommodityEscrow, Config, Escrow, FeeVault, Listing, PriceFeed,
+    PriceReference, ReceiptTree, SwapEscrow, UserStats,
+};
+
+/// Decode any program account, checking its discriminator
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn config(data: &[u8]) -> Result<Config> {
+    decode(data)
+}
+
+pub fn escrow(data: &[u8]) -> Result<Escrow> {
+    decode(data)
+}
+
+pub fn commodity_escrow(data: &[u8]) -> Result<CommodityEscrow> {
+    decode(data)
+}
+
+pub fn user_stats(data: &[u8]) -> Result<UserStats> {
+    decode(data)
+}
+
+pub fn fee_vault(data: &[u8]) -> Result<FeeVault> {
+    decode(data)
+}
+
+pub fn listing(data: &[u8]) -> Result<Listing> {
+    decode(data)
+}
+
+pub fn buy_order(data: &[u8]) -> Result<BuyOrder> {
+    decode(data)
+}
+
+pub fn auction(data: &[u8]) -> Result<Auction> {
+    decode(data)
+}
+
+pub fn swap(data: &[u8]) -> Result<SwapEscrow> {
+    decode(data)
+}
+
+pub fn price_reference(data: &[u8]) -> Result<PriceReference> {
+    decode(data)
+}
+
+pub fn price_feed(data: &[u8]) -> Result<PriceFeed> {
+    decode(data)
+}
+
+pub fn receipt_tree(data: &[u8]) -> Result<ReceiptTree> {
+    decode(data)
+}
\ No newline at end of file
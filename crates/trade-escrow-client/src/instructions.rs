@@ -0,0 +1,929 @@
+//! Typed builders for every program instruction.
+//!
+//! Builders derive every PDA themselves; callers pass wallets, mints and
+//! token accounts. Escrows opened by `lock`, `buy_listing`, `fill_buy_order`,
+//! `finalize_auction` and `lock_commodity` are seeded by the cluster time the
+//! instruction executes at, which the caller passes as `nonce`.
+//!
+//! The program only accepts the ask and oracle signatures passed to `lock`,
+//! `lock_commodity` and the settle and refund builders when an earlier
+//! instruction of the same transaction verifies them; see
+//! [`crate::messages::ed25519_verify_instruction`].
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use trade_escrow::state::{
+    Auction, BuyOrder, CommodityEscrow, DutchPricing, Escrow, FeePayer, ItemClass, Listing,
+    PriceTier, SwapEscrow, VolumeTier, WearBounds, FEE_TIER_COUNT,
+};
+use trade_escrow::{accounts, instruction};
+
+use crate::messages::AskPrice;
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: trade_escrow::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize(
+    admin: &Pubkey,
+    guardian: &Pubkey,
+    fee_recipient: &Pubkey,
+    oracle_pubkeys: [Pubkey; 3],
+) -> Instruction {
+    build(
+        accounts::Initialize {
+            config: pda::config(),
+            admin: *admin,
+            guardian: *guardian,
+            fee_recipient: *fee_recipient,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::Initialize { oracle_pubkeys },
+    )
+}
+
+/// Accounts for `lock` and `lock_usd`
+#[derive(Clone, Debug, Default)]
+pub struct LockAccounts {
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub buyer_token_account: Pubkey,
+    pub referrer: Option<Pubkey>,
+}
+
+/// Terms of a signed ask being locked
+#[derive(Clone, Debug)]
+pub struct LockArgs {
+    pub asset_id: u64,
+    /// Class of the item, whose reference price the amount is checked against
+    pub item_class: ItemClass,
+    pub price: AskPrice,
+    pub price_max: u64,
+    pub ask_signature: [u8; 64],
+    pub deadline_offset: i64,
+    pub fee_payer_override: Option<FeePayer>,
+    pub allowed_buyer: Option<Pubkey>,
+    pub price_override: bool,
+}
+
+/// `lock` for token-priced asks, `lock_usd` (with the mint's price feed) for USD asks
+pub fn lock(accounts: &LockAccounts, args: &LockArgs, nonce: u64) -> Instruction {
+    let escrow = pda::escrow(&accounts.buyer, &accounts.seller, args.asset_id, nonce);
+    let price_feed = match args.price {
+        AskPrice::Tokens(_) => None,
+        AskPrice::Usd(_) => Some(pda::price_feed(&accounts.mint)),
+    };
+    let metas = accounts::Lock {
+        escrow,
+        config: pda::config(),
+        user_stats: pda::user_stats(&accounts.buyer),
+        buyer: accounts.buyer,
+        seller: accounts.seller,
+        referrer: accounts.referrer,
+        mint: accounts.mint,
+        fee_vault: pda::fee_vault(&accounts.mint),
+        price_reference: pda::price_reference(&accounts.mint, &args.item_class),
+        price_feed,
+        buyer_token_account: accounts.buyer_token_account,
+        escrow_token_account: pda::escrow_vault(&escrow),
+        instructions_sysvar: sysvar::instructions::ID,
+        token_program: token::ID,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+
+    match args.price {
+        AskPrice::Tokens(amount) => build(
+            metas,
+            instruction::Lock {
+                asset_id: args.asset_id,
+                item_class: args.item_class,
+                amount,
+                price_max: args.price_max,
+                ask_signature: args.ask_signature,
+                deadline_offset: args.deadline_offset,
+                fee_payer_override: args.fee_payer_override,
+                allowed_buyer: args.allowed_buyer,
+                price_override: args.price_override,
+            },
+        ),
+        AskPrice::Usd(usd_amount) => build(
+            metas,
+            instruction::LockUsd {
+                asset_id: args.asset_id,
+                item_class: args.item_class,
+                usd_amount,
+                price_max: args.price_max,
+                ask_signature: args.ask_signature,
+                deadline_offset: args.deadline_offset,
+                fee_payer_override: args.fee_payer_override,
+                allowed_buyer: args.allowed_buyer,
+                price_override: args.price_override,
+            },
+        ),
+    }
+}
+
+/// Settle an escrow; the referrer's token account is required when it has a referrer
+pub fn settle(
+    escrow_key: &Pubkey,
+    escrow: &Escrow,
+    mint: &Pubkey,
+    seller_token_account: &Pubkey,
+    referrer_token_account: Option<Pubkey>,
+    oracle_signatures: Vec<[u8; 64]>,
+) -> Instruction {
+    build(
+        accounts::Settle {
+            escrow: *escrow_key,
+            config: pda::config(),
+            escrow_token_account: pda::escrow_vault(escrow_key),
+            seller_token_account: *seller_token_account,
+            fee_vault: pda::fee_vault(mint),
+            fee_vault_token_account: pda::fee_vault_tokens(mint),
+            referrer_token_account,
+            user_stats: pda::user_stats(&escrow.buyer),
+            receipt_tree: pda::receipt_tree(mint),
+            instructions_sysvar: sysvar::instructions::ID,
+            token_program: token::ID,
+        },
+        instruction::Settle { oracle_signatures },
+    )
+}
+
+pub fn refund(
+    escrow_key: &Pubkey,
+    escrow: &Escrow,
+    mint: &Pubkey,
+    buyer_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::Refund {
+            escrow: *escrow_key,
+            config: pda::config(),
+            buyer: escrow.buyer,
+            escrow_token_account: pda::escrow_vault(escrow_key),
+            buyer_token_account: *buyer_token_account,
+            user_stats: pda::user_stats(&escrow.buyer),
+            receipt_tree: pda::receipt_tree(mint),
+            token_program: token::ID,
+        },
+        instruction::Refund {},
+    )
+}
+
+fn create_listing_accounts(seller: &Pubkey, mint: &Pubkey, listing_id: u64) -> accounts::CreateListing {
+    accounts::CreateListing {
+        listing: pda::listing(seller, listing_id),
+        config: pda::config(),
+        seller: *seller,
+        mint: *mint,
+        system_program: system_program::ID,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_listing(
+    seller: &Pubkey,
+    mint: &Pubkey,
+    listing_id: u64,
+    asset_id: u64,
+    price: u64,
+    expiry: i64,
+    allowed_buyer: Option<Pubkey>,
+    fee_payer: Option<FeePayer>,
+) -> Instruction {
+    build(
+        create_listing_accounts(seller, mint, listing_id),
+        instruction::CreateListing {
+            listing_id,
+            asset_id,
+            price,
+            expiry,
+            allowed_buyer,
+            fee_payer,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_dutch_listing(
+    seller: &Pubkey,
+    mint: &Pubkey,
+    listing_id: u64,
+    asset_id: u64,
+    pricing: DutchPricing,
+    expiry: i64,
+    allowed_buyer: Option<Pubkey>,
+    fee_payer: Option<FeePayer>,
+) -> Instruction {
+    build(
+        create_listing_accounts(seller, mint, listing_id),
+        instruction::CreateDutchListing {
+            listing_id,
+            asset_id,
+            pricing,
+            expiry,
+            allowed_buyer,
+            fee_payer,
+        },
+    )
+}
+
+pub fn update_listing_price(listing_key: &Pubkey, listing: &Listing, new_price: u64) -> Instruction {
+    build(
+        accounts::UpdateListingPrice {
+            listing: *listing_key,
+            seller: listing.seller,
+        },
+        instruction::UpdateListingPrice { new_price },
+    )
+}
+
+pub fn cancel_listing(listing_key: &Pubkey, listing: &Listing) -> Instruction {
+    build(
+        accounts::CancelListing {
+            listing: *listing_key,
+            seller: listing.seller,
+        },
+        instruction::CancelListing {},
+    )
+}
+
+pub fn close_listing(listing_key: &Pubkey, listing: &Listing) -> Instruction {
+    build(
+        accounts::CloseListing {
+            listing: *listing_key,
+            seller: listing.seller,
+        },
+        instruction::CloseListing {},
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn buy_listing(
+    listing_key: &Pubkey,
+    listing: &Listing,
+    buyer: &Pubkey,
+    buyer_token_account: &Pubkey,
+    referrer: Option<Pubkey>,
+    price_max: u64,
+    deadline_offset: i64,
+    nonce: u64,
+) -> Instruction {
+    let escrow = pda::escrow(buyer, &listing.seller, listing.asset_id, nonce);
+    build(
+        accounts::BuyListing {
+            listing: *listing_key,
+            escrow,
+            config: pda::config(),
+            user_stats: pda::user_stats(buyer),
+            buyer: *buyer,
+            referrer,
+            mint: listing.mint,
+            fee_vault: pda::fee_vault(&listing.mint),
+            buyer_token_account: *buyer_token_account,
+            escrow_token_account: pda::escrow_vault(&escrow),
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::BuyListing {
+            price_max,
+            deadline_offset,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_buy_order(
+    buyer: &Pubkey,
+    mint: &Pubkey,
+    buyer_token_account: &Pubkey,
+    order_id: u64,
+    item_class: ItemClass,
+    wear_bounds: Option<WearBounds>,
+    unit_price: u64,
+    quantity: u32,
+    expiry: i64,
+    fee_payer: Option<FeePayer>,
+) -> Instruction {
+    let buy_order = pda::buy_order(buyer, order_id);
+    build(
+        accounts::CreateBuyOrder {
+            buy_order,
+            buy_order_vault: pda::buy_order_vault(&buy_order),
+            config: pda::config(),
+            user_stats: pda::user_stats(buyer),
+            buyer: *buyer,
+            mint: *mint,
+            fee_vault: pda::fee_vault(mint),
+            buyer_token_account: *buyer_token_account,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateBuyOrder {
+            order_id,
+            item_class,
+            wear_bounds,
+            unit_price,
+            quantity,
+            expiry,
+            fee_payer,
+        },
+    )
+}
+
+pub fn fill_buy_order(
+    buy_order_key: &Pubkey,
+    buy_order: &BuyOrder,
+    seller: &Pubkey,
+    asset_id: u64,
+    wear: Option<u32>,
+    deadline_offset: i64,
+    nonce: u64,
+) -> Instruction {
+    let escrow = pda::escrow(&buy_order.buyer, seller, asset_id, nonce);
+    build(
+        accounts::FillBuyOrder {
+            buy_order: *buy_order_key,
+            buy_order_vault: pda::buy_order_vault(buy_order_key),
+            escrow,
+            config: pda::config(),
+            seller: *seller,
+            mint: buy_order.mint,
+            escrow_token_account: pda::escrow_vault(&escrow),
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::FillBuyOrder {
+            asset_id,
+            wear,
+            deadline_offset,
+        },
+    )
+}
+
+pub fn cancel_buy_order(
+    buy_order_key: &Pubkey,
+    buy_order: &BuyOrder,
+    buyer_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CancelBuyOrder {
+            buy_order: *buy_order_key,
+            buy_order_vault: pda::buy_order_vault(buy_order_key),
+            config: pda::config(),
+            user_stats: pda::user_stats(&buy_order.buyer),
+            buyer: buy_order.buyer,
+            buyer_token_account: *buyer_token_account,
+            token_program: token::ID,
+        },
+        instruction::CancelBuyOrder {},
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_auction(
+    seller: &Pubkey,
+    mint: &Pubkey,
+    auction_id: u64,
+    asset_id: u64,
+    reserve_price: u64,
+    min_increment: u64,
+    end_time: i64,
+    extension_window: i64,
+    delivery_window: i64,
+    fee_payer: Option<FeePayer>,
+) -> Instruction {
+    let auction = pda::auction(seller, auction_id);
+    build(
+        accounts::CreateAuction {
+            auction,
+            auction_vault: pda::auction_vault(&auction),
+            config: pda::config(),
+            seller: *seller,
+            mint: *mint,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateAuction {
+            auction_id,
+            asset_id,
+            reserve_price,
+            min_increment,
+            end_time,
+            extension_window,
+            delivery_window,
+            fee_payer,
+        },
+    )
+}
+
+/// Bid on an auction; the outbid bidder's token account is required once there is a high bid
+pub fn place_bid(
+    auction_key: &Pubkey,
+    auction: &Auction,
+    bidder: &Pubkey,
+    bidder_token_account: &Pubkey,
+    previous_bidder_token_account: Option<Pubkey>,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::PlaceBid {
+            auction: *auction_key,
+            auction_vault: pda::auction_vault(auction_key),
+            config: pda::config(),
+            user_stats: pda::user_stats(bidder),
+            bidder: *bidder,
+            fee_vault: pda::fee_vault(&auction.mint),
+            bidder_token_account: *bidder_token_account,
+            previous_bidder_token_account,
+            previous_bidder_stats: auction
+                .highest_bidder
+                .filter(|previous_bidder| previous_bidder != bidder)
+                .map(|previous_bidder| pda::user_stats(&previous_bidder)),
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::PlaceBid { amount },
+    )
+}
+
+/// Finalize an auction with a winning bid, opening the winner's escrow
+pub fn finalize_auction(
+    auction_key: &Pubkey,
+    auction: &Auction,
+    winner: &Pubkey,
+    payer: &Pubkey,
+    nonce: u64,
+) -> Instruction {
+    let escrow = pda::escrow(winner, &auction.seller, auction.asset_id, nonce);
+    build(
+        accounts::FinalizeAuction {
+            auction: *auction_key,
+            auction_vault: pda::auction_vault(auction_key),
+            config: pda::config(),
+            escrow,
+            winner: *winner,
+            seller: auction.seller,
+            mint: auction.mint,
+            escrow_token_account: pda::escrow_vault(&escrow),
+            payer: *payer,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::FinalizeAuction {},
+    )
+}
+
+pub fn cancel_auction(auction_key: &Pubkey, auction: &Auction) -> Instruction {
+    build(
+        accounts::CancelAuction {
+            auction: *auction_key,
+            auction_vault: pda::auction_vault(auction_key),
+            seller: auction.seller,
+            token_program: token::ID,
+        },
+        instruction::CancelAuction {},
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn propose_swap(
+    maker: &Pubkey,
+    taker: &Pubkey,
+    mint: &Pubkey,
+    maker_token_account: &Pubkey,
+    swap_id: u64,
+    maker_items: Vec<u64>,
+    taker_items: Vec<u64>,
+    maker_cash: u64,
+    taker_cash: u64,
+    expiry: i64,
+    delivery_window: i64,
+) -> Instruction {
+    let swap = pda::swap(maker, swap_id);
+    build(
+        accounts::ProposeSwap {
+            swap,
+            swap_vault: pda::swap_vault(&swap),
+            config: pda::config(),
+            maker_stats: pda::user_stats(maker),
+            maker: *maker,
+            taker: *taker,
+            mint: *mint,
+            maker_token_account: *maker_token_account,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::ProposeSwap {
+            swap_id,
+            maker_items,
+            taker_items,
+            maker_cash,
+            taker_cash,
+            expiry,
+            delivery_window,
+        },
+    )
+}
+
+pub fn accept_swap(swap_key: &Pubkey, swap: &SwapEscrow, taker_token_account: &Pubkey) -> Instruction {
+    build(
+        accounts::AcceptSwap {
+            swap: *swap_key,
+            swap_vault: pda::swap_vault(swap_key),
+            config: pda::config(),
+            taker_stats: pda::user_stats(&swap.taker),
+            taker: swap.taker,
+            taker_token_account: *taker_token_account,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::AcceptSwap {},
+    )
+}
+
+fn complete_swap_accounts(
+    swap_key: &Pubkey,
+    swap: &SwapEscrow,
+    maker_token_account: &Pubkey,
+    taker_token_account: &Pubkey,
+) -> accounts::CompleteSwap {
+    accounts::CompleteSwap {
+        swap: *swap_key,
+        swap_vault: pda::swap_vault(swap_key),
+        config: pda::config(),
+        maker: swap.maker,
+        maker_token_account: *maker_token_account,
+        taker_token_account: *taker_token_account,
+        receipt_tree: pda::receipt_tree(&swap.mint),
+        instructions_sysvar: sysvar::instructions::ID,
+        token_program: token::ID,
+    }
+}
+
+pub fn settle_swap(
+    swap_key: &Pubkey,
+    swap: &SwapEscrow,
+    maker_token_account: &Pubkey,
+    taker_token_account: &Pubkey,
+    oracle_signatures: Vec<[u8; 64]>,
+) -> Instruction {
+    build(
+        complete_swap_accounts(swap_key, swap, maker_token_account, taker_token_account),
+        instruction::SettleSwap { oracle_signatures },
+    )
+}
+
+/// Refund a swap; signatures are only needed before the deadline
+pub fn refund_swap(
+    swap_key: &Pubkey,
+    swap: &SwapEscrow,
+    maker_token_account: &Pubkey,
+    taker_token_account: &Pubkey,
+    oracle_signatures: Vec<[u8; 64]>,
+) -> Instruction {
+    build(
+        accounts::RefundSwap {
+            swap: *swap_key,
+            swap_vault: pda::swap_vault(swap_key),
+            config: pda::config(),
+            maker: swap.maker,
+            maker_token_account: *maker_token_account,
+            taker_token_account: *taker_token_account,
+            maker_stats: pda::user_stats(&swap.maker),
+            taker_stats: pda::user_stats(&swap.taker),
+            receipt_tree: pda::receipt_tree(&swap.mint),
+            instructions_sysvar: sysvar::instructions::ID,
+            token_program: token::ID,
+        },
+        instruction::RefundSwap { oracle_signatures },
+    )
+}
+
+pub fn cancel_swap(swap_key: &Pubkey, swap: &SwapEscrow, maker_token_account: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelSwap {
+            swap: *swap_key,
+            swap_vault: pda::swap_vault(swap_key),
+            config: pda::config(),
+            maker_stats: pda::user_stats(&swap.maker),
+            maker: swap.maker,
+            maker_token_account: *maker_token_account,
+            token_program: token::ID,
+        },
+        instruction::CancelSwap {},
+    )
+}
+
+/// Accounts for `lock_commodity`
+#[derive(Clone, Debug, Default)]
+pub struct LockCommodityAccounts {
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub buyer_token_account: Pubkey,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn lock_commodity(
+    accounts: &LockCommodityAccounts,
+    commodity: ItemClass,
+    quantity: u32,
+    unit_price: u64,
+    price_max: u64,
+    ask_signature: [u8; 64],
+    deadline_offset: i64,
+    fee_payer_override: Option<FeePayer>,
+    nonce: u64,
+) -> Instruction {
+    let escrow = pda::commodity_escrow(&accounts.buyer, &accounts.seller, nonce);
+    build(
+        accounts::LockCommodity {
+            escrow,
+            config: pda::config(),
+            user_stats: pda::user_stats(&accounts.buyer),
+            buyer: accounts.buyer,
+            seller: accounts.seller,
+            mint: accounts.mint,
+            fee_vault: pda::fee_vault(&accounts.mint),
+            buyer_token_account: accounts.buyer_token_account,
+            escrow_token_account: pda::escrow_vault(&escrow),
+            instructions_sysvar: sysvar::instructions::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::LockCommodity {
+            commodity,
+            quantity,
+            unit_price,
+            price_max,
+            ask_signature,
+            deadline_offset,
+            fee_payer_override,
+        },
+    )
+}
+
+pub fn settle_commodity(
+    escrow_key: &Pubkey,
+    escrow: &CommodityEscrow,
+    mint: &Pubkey,
+    seller_token_account: &Pubkey,
+    buyer_token_account: &Pubkey,
+    delivered: u32,
+    oracle_signatures: Vec<[u8; 64]>,
+) -> Instruction {
+    build(
+        accounts::SettleCommodity {
+            escrow: *escrow_key,
+            config: pda::config(),
+            escrow_token_account: pda::escrow_vault(escrow_key),
+            seller_token_account: *seller_token_account,
+            buyer_token_account: *buyer_token_account,
+            fee_vault: pda::fee_vault(mint),
+            fee_vault_token_account: pda::fee_vault_tokens(mint),
+            user_stats: pda::user_stats(&escrow.buyer),
+            receipt_tree: pda::receipt_tree(mint),
+            instructions_sysvar: sysvar::instructions::ID,
+            token_program: token::ID,
+        },
+        instruction::SettleCommodity {
+            delivered,
+            oracle_signatures,
+        },
+    )
+}
+
+pub fn refund_commodity(
+    escrow_key: &Pubkey,
+    escrow: &CommodityEscrow,
+    mint: &Pubkey,
+    buyer_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::RefundCommodity {
+            escrow: *escrow_key,
+            config: pda::config(),
+            buyer: escrow.buyer,
+            escrow_token_account: pda::escrow_vault(escrow_key),
+            buyer_token_account: *buyer_token_account,
+            user_stats: pda::user_stats(&escrow.buyer),
+            receipt_tree: pda::receipt_tree(mint),
+            token_program: token::ID,
+        },
+        instruction::RefundCommodity {},
+    )
+}
+
+pub fn pause(guardian: &Pubkey) -> Instruction {
+    build(
+        accounts::Pause {
+            config: pda::config(),
+            guardian: *guardian,
+        },
+        instruction::Pause {},
+    )
+}
+
+pub fn unpause(guardian: &Pubkey) -> Instruction {
+    build(
+        accounts::Unpause {
+            config: pda::config(),
+            guardian: *guardian,
+        },
+        instruction::Unpause {},
+    )
+}
+
+pub fn update_oracles(admin: &Pubkey, new_oracles: [Pubkey; 3]) -> Instruction {
+    build(
+        accounts::UpdateOracles {
+            config: pda::config(),
+            admin: *admin,
+        },
+        instruction::UpdateOracles { new_oracles },
+    )
+}
+
+pub fn update_limits(
+    admin: &Pubkey,
+    max_tvl: u64,
+    max_trade_amount: u64,
+    user_limit_amount: u64,
+    user_limit_window: i64,
+) -> Instruction {
+    build(
+        accounts::UpdateLimits {
+            config: pda::config(),
+            admin: *admin,
+        },
+        instruction::UpdateLimits {
+            max_tvl,
+            max_trade_amount,
+            user_limit_amount,
+            user_limit_window,
+        },
+    )
+}
+
+pub fn update_fees(
+    admin: &Pubkey,
+    fee_bps: u16,
+    referral_share_bps: u16,
+    fee_payer: FeePayer,
+) -> Instruction {
+    build(
+        accounts::UpdateFees {
+            config: pda::config(),
+            admin: *admin,
+        },
+        instruction::UpdateFees {
+            fee_bps,
+            referral_share_bps,
+            fee_payer,
+        },
+    )
+}
+
+pub fn update_fee_schedule(
+    admin: &Pubkey,
+    price_tiers: [PriceTier; FEE_TIER_COUNT],
+    volume_tiers: [VolumeTier; FEE_TIER_COUNT],
+) -> Instruction {
+    build(
+        accounts::UpdateFeeSchedule {
+            config: pda::config(),
+            admin: *admin,
+        },
+        instruction::UpdateFeeSchedule {
+            price_tiers,
+            volume_tiers,
+        },
+    )
+}
+
+pub fn update_price_checks(
+    admin: &Pubkey,
+    price_publisher: Pubkey,
+    price_band_bps: u16,
+    max_price_staleness: i64,
+    max_confidence_bps: u16,
+    max_reference_age: i64,
+) -> Instruction {
+    build(
+        accounts::UpdatePriceChecks {
+            config: pda::config(),
+            admin: *admin,
+        },
+        instruction::UpdatePriceChecks {
+            price_publisher,
+            price_band_bps,
+            max_price_staleness,
+            max_confidence_bps,
+            max_reference_age,
+        },
+    )
+}
+
+pub fn publish_price_feed(publisher: &Pubkey, mint: &Pubkey, price: u64, confidence: u64) -> Instruction {
+    build(
+        accounts::PublishPriceFeed {
+            price_feed: pda::price_feed(mint),
+            config: pda::config(),
+            publisher: *publisher,
+            mint: *mint,
+            system_program: system_program::ID,
+        },
+        instruction::PublishPriceFeed { price, confidence },
+    )
+}
+
+pub fn publish_reference_price(
+    publisher: &Pubkey,
+    mint: &Pubkey,
+    item_class: ItemClass,
+    price: u64,
+) -> Instruction {
+    build(
+        accounts::PublishReferencePrice {
+            price_reference: pda::price_reference(mint, &item_class),
+            config: pda::config(),
+            publisher: *publisher,
+            mint: *mint,
+            system_program: system_program::ID,
+        },
+        instruction::PublishReferencePrice { item_class, price },
+    )
+}
+
+pub fn initialize_fee_vault(admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeFeeVault {
+            fee_vault: pda::fee_vault(mint),
+            fee_vault_token_account: pda::fee_vault_tokens(mint),
+            receipt_tree: pda::receipt_tree(mint),
+            config: pda::config(),
+            admin: *admin,
+            mint: *mint,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::InitializeFeeVault {},
+    )
+}
+
+pub fn update_min_fee(admin: &Pubkey, mint: &Pubkey, min_fee: u64) -> Instruction {
+    build(
+        accounts::UpdateMinFee {
+            fee_vault: pda::fee_vault(mint),
+            config: pda::config(),
+            admin: *admin,
+        },
+        instruction::UpdateMinFee { min_fee },
+    )
+}
+
+pub fn withdraw_fees(authority: &Pubkey, mint: &Pubkey, recipient_token_account: &Pubkey) -> Instruction {
+    build(
+        accounts::WithdrawFees {
+            fee_vault: pda::fee_vault(mint),
+            fee_vault_token_account: pda::fee_vault_tokens(mint),
+            config: pda::config(),
+            authority: *authority,
+            recipient_token_account: *recipient_token_account,
+            token_program: token::ID,
+        },
+        instruction::WithdrawFees {},
+    )
+}
+
+/// View instruction; simulate it and read the return data
+pub fn get_accrued_fees(mint: &Pubkey) -> Instruction {
+    build(
+        accounts::GetAccruedFees {
+            fee_vault: pda::fee_vault(mint),
+            fee_vault_token_account: pda::fee_vault_tokens(mint),
+        },
+        instruction::GetAccruedFees {},
+    )
+}
\ No newline at end of file
@@ -0,0 +1,15 @@
+//! Rust client for the trade-escrow program.
+//!
+//! Builds instructions, derives program addresses, formats the messages
+//! sellers and oracles sign, decodes program accounts and proves trade
+//! receipts. Depends on the program with `no-entrypoint`, so it builds for
+//! any host target.
+
+pub mod accounts;
+pub mod instructions;
+pub mod messages;
+pub mod pda;
+pub mod receipts;
+
+pub use trade_escrow;
+pub use trade_escrow::ID as PROGRAM_ID;
\ No newline at end of file
@@ -0,0 +1,72 @@
+//! Signed messages and the Ed25519 payloads that carry them.
+//!
+//! Message formats come from the program itself so they cannot drift.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::Instruction;
+use trade_escrow::state::{CommodityEscrow, Escrow, SwapEscrow};
+
+pub use trade_escrow::instructions::{ask_message, commodity_ask_message, AskPrice};
+
+/// Message oracles sign to settle a single-item escrow
+pub fn settlement_message(escrow_key: &Pubkey, escrow: &Escrow) -> String {
+    escrow.settlement_message(escrow_key)
+}
+
+/// Message oracles sign to settle `delivered` items of a commodity escrow
+pub fn commodity_settlement_message(
+    escrow_key: &Pubkey,
+    escrow: &CommodityEscrow,
+    delivered: u32,
+) -> String {
+    escrow.settlement_message(escrow_key, delivered)
+}
+
+/// Message oracles sign once both sides of a swap delivered
+pub fn swap_settlement_message(swap_key: &Pubkey, swap: &SwapEscrow) -> String {
+    swap.settlement_message(swap_key)
+}
+
+/// Message oracles sign when either side of a swap failed to deliver
+pub fn swap_failure_message(swap_key: &Pubkey, swap: &SwapEscrow) -> String {
+    swap.failure_message(swap_key)
+}
+
+/// Size of the signature offsets header in Ed25519 program data
+const ED25519_OFFSETS_SIZE: usize = 14;
+/// Where the public key starts: count, padding, then one offsets header
+const ED25519_DATA_START: usize = 2 + ED25519_OFFSETS_SIZE;
+/// Instruction index meaning "this instruction"
+const ED25519_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Ed25519 program instruction verifying `signature` by `pubkey` over `message`
+pub fn ed25519_verify_instruction(pubkey: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    let public_key_offset = ED25519_DATA_START;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+
+    let mut data = Vec::with_capacity(message_offset + message.len());
+    data.push(1); // number of signatures
+    data.push(0); // padding
+    for field in [
+        signature_offset as u16,
+        ED25519_CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        ED25519_CURRENT_INSTRUCTION,
+        message_offset as u16,
+        message.len() as u16,
+        ED25519_CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
\ No newline at end of file
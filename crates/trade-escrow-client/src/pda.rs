@@ -0,0 +1,75 @@
+//! Program addresses, derived with the program's own seed helpers.
+
+use anchor_lang::prelude::Pubkey;
+use trade_escrow::state::{self, ItemClass};
+use trade_escrow::ID;
+
+pub fn config() -> Pubkey {
+    state::get_config_pda(&ID).0
+}
+
+/// Receipt tree of the trades in a mint
+pub fn receipt_tree(mint: &Pubkey) -> Pubkey {
+    state::get_receipt_tree_pda(mint, &ID).0
+}
+
+pub fn escrow(buyer: &Pubkey, seller: &Pubkey, asset_id: u64, nonce: u64) -> Pubkey {
+    state::get_escrow_pda(buyer, seller, asset_id, nonce, &ID).0
+}
+
+/// Token vault of a single-item or commodity escrow
+pub fn escrow_vault(escrow: &Pubkey) -> Pubkey {
+    state::get_escrow_vault_pda(escrow, &ID).0
+}
+
+pub fn commodity_escrow(buyer: &Pubkey, seller: &Pubkey, nonce: u64) -> Pubkey {
+    state::get_commodity_escrow_pda(buyer, seller, nonce, &ID).0
+}
+
+pub fn user_stats(user: &Pubkey) -> Pubkey {
+    state::get_user_stats_pda(user, &ID).0
+}
+
+pub fn fee_vault(mint: &Pubkey) -> Pubkey {
+    state::get_fee_vault_pda(mint, &ID).0
+}
+
+pub fn fee_vault_tokens(mint: &Pubkey) -> Pubkey {
+    state::get_fee_vault_token_pda(mint, &ID).0
+}
+
+pub fn listing(seller: &Pubkey, listing_id: u64) -> Pubkey {
+    state::get_listing_pda(seller, listing_id, &ID).0
+}
+
+pub fn buy_order(buyer: &Pubkey, order_id: u64) -> Pubkey {
+    state::get_buy_order_pda(buyer, order_id, &ID).0
+}
+
+pub fn buy_order_vault(buy_order: &Pubkey) -> Pubkey {
+    state::get_buy_order_vault_pda(buy_order, &ID).0
+}
+
+pub fn auction(seller: &Pubkey, auction_id: u64) -> Pubkey {
+    state::get_auction_pda(seller, auction_id, &ID).0
+}
+
+pub fn auction_vault(auction: &Pubkey) -> Pubkey {
+    state::get_auction_vault_pda(auction, &ID).0
+}
+
+pub fn swap(maker: &Pubkey, swap_id: u64) -> Pubkey {
+    state::get_swap_pda(maker, swap_id, &ID).0
+}
+
+pub fn swap_vault(swap: &Pubkey) -> Pubkey {
+    state::get_swap_vault_pda(swap, &ID).0
+}
+
+pub fn price_reference(mint: &Pubkey, item_class: &ItemClass) -> Pubkey {
+    state::get_price_reference_pda(mint, item_class, &ID).0
+}
+
+pub fn price_feed(mint: &Pubkey) -> Pubkey {
+    state::get_price_feed_pda(mint, &ID).0
+}
\ No newline at end of file
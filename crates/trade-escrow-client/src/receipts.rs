@@ -0,0 +1,77 @@
+//! Inclusion proofs for the receipts settled and refunded trades leave.
+//!
+//! The program keeps one receipt tree per mint and stores only its root
+//! on-chain. Proofs are built from the receipts emitted in one mint's
+//! `ReceiptAppended` events, taken in leaf order, and checked against the
+//! root read from that mint's `ReceiptTree` account or a `ReceiptAppended`
+//! event.
+
+use trade_escrow::state::{
+    hash_receipt_nodes, Receipt, EMPTY_RECEIPT_SUBTREES, RECEIPT_TREE_DEPTH,
+};
+
+/// Sibling hashes on the path from a receipt's leaf to the root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptProof {
+    /// Leaf index the receipt was appended at
+    pub index: u64,
+    /// Sibling at each height, starting next to the leaf
+    pub siblings: [[u8; 32]; RECEIPT_TREE_DEPTH],
+}
+
+/// Root of the tree holding `receipts`, as the program computes it after
+/// appending the same receipts in the same order
+pub fn receipt_root(receipts: &[Receipt]) -> [u8; 32] {
+    let mut level = leaves(receipts);
+    for empty in &EMPTY_RECEIPT_SUBTREES[..RECEIPT_TREE_DEPTH] {
+        level = parents(&level, empty);
+    }
+    level.first().copied().unwrap_or(EMPTY_RECEIPT_SUBTREES[RECEIPT_TREE_DEPTH])
+}
+
+/// Prove the receipt at `index` is part of the tree holding `receipts`.
+///
+/// Returns None if there is no receipt at `index`.
+pub fn prove_receipt(receipts: &[Receipt], index: u64) -> Option<ReceiptProof> {
+    let mut position = usize::try_from(index).ok()?;
+    if position >= receipts.len() {
+        return None;
+    }
+
+    let mut level = leaves(receipts);
+    let mut siblings = [[0; 32]; RECEIPT_TREE_DEPTH];
+    for (sibling, empty) in siblings.iter_mut().zip(&EMPTY_RECEIPT_SUBTREES) {
+        *sibling = level.get(position ^ 1).copied().unwrap_or(*empty);
+        level = parents(&level, empty);
+        position /= 2;
+    }
+    Some(ReceiptProof { index, siblings })
+}
+
+/// Whether `receipt` is the leaf at `proof.index` of the tree with `root`
+pub fn verify_receipt(receipt: &Receipt, proof: &ReceiptProof, root: &[u8; 32]) -> bool {
+    if proof.index >> RECEIPT_TREE_DEPTH != 0 {
+        return false;
+    }
+    let mut node = receipt.leaf();
+    for (height, sibling) in proof.siblings.iter().enumerate() {
+        node = if (proof.index >> height) & 1 == 1 {
+            hash_receipt_nodes(sibling, &node)
+        } else {
+            hash_receipt_nodes(&node, sibling)
+        };
+    }
+    node == *root
+}
+
+fn leaves(receipts: &[Receipt]) -> Vec<[u8; 32]> {
+    receipts.iter().map(Receipt::leaf).collect()
+}
+
+/// The level above `level`, pairing a trailing node with an empty subtree
+fn parents(level: &[[u8; 32]], empty: &[u8; 32]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| hash_receipt_nodes(&pair[0], pair.get(1).unwrap_or(empty)))
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,132 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use trade_escrow::state::{
+    Escrow, FeePayer, ItemClass, Receipt, ReceiptTree, TradeOutcome, TradedItem,
+};
+use trade_escrow_client::instructions::{self, LockAccounts, LockArgs};
+use trade_escrow_client::messages::{self, AskPrice};
+use trade_escrow_client::receipts::{prove_receipt, receipt_root, verify_receipt};
+use trade_escrow_client::{accounts, pda};
+
+fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = PublicKey::from(&secret);
+    Keypair { secret, public }
+}
+
+#[test]
+fn ed25519_payload_matches_sdk() {
+    let oracle = keypair(7);
+    let message = b"settle:42:buyer:escrow";
+    let signature = oracle.sign(message).to_bytes();
+
+    let expected = solana_sdk::ed25519_instruction::new_ed25519_instruction(&oracle, message);
+    let built = messages::ed25519_verify_instruction(
+        &Pubkey::new_from_array(oracle.public.to_bytes()),
+        &signature,
+        message,
+    );
+
+    assert_eq!(built.program_id, expected.program_id);
+    assert_eq!(built.data, expected.data);
+}
+
+#[test]
+fn lock_derives_escrow_vault_and_price_reference() {
+    let accounts = LockAccounts {
+        buyer: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        buyer_token_account: Pubkey::new_unique(),
+        ..Default::default()
+    };
+    let item_class = ItemClass {
+        appid: 730,
+        market_hash_name_hash: [3; 32],
+    };
+    let args = LockArgs {
+        asset_id: 42,
+        item_class,
+        price: AskPrice::Tokens(1_000),
+        price_max: 1_000,
+        ask_signature: [1; 64],
+        deadline_offset: 300,
+        fee_payer_override: Some(FeePayer::Seller),
+        allowed_buyer: None,
+        price_override: false,
+    };
+    let ix = instructions::lock(&accounts, &args, 1_700_000_000);
+
+    let escrow = pda::escrow(&accounts.buyer, &accounts.seller, 42, 1_700_000_000);
+    assert_eq!(ix.accounts[0].pubkey, escrow);
+    assert!(ix
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == pda::escrow_vault(&escrow)));
+    let reference = pda::price_reference(&accounts.mint, &item_class);
+    assert!(ix.accounts.iter().any(|meta| meta.pubkey == reference));
+    assert!(ix
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == accounts.buyer && meta.is_signer));
+}
+
+#[test]
+fn decodes_escrow_and_its_settlement_message() {
+    let escrow = Escrow {
+        buyer: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        asset_id: 42,
+        amount: 1_000,
+        ..Default::default()
+    };
+    let mut data = Vec::new();
+    escrow.try_serialize(&mut data).unwrap();
+
+    let decoded = accounts::escrow(&data).unwrap();
+    let key = Pubkey::new_unique();
+    assert_eq!(decoded.buyer, escrow.buyer);
+    assert_eq!(
+        messages::settlement_message(&key, &decoded),
+        format!("settle:42:{}:{}", escrow.buyer, key)
+    );
+    assert!(accounts::config(&data).is_err());
+}
+
+#[test]
+fn receipt_proofs_match_the_program_tree() {
+    let mut tree = ReceiptTree::default();
+    let mut receipts = Vec::new();
+    // Past a few powers of two, where the right edge of the tree moves up
+    for asset_id in 0..9 {
+        let item = match asset_id % 3 {
+            0 => TradedItem::Asset(asset_id),
+            1 => TradedItem::Commodity {
+                class: ItemClass::default(),
+                quantity: asset_id as u32,
+            },
+            _ => TradedItem::Swap {
+                maker_items: vec![asset_id],
+                taker_items: vec![],
+            },
+        };
+        let receipt = Receipt {
+            escrow: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            seller: Pubkey::new_unique(),
+            item,
+            amount: 1_000 + asset_id,
+            outcome: TradeOutcome::Settled,
+            timestamp: 1_700_000_000,
+        };
+        assert_eq!(tree.append(&receipt).unwrap(), asset_id);
+        receipts.push(receipt);
+
+        assert_eq!(tree.root, receipt_root(&receipts));
+        for (index, receipt) in receipts.iter().enumerate() {
+            let proof = prove_receipt(&receipts, index as u64).unwrap();
+            assert!(verify_receipt(receipt, &proof, &tree.root));
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,29 @@
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error("rpc error: {0}")]
+    Rpc(String),
+
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("replay file error: {0}")]
+    Replay(String),
+
+    #[error("failed to decode {event} in transaction {signature}")]
+    Decode {
+        signature: String,
+        event: &'static str,
+    },
+
+    #[error("settled volume in mint {0} overflows a u64")]
+    VolumeOverflow(Pubkey),
+}
+
+impl From<solana_client::client_error::ClientError> for IndexerError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        IndexerError::Rpc(error.to_string())
+    }
+}
\ No newline at end of file
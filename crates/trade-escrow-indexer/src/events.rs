@@ -0,0 +1,129 @@
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use trade_escrow::{
+    CommodityEscrowLocked, CommodityEscrowSettled, ConfigUpdated, EmergencyPause, EscrowLocked,
+    EscrowRefunded, EscrowSettled,
+};
+use trade_escrow_client::PROGRAM_ID;
+
+use crate::error::IndexerError;
+use crate::source::IndexedTransaction;
+
+/// Prefix of the log line `emit!` writes an event to
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// A program event the indexer stores
+pub enum TradeEvent {
+    Locked(EscrowLocked),
+    Settled(EscrowSettled),
+    Refunded(EscrowRefunded),
+    CommodityLocked(CommodityEscrowLocked),
+    CommoditySettled(CommodityEscrowSettled),
+    Paused(EmergencyPause),
+    ConfigUpdated(ConfigUpdated),
+}
+
+impl TradeEvent {
+    /// Decode an event from its discriminator and Borsh body.
+    ///
+    /// Returns `Ok(None)` for events the indexer does not store, and the
+    /// event name when the body does not match its discriminator.
+    pub fn decode(data: &[u8]) -> Result<Option<Self>, &'static str> {
+        if data.len() < 8 {
+            return Ok(None);
+        }
+        let (discriminator, mut body) = data.split_at(8);
+        let event = match discriminator {
+            d if d == EscrowLocked::DISCRIMINATOR => EscrowLocked::deserialize(&mut body)
+                .map(TradeEvent::Locked)
+                .map_err(|_| "EscrowLocked")?,
+            d if d == EscrowSettled::DISCRIMINATOR => EscrowSettled::deserialize(&mut body)
+                .map(TradeEvent::Settled)
+                .map_err(|_| "EscrowSettled")?,
+            d if d == EscrowRefunded::DISCRIMINATOR => EscrowRefunded::deserialize(&mut body)
+                .map(TradeEvent::Refunded)
+                .map_err(|_| "EscrowRefunded")?,
+            d if d == CommodityEscrowLocked::DISCRIMINATOR => {
+                CommodityEscrowLocked::deserialize(&mut body)
+                    .map(TradeEvent::CommodityLocked)
+                    .map_err(|_| "CommodityEscrowLocked")?
+            }
+            d if d == CommodityEscrowSettled::DISCRIMINATOR => {
+                CommodityEscrowSettled::deserialize(&mut body)
+                    .map(TradeEvent::CommoditySettled)
+                    .map_err(|_| "CommodityEscrowSettled")?
+            }
+            d if d == EmergencyPause::DISCRIMINATOR => EmergencyPause::deserialize(&mut body)
+                .map(TradeEvent::Paused)
+                .map_err(|_| "EmergencyPause")?,
+            d if d == ConfigUpdated::DISCRIMINATOR => ConfigUpdated::deserialize(&mut body)
+                .map(TradeEvent::ConfigUpdated)
+                .map_err(|_| "ConfigUpdated")?,
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+    /// Position of the event in the program's event sequence
+    pub fn sequence(&self) -> u64 {
+        match self {
+            TradeEvent::Locked(event) => event.sequence,
+            TradeEvent::Settled(event) => event.sequence,
+            TradeEvent::Refunded(event) => event.sequence,
+            TradeEvent::CommodityLocked(event) => event.sequence,
+            TradeEvent::CommoditySettled(event) => event.sequence,
+            TradeEvent::Paused(event) => event.sequence,
+            TradeEvent::ConfigUpdated(event) => event.sequence,
+        }
+    }
+}
+
+impl IndexedTransaction {
+    /// Events the program emitted in this transaction, in log order.
+    ///
+    /// Only data logged while the program itself is executing counts, so
+    /// other programs cannot forge events by logging the same bytes. Failed
+    /// transactions emitted nothing that took effect.
+    pub fn events(&self) -> Result<Vec<TradeEvent>, IndexerError> {
+        if self.failed {
+            return Ok(Vec::new());
+        }
+
+        let program = PROGRAM_ID.to_string();
+        let mut invocations: Vec<&str> = Vec::new();
+        let mut events = Vec::new();
+        for log in &self.logs {
+            if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+                if invocations.last() != Some(&program.as_str()) {
+                    continue;
+                }
+                let event = STANDARD
+                    .decode(data)
+                    .map_err(|_| "program data")
+                    .and_then(|data| TradeEvent::decode(&data))
+                    .map_err(|event| IndexerError::Decode {
+                        signature: self.signature.clone(),
+                        event,
+                    })?;
+                events.extend(event);
+                continue;
+            }
+
+            // "Program <id> invoke [n]", "Program <id> success" or "Program <id> failed: ..."
+            let mut words = log.split_whitespace();
+            let (Some("Program"), Some(id), Some(status)) =
+                (words.next(), words.next(), words.next())
+            else {
+                continue;
+            };
+            match status {
+                "invoke" => invocations.push(id),
+                "success" | "failed:" => {
+                    invocations.pop();
+                }
+                _ => {}
+            }
+        }
+        Ok(events)
+    }
+}
\ No newline at end of file
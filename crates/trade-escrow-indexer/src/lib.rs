@@ -0,0 +1,18 @@
+//! Event indexer for trade-escrow.
+//!
+//! Reads program transactions from a JSON-RPC node or a replay file,
+//! decodes the escrow and commodity escrow lock, settle and refund events,
+//! `EmergencyPause` and `ConfigUpdated` from their logs and stores them in
+//! SQLite, where trade history, seller fill rates and volume per mint can be
+//! queried. Each of those events carries the program's event sequence
+//! number, so gaps in what was indexed can be detected.
+
+pub mod error;
+pub mod events;
+pub mod source;
+pub mod store;
+
+pub use error::IndexerError;
+pub use events::TradeEvent;
+pub use source::{IndexedTransaction, ReplayFile, RpcSource, TransactionSource};
+pub use store::{AdminAction, FillRate, MintVolume, SequenceGap, Store, Trade, TradeStatus};
\ No newline at end of file
@@ -0,0 +1,146 @@
+use clap::{Parser, Subcommand};
+use solana_sdk::pubkey::Pubkey;
+use std::error::Error;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use trade_escrow::state::TradedItem;
+use trade_escrow_indexer::{ReplayFile, RpcSource, Store, TradeStatus, TransactionSource};
+
+/// Index trade-escrow events into SQLite and query them
+#[derive(Parser)]
+#[command(name = "trade-escrow-indexer", version)]
+struct Cli {
+    /// SQLite database holding the index
+    #[arg(long, global = true, default_value = "trade-escrow.db")]
+    db: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index new program transactions from a cluster
+    Sync {
+        /// JSON-RPC endpoint of the cluster
+        #[arg(long, default_value = "http://127.0.0.1:8899")]
+        url: String,
+
+        /// Keep polling, waiting this many milliseconds between syncs
+        #[arg(long)]
+        follow_ms: Option<u64>,
+    },
+    /// Index transactions from a replay file
+    Replay { file: PathBuf },
+    /// Append a cluster's program transactions to a replay file
+    Record {
+        file: PathBuf,
+
+        /// JSON-RPC endpoint of the cluster
+        #[arg(long, default_value = "http://127.0.0.1:8899")]
+        url: String,
+    },
+    /// Print every trade a wallet bought or sold in
+    History { user: Pubkey },
+    /// Print how often each seller's escrows settle rather than refund
+    FillRates,
+    /// Print settled volume per payment mint
+    Volume,
+    /// Print ranges of event sequence numbers missing from the index
+    Gaps,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Command::Sync { url, follow_ms } => {
+            let mut store = Store::open(&cli.db)?;
+            let source = RpcSource::new(url);
+            loop {
+                match store.sync(&source) {
+                    Ok(indexed) => println!("indexed {} transactions", indexed),
+                    Err(e) if follow_ms.is_some() => eprintln!("failed to sync: {}", e),
+                    Err(e) => return Err(e.into()),
+                }
+                let Some(interval) = follow_ms else {
+                    return Ok(());
+                };
+                thread::sleep(Duration::from_millis(*interval));
+            }
+        }
+        Command::Replay { file } => {
+            let indexed = Store::open(&cli.db)?.sync(&ReplayFile::new(file))?;
+            println!("indexed {} transactions", indexed);
+            Ok(())
+        }
+        Command::Record { file, url } => {
+            let replay = ReplayFile::new(file);
+            let last = replay
+                .read()?
+                .pop()
+                .map(|transaction| transaction.signature);
+            let transactions = RpcSource::new(url).transactions_after(last.as_deref())?;
+            replay.append(&transactions)?;
+            println!("recorded {} transactions", transactions.len());
+            Ok(())
+        }
+        Command::History { user } => {
+            for trade in Store::open(&cli.db)?.trade_history(user)? {
+                let (role, counterparty) = if trade.buyer == *user {
+                    ("bought", trade.seller)
+                } else {
+                    ("sold", trade.buyer)
+                };
+                let status = match &trade.status {
+                    TradeStatus::Open => "open".to_string(),
+                    TradeStatus::Settled => "settled".to_string(),
+                    TradeStatus::Refunded { reason } => format!("refunded ({:?})", reason),
+                };
+                let item = match &trade.item {
+                    TradedItem::Asset(asset_id) => format!("asset {}", asset_id),
+                    TradedItem::Commodity { class, quantity } => {
+                        format!("{} x {}", quantity, class.identifier())
+                    }
+                    TradedItem::Swap {
+                        maker_items,
+                        taker_items,
+                    } => format!("assets {:?} for {:?}", maker_items, taker_items),
+                };
+                println!(
+                    "{} {} {} for {} of {} with {}: {}",
+                    trade.escrow_id, role, item, trade.amount, trade.mint, counterparty, status
+                );
+            }
+            Ok(())
+        }
+        Command::FillRates => {
+            for fill in Store::open(&cli.db)?.fill_rates()? {
+                let rate = fill
+                    .rate()
+                    .map_or("-".to_string(), |rate| format!("{:.1}%", rate * 100.0));
+                println!(
+                    "{} {} settled, {} refunded, {} open: {}",
+                    fill.seller, fill.settled, fill.refunded, fill.open, rate
+                );
+            }
+            Ok(())
+        }
+        Command::Volume => {
+            for volume in Store::open(&cli.db)?.volume_by_mint()? {
+                println!(
+                    "{} {} trades, volume {}, fees {}",
+                    volume.mint, volume.trades, volume.volume, volume.fees
+                );
+            }
+            Ok(())
+        }
+        Command::Gaps => {
+            for gap in Store::open(&cli.db)?.missing_sequences()? {
+                println!("missing events {}..={}", gap.first, gap.last);
+            }
+            Ok(())
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use trade_escrow_client::PROGRAM_ID;
+
+use crate::error::IndexerError;
+
+/// A confirmed transaction that invoked the program
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Whether the transaction failed, undoing everything it logged
+    #[serde(default)]
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+/// Where the indexer reads program transactions from
+pub trait TransactionSource {
+    /// Transactions after the one with signature `after`, oldest first.
+    ///
+    /// With no `after`, or one the source does not know, returns everything.
+    fn transactions_after(
+        &self,
+        after: Option<&str>,
+    ) -> Result<Vec<IndexedTransaction>, IndexerError>;
+}
+
+/// Transactions recorded one JSON object per line, for replaying offline
+pub struct ReplayFile {
+    path: PathBuf,
+}
+
+impl ReplayFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Every recorded transaction, in file order
+    pub fn read(&self) -> Result<Vec<IndexedTransaction>, IndexerError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(IndexerError::Replay(e.to_string())),
+        };
+        let mut transactions = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| IndexerError::Replay(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let transaction = serde_json::from_str(&line)
+                .map_err(|e| IndexerError::Replay(format!("line {}: {}", number + 1, e)))?;
+            transactions.push(transaction);
+        }
+        Ok(transactions)
+    }
+
+    /// Record `transactions` after those already in the file
+    pub fn append(&self, transactions: &[IndexedTransaction]) -> Result<(), IndexerError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| IndexerError::Replay(e.to_string()))?;
+        for transaction in transactions {
+            let line = serde_json::to_string(transaction)
+                .map_err(|e| IndexerError::Replay(e.to_string()))?;
+            writeln!(file, "{}", line).map_err(|e| IndexerError::Replay(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl TransactionSource for ReplayFile {
+    fn transactions_after(
+        &self,
+        after: Option<&str>,
+    ) -> Result<Vec<IndexedTransaction>, IndexerError> {
+        let transactions = self.read()?;
+        let start = after
+            .and_then(|after| transactions.iter().position(|t| t.signature == after))
+            .map_or(0, |position| position + 1);
+        Ok(transactions[start..].to_vec())
+    }
+}
+
+/// Transactions fetched from a JSON-RPC node
+pub struct RpcSource {
+    client: RpcClient,
+}
+
+impl RpcSource {
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            client: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+        }
+    }
+
+    fn transaction(&self, signature: &str) -> Result<IndexedTransaction, IndexerError> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = self
+            .client
+            .get_transaction_with_config(&parse_signature(signature)?, config)?;
+        let meta = transaction
+            .transaction
+            .meta
+            .ok_or_else(|| IndexerError::Rpc(format!("no status for {}", signature)))?;
+        Ok(IndexedTransaction {
+            signature: signature.to_string(),
+            slot: transaction.slot,
+            block_time: transaction.block_time,
+            failed: meta.err.is_some(),
+            logs: Option::from(meta.log_messages).unwrap_or_default(),
+        })
+    }
+}
+
+impl TransactionSource for RpcSource {
+    fn transactions_after(
+        &self,
+        after: Option<&str>,
+    ) -> Result<Vec<IndexedTransaction>, IndexerError> {
+        let until = after.map(parse_signature).transpose()?;
+
+        // Signatures come newest first, a page at a time
+        let mut signatures = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self.client.get_signatures_for_address_with_config(
+                &PROGRAM_ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: None,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )?;
+            let Some(oldest) = page.last() else { break };
+            before = Some(parse_signature(&oldest.signature)?);
+            signatures.extend(page.into_iter().map(|status| status.signature));
+        }
+
+        signatures
+            .iter()
+            .rev()
+            .map(|signature| self.transaction(signature))
+            .collect()
+    }
+}
+
+fn parse_signature(signature: &str) -> Result<Signature, IndexerError> {
+    Signature::from_str(signature)
+        .map_err(|_| IndexerError::Rpc(format!("invalid signature {}", signature)))
+}
\ No newline at end of file
@@ -0,0 +1,576 @@
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
+use std::str::FromStr;
+use trade_escrow::state::{ConfigChange, ItemClass, RefundReason, TradedItem};
+
+use crate::error::IndexerError;
+use crate::events::TradeEvent;
+use crate::source::{IndexedTransaction, TransactionSource};
+
+// Token amounts and asset IDs are u64s, which can exceed SQLite's signed
+// integers, so they are stored as decimal TEXT and summed in Rust.
+// Commodity escrows have a Borsh-encoded class and quantity instead of an
+// asset ID, and their amount is what the delivered items cost once settled.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS transactions (
+        signature TEXT PRIMARY KEY,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        failed INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS escrows (
+        escrow_id TEXT PRIMARY KEY,
+        buyer TEXT NOT NULL,
+        seller TEXT NOT NULL,
+        mint TEXT NOT NULL,
+        asset_id TEXT,
+        commodity BLOB,
+        quantity INTEGER,
+        unit_price TEXT,
+        delivered INTEGER,
+        amount TEXT NOT NULL,
+        fee TEXT NOT NULL,
+        deadline INTEGER NOT NULL,
+        usd_rate TEXT,
+        locked_in TEXT NOT NULL REFERENCES transactions (signature),
+        status TEXT NOT NULL DEFAULT 'open',
+        closed_in TEXT REFERENCES transactions (signature),
+        protocol_fee TEXT,
+        referrer TEXT,
+        referral_fee TEXT,
+        refunded TEXT,
+        refund_reason TEXT
+    );
+    CREATE INDEX IF NOT EXISTS escrows_by_buyer ON escrows (buyer);
+    CREATE INDEX IF NOT EXISTS escrows_by_seller ON escrows (seller);
+
+    CREATE TABLE IF NOT EXISTS admin_actions (
+        signature TEXT NOT NULL REFERENCES transactions (signature),
+        position INTEGER NOT NULL,
+        authority TEXT NOT NULL,
+        action TEXT NOT NULL,
+        change BLOB,
+        timestamp INTEGER NOT NULL,
+        PRIMARY KEY (signature, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS events (
+        sequence INTEGER PRIMARY KEY,
+        signature TEXT NOT NULL REFERENCES transactions (signature),
+        position INTEGER NOT NULL
+    );
+";
+
+/// Where an indexed trade stands
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TradeStatus {
+    Open,
+    Settled,
+    Refunded { reason: RefundReason },
+}
+
+/// One escrow as seen by either side of the trade
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trade {
+    pub escrow_id: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    /// The asset, or the commodity items ordered and, once settled, delivered
+    pub item: TradedItem,
+    pub amount: u64,
+    pub fee: u64,
+    pub status: TradeStatus,
+    /// Block time of the lock, when the cluster reported one
+    pub locked_at: Option<i64>,
+    /// Block time of the settlement or refund
+    pub closed_at: Option<i64>,
+}
+
+/// How a seller's escrows have ended
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FillRate {
+    pub seller: Pubkey,
+    pub settled: u64,
+    pub refunded: u64,
+    pub open: u64,
+}
+
+impl FillRate {
+    /// Share of closed escrows the seller delivered, or None before any closed
+    pub fn rate(&self) -> Option<f64> {
+        let closed = self.settled + self.refunded;
+        (closed > 0).then(|| self.settled as f64 / closed as f64)
+    }
+}
+
+/// Settled trade volume in one mint
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MintVolume {
+    pub mint: Pubkey,
+    pub trades: u64,
+    pub volume: u64,
+    /// Protocol and referral fees taken on those trades
+    pub fees: u64,
+}
+
+/// Pause or configuration change made by the admin or guardian
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdminAction {
+    pub signature: String,
+    pub authority: Pubkey,
+    /// Configuration change, or None for a pause
+    pub change: Option<ConfigChange>,
+    pub timestamp: i64,
+}
+
+/// Event sequence numbers `first..=last` that were never indexed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SequenceGap {
+    pub first: u64,
+    pub last: u64,
+}
+
+/// SQLite database of indexed program events
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IndexerError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn in_memory() -> Result<Self, IndexerError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, IndexerError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Signature of the most recently indexed transaction
+    pub fn last_signature(&self) -> Result<Option<String>, IndexerError> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT signature FROM transactions ORDER BY slot DESC, rowid DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Index everything `source` has after the last indexed transaction.
+    ///
+    /// Returns how many transactions were new.
+    pub fn sync(&mut self, source: &impl TransactionSource) -> Result<usize, IndexerError> {
+        let after = self.last_signature()?;
+        let mut indexed = 0;
+        for transaction in source.transactions_after(after.as_deref())? {
+            if self.index(&transaction)? {
+                indexed += 1;
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Store a transaction's events; false if it was already indexed.
+    ///
+    /// Settlements and refunds of escrows locked before the first indexed
+    /// transaction are not recorded.
+    pub fn index(&mut self, transaction: &IndexedTransaction) -> Result<bool, IndexerError> {
+        let events = transaction.events()?;
+        let db = self.connection.transaction()?;
+        let inserted = db.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time, failed)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                transaction.signature,
+                transaction.slot,
+                transaction.block_time,
+                transaction.failed
+            ],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        let signature = &transaction.signature;
+        for (position, event) in events.iter().enumerate() {
+            db.execute(
+                "INSERT INTO events (sequence, signature, position) VALUES (?1, ?2, ?3)",
+                params![event.sequence(), signature, position],
+            )?;
+            match event {
+                TradeEvent::Locked(event) => {
+                    db.execute(
+                        "INSERT INTO escrows (escrow_id, buyer, seller, mint, asset_id, amount,
+                                              fee, deadline, usd_rate, locked_in)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                        params![
+                            event.escrow_id.to_string(),
+                            event.buyer.to_string(),
+                            event.seller.to_string(),
+                            event.mint.to_string(),
+                            event.asset_id.to_string(),
+                            event.amount.to_string(),
+                            event.fee.to_string(),
+                            event.deadline,
+                            event.usd_rate.map(|rate| rate.to_string()),
+                            signature
+                        ],
+                    )?;
+                }
+                TradeEvent::Settled(event) => {
+                    db.execute(
+                        "UPDATE escrows
+                         SET status = 'settled', closed_in = ?2, protocol_fee = ?3,
+                             referrer = ?4, referral_fee = ?5
+                         WHERE escrow_id = ?1",
+                        params![
+                            event.escrow_id.to_string(),
+                            signature,
+                            event.protocol_fee.to_string(),
+                            event.referrer.map(|referrer| referrer.to_string()),
+                            event.referral_fee.to_string()
+                        ],
+                    )?;
+                }
+                TradeEvent::CommodityLocked(event) => {
+                    // The program refuses to lock an order whose total overflows
+                    let amount = event
+                        .unit_price
+                        .checked_mul(event.quantity as u64)
+                        .ok_or_else(|| IndexerError::Decode {
+                            signature: signature.clone(),
+                            event: "CommodityEscrowLocked",
+                        })?;
+                    db.execute(
+                        "INSERT INTO escrows (escrow_id, buyer, seller, mint, commodity, quantity,
+                                              unit_price, amount, fee, deadline, locked_in)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                        params![
+                            event.escrow_id.to_string(),
+                            event.buyer.to_string(),
+                            event.seller.to_string(),
+                            event.mint.to_string(),
+                            event.commodity.try_to_vec().expect("borsh into a Vec"),
+                            event.quantity,
+                            event.unit_price.to_string(),
+                            amount.to_string(),
+                            event.fee.to_string(),
+                            event.deadline,
+                            signature
+                        ],
+                    )?;
+                }
+                TradeEvent::CommoditySettled(event) => {
+                    let locked = db
+                        .query_row(
+                            "SELECT unit_price FROM escrows WHERE escrow_id = ?1",
+                            [event.escrow_id.to_string()],
+                            |row| integer(row, 0),
+                        )
+                        .optional()?;
+                    let Some(unit_price) = locked else {
+                        continue;
+                    };
+                    let amount =
+                        unit_price
+                            .checked_mul(event.delivered as u64)
+                            .ok_or_else(|| IndexerError::Decode {
+                                signature: signature.clone(),
+                                event: "CommodityEscrowSettled",
+                            })?;
+                    db.execute(
+                        "UPDATE escrows
+                         SET status = 'settled', closed_in = ?2, delivered = ?3, amount = ?4,
+                             protocol_fee = ?5, referral_fee = '0', refunded = ?6
+                         WHERE escrow_id = ?1",
+                        params![
+                            event.escrow_id.to_string(),
+                            signature,
+                            event.delivered,
+                            amount.to_string(),
+                            event.protocol_fee.to_string(),
+                            event.refunded.to_string()
+                        ],
+                    )?;
+                }
+                TradeEvent::Refunded(event) => {
+                    db.execute(
+                        "UPDATE escrows
+                         SET status = 'refunded', closed_in = ?2, refunded = ?3, refund_reason = ?4
+                         WHERE escrow_id = ?1",
+                        params![
+                            event.escrow_id.to_string(),
+                            signature,
+                            event.amount.to_string(),
+                            reason_label(event.reason)
+                        ],
+                    )?;
+                }
+                TradeEvent::Paused(event) => {
+                    db.execute(
+                        "INSERT INTO admin_actions (signature, position, authority, action, timestamp)
+                         VALUES (?1, ?2, ?3, 'pause', ?4)",
+                        params![
+                            signature,
+                            position,
+                            event.triggered_by.to_string(),
+                            event.timestamp
+                        ],
+                    )?;
+                }
+                TradeEvent::ConfigUpdated(event) => {
+                    db.execute(
+                        "INSERT INTO admin_actions (signature, position, authority, action, change,
+                                                    timestamp)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            signature,
+                            position,
+                            event.updated_by.to_string(),
+                            change_label(&event.change),
+                            event.change.try_to_vec().expect("borsh into a Vec"),
+                            event.timestamp
+                        ],
+                    )?;
+                }
+            }
+        }
+        db.commit()?;
+        Ok(true)
+    }
+
+    /// Every escrow `user` bought or sold in, oldest first
+    pub fn trade_history(&self, user: &Pubkey) -> Result<Vec<Trade>, IndexerError> {
+        let mut statement = self.connection.prepare(
+            "SELECT e.escrow_id, e.buyer, e.seller, e.mint, e.asset_id, e.amount, e.fee,
+                    e.status, e.refund_reason, locked.block_time, closed.block_time,
+                    e.commodity, COALESCE(e.delivered, e.quantity)
+             FROM escrows e
+             JOIN transactions locked ON locked.signature = e.locked_in
+             LEFT JOIN transactions closed ON closed.signature = e.closed_in
+             WHERE e.buyer = ?1 OR e.seller = ?1
+             ORDER BY locked.slot, e.rowid",
+        )?;
+        let trades = statement
+            .query_map([user.to_string()], |row| {
+                let status = match row.get::<_, String>(7)?.as_str() {
+                    "settled" => TradeStatus::Settled,
+                    "refunded" => TradeStatus::Refunded {
+                        reason: refund_reason(row, 8)?,
+                    },
+                    _ => TradeStatus::Open,
+                };
+                Ok(Trade {
+                    escrow_id: pubkey(row, 0)?,
+                    buyer: pubkey(row, 1)?,
+                    seller: pubkey(row, 2)?,
+                    mint: pubkey(row, 3)?,
+                    item: traded_item(row, 4, 11, 12)?,
+                    amount: integer(row, 5)?,
+                    fee: integer(row, 6)?,
+                    status,
+                    locked_at: row.get(9)?,
+                    closed_at: row.get(10)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(trades)
+    }
+
+    /// Settled, refunded and open escrow counts for every seller
+    pub fn fill_rates(&self) -> Result<Vec<FillRate>, IndexerError> {
+        let mut statement = self.connection.prepare(
+            "SELECT seller,
+                    SUM(status = 'settled'), SUM(status = 'refunded'), SUM(status = 'open')
+             FROM escrows
+             GROUP BY seller
+             ORDER BY seller",
+        )?;
+        let rates = statement
+            .query_map([], |row| {
+                Ok(FillRate {
+                    seller: pubkey(row, 0)?,
+                    settled: row.get(1)?,
+                    refunded: row.get(2)?,
+                    open: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(rates)
+    }
+
+    /// Volume and fees of settled trades, per payment mint
+    pub fn volume_by_mint(&self) -> Result<Vec<MintVolume>, IndexerError> {
+        let mut statement = self.connection.prepare(
+            "SELECT mint, amount, protocol_fee, referral_fee
+             FROM escrows
+             WHERE status = 'settled'
+             ORDER BY mint",
+        )?;
+        let mut rows = statement.query([])?;
+        let mut volumes: Vec<MintVolume> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mint = pubkey(row, 0)?;
+            let fees = integer(row, 2)?
+                .checked_add(integer(row, 3)?)
+                .ok_or(IndexerError::VolumeOverflow(mint))?;
+            let volume = match volumes.last_mut() {
+                Some(volume) if volume.mint == mint => volume,
+                _ => {
+                    volumes.push(MintVolume {
+                        mint,
+                        trades: 0,
+                        volume: 0,
+                        fees: 0,
+                    });
+                    volumes.last_mut().unwrap()
+                }
+            };
+            volume.trades += 1;
+            volume.volume = volume
+                .volume
+                .checked_add(integer(row, 1)?)
+                .ok_or(IndexerError::VolumeOverflow(mint))?;
+            volume.fees = volume
+                .fees
+                .checked_add(fees)
+                .ok_or(IndexerError::VolumeOverflow(mint))?;
+        }
+        Ok(volumes)
+    }
+
+    /// Pauses and configuration changes, oldest first
+    pub fn admin_actions(&self) -> Result<Vec<AdminAction>, IndexerError> {
+        let mut statement = self.connection.prepare(
+            "SELECT a.signature, a.authority, a.change, a.timestamp
+             FROM admin_actions a
+             JOIN transactions t ON t.signature = a.signature
+             ORDER BY t.slot, t.rowid, a.position",
+        )?;
+        let actions = statement
+            .query_map([], |row| {
+                let change = row
+                    .get::<_, Option<Vec<u8>>>(2)?
+                    .map(|bytes| ConfigChange::try_from_slice(&bytes))
+                    .transpose()
+                    .map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            2,
+                            rusqlite::types::Type::Blob,
+                            Box::new(e),
+                        )
+                    })?;
+                Ok(AdminAction {
+                    signature: row.get(0)?,
+                    authority: pubkey(row, 1)?,
+                    change,
+                    timestamp: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(actions)
+    }
+
+    /// Sequence numbers missing between the first event and the last indexed.
+    ///
+    /// A gap means the source skipped transactions, and the index is
+    /// incomplete until they are replayed.
+    pub fn missing_sequences(&self) -> Result<Vec<SequenceGap>, IndexerError> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT sequence FROM events ORDER BY sequence")?;
+        let sequences = statement.query_map([], |row| row.get::<_, u64>(0))?;
+        let mut gaps = Vec::new();
+        let mut expected = 1;
+        for sequence in sequences {
+            let sequence = sequence?;
+            if sequence > expected {
+                gaps.push(SequenceGap {
+                    first: expected,
+                    last: sequence - 1,
+                });
+            }
+            expected = sequence + 1;
+        }
+        Ok(gaps)
+    }
+}
+
+/// Label a refund reason is stored under
+fn reason_label(reason: RefundReason) -> &'static str {
+    match reason {
+        RefundReason::DeadlineExpired => "deadline_expired",
+        RefundReason::DeliveryFailed => "delivery_failed",
+    }
+}
+
+/// Label a configuration change is stored under, next to its encoded values
+fn change_label(change: &ConfigChange) -> &'static str {
+    match change {
+        ConfigChange::Unpause => "unpause",
+        ConfigChange::Oracles { .. } => "oracles",
+        ConfigChange::Limits { .. } => "limits",
+        ConfigChange::Fees { .. } => "fees",
+        ConfigChange::FeeSchedule { .. } => "fee_schedule",
+        ConfigChange::MinFee { .. } => "min_fee",
+        ConfigChange::PriceChecks { .. } => "price_checks",
+    }
+}
+
+fn refund_reason(row: &Row, index: usize) -> rusqlite::Result<RefundReason> {
+    match row.get::<_, String>(index)?.as_str() {
+        "deadline_expired" => Ok(RefundReason::DeadlineExpired),
+        "delivery_failed" => Ok(RefundReason::DeliveryFailed),
+        other => Err(rusqlite::Error::InvalidColumnType(
+            index,
+            other.to_string(),
+            rusqlite::types::Type::Text,
+        )),
+    }
+}
+
+/// Asset ID at `asset`, or the Borsh-encoded class at `class` with `quantity` items
+fn traded_item(
+    row: &Row,
+    asset: usize,
+    class: usize,
+    quantity: usize,
+) -> rusqlite::Result<TradedItem> {
+    match row.get::<_, Option<Vec<u8>>>(class)? {
+        Some(bytes) => Ok(TradedItem::Commodity {
+            class: ItemClass::try_from_slice(&bytes).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    class,
+                    rusqlite::types::Type::Blob,
+                    Box::new(e),
+                )
+            })?,
+            quantity: row.get(quantity)?,
+        }),
+        None => Ok(TradedItem::Asset(integer(row, asset)?)),
+    }
+}
+
+/// u64 stored as decimal TEXT
+fn integer(row: &Row, index: usize) -> rusqlite::Result<u64> {
+    let text: String = row.get(index)?;
+    text.parse().map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn pubkey(row: &Row, index: usize) -> rusqlite::Result<Pubkey> {
+    let text: String = row.get(index)?;
+    Pubkey::from_str(&text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
\ No newline at end of file
@@ -0,0 +1,505 @@
+use anchor_lang::{Discriminator, Event};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::pubkey::Pubkey;
+use std::process::Command;
+use trade_escrow::state::{ConfigChange, ItemClass, RefundReason, TradedItem};
+use trade_escrow::{
+    CommodityEscrowLocked, CommodityEscrowSettled, ConfigUpdated, EmergencyPause, EscrowLocked,
+    EscrowRefunded, EscrowSettled,
+};
+use trade_escrow_client::PROGRAM_ID;
+use trade_escrow_indexer::{
+    FillRate, IndexedTransaction, IndexerError, MintVolume, ReplayFile, SequenceGap, Store,
+    TradeStatus,
+};
+
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGqPFXCWuBvf9Ss623VQ5DA";
+
+fn data_log(event: &impl Event) -> String {
+    format!("Program data: {}", STANDARD.encode(event.data()))
+}
+
+/// Logs of a program instruction emitting `events` after a token transfer
+fn program_logs(events: &[String]) -> Vec<String> {
+    let mut logs = vec![
+        format!("Program {} invoke [1]", PROGRAM_ID),
+        "Program log: Instruction: Lock".to_string(),
+        format!("Program {} invoke [2]", TOKEN_PROGRAM),
+        "Program log: Instruction: Transfer".to_string(),
+        format!("Program {} success", TOKEN_PROGRAM),
+    ];
+    logs.extend(events.iter().cloned());
+    logs.push(format!(
+        "Program {} consumed 24109 of 200000 compute units",
+        PROGRAM_ID
+    ));
+    logs.push(format!("Program {} success", PROGRAM_ID));
+    logs
+}
+
+fn transaction(slot: u64, events: &[String]) -> IndexedTransaction {
+    IndexedTransaction {
+        signature: format!("tx-{}", slot),
+        slot,
+        block_time: Some(1_700_000_000 + slot as i64),
+        failed: false,
+        logs: program_logs(events),
+    }
+}
+
+fn locked(
+    sequence: u64,
+    escrow_id: Pubkey,
+    buyer: Pubkey,
+    seller: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+) -> String {
+    data_log(&EscrowLocked {
+        escrow_id,
+        buyer,
+        seller,
+        mint,
+        asset_id: 42,
+        amount,
+        fee: amount / 200,
+        deadline: 1_700_000_300,
+        usd_rate: None,
+        sequence,
+    })
+}
+
+fn settled(sequence: u64, escrow_id: Pubkey, buyer: Pubkey, seller: Pubkey, amount: u64) -> String {
+    data_log(&EscrowSettled {
+        escrow_id,
+        buyer,
+        seller,
+        amount,
+        protocol_fee: amount / 200,
+        referrer: None,
+        referral_fee: 0,
+        oracle_count: 2,
+        sequence,
+    })
+}
+
+fn refunded(sequence: u64, escrow_id: Pubkey, buyer: Pubkey, amount: u64) -> String {
+    data_log(&EscrowRefunded {
+        escrow_id,
+        buyer,
+        amount,
+        reason: RefundReason::DeadlineExpired,
+        sequence,
+    })
+}
+
+fn commodity_locked(
+    sequence: u64,
+    escrow_id: Pubkey,
+    buyer: Pubkey,
+    seller: Pubkey,
+    mint: Pubkey,
+    quantity: u32,
+) -> String {
+    data_log(&CommodityEscrowLocked {
+        escrow_id,
+        buyer,
+        seller,
+        mint,
+        commodity: case(),
+        quantity,
+        unit_price: 100_000,
+        fee: quantity as u64 * 500,
+        deadline: 1_700_000_300,
+        sequence,
+    })
+}
+
+fn case() -> ItemClass {
+    ItemClass {
+        appid: 730,
+        market_hash_name_hash: [2; 32],
+    }
+}
+
+fn min_fee_updated(sequence: u64, admin: Pubkey, mint: Pubkey, new: u64) -> String {
+    data_log(&ConfigUpdated {
+        updated_by: admin,
+        change: ConfigChange::MinFee { mint, old: 0, new },
+        timestamp: 1_700_000_007,
+        sequence,
+    })
+}
+
+/// Two sellers trading in two mints: one settled, one refunded and one open
+/// escrow, with a pause and a fee change in between
+struct History {
+    buyer: Pubkey,
+    sellers: [Pubkey; 2],
+    mints: [Pubkey; 2],
+    escrows: [Pubkey; 3],
+    guardian: Pubkey,
+    admin: Pubkey,
+    transactions: Vec<IndexedTransaction>,
+}
+
+impl History {
+    fn new() -> Self {
+        let buyer = Pubkey::new_unique();
+        let sellers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let escrows = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let guardian = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let transactions = vec![
+            transaction(
+                1,
+                &[locked(
+                    1, escrows[0], buyer, sellers[0], mints[0], 1_000_000,
+                )],
+            ),
+            transaction(
+                2,
+                &[locked(2, escrows[1], buyer, sellers[0], mints[1], 400_000)],
+            ),
+            transaction(3, &[settled(3, escrows[0], buyer, sellers[0], 1_000_000)]),
+            transaction(4, &[refunded(4, escrows[1], buyer, 402_000)]),
+            transaction(
+                5,
+                &[
+                    data_log(&EmergencyPause {
+                        triggered_by: guardian,
+                        timestamp: 1_700_000_005,
+                        sequence: 5,
+                    }),
+                    data_log(&ConfigUpdated {
+                        updated_by: guardian,
+                        change: ConfigChange::Unpause,
+                        timestamp: 1_700_000_005,
+                        sequence: 6,
+                    }),
+                ],
+            ),
+            transaction(
+                6,
+                &[locked(
+                    7,
+                    escrows[2],
+                    Pubkey::new_unique(),
+                    sellers[1],
+                    mints[0],
+                    250_000,
+                )],
+            ),
+            transaction(7, &[min_fee_updated(8, admin, mints[1], 1_000)]),
+        ];
+        Self {
+            buyer,
+            sellers,
+            mints,
+            escrows,
+            guardian,
+            admin,
+            transactions,
+        }
+    }
+}
+
+#[test]
+fn indexed_events_answer_trade_queries() {
+    let history = History::new();
+    let mut store = Store::in_memory().unwrap();
+    for transaction in &history.transactions {
+        assert!(store.index(transaction).unwrap());
+    }
+
+    let trades = store.trade_history(&history.buyer).unwrap();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].escrow_id, history.escrows[0]);
+    assert_eq!(trades[0].mint, history.mints[0]);
+    assert_eq!(trades[0].fee, 5_000);
+    assert_eq!(trades[0].status, TradeStatus::Settled);
+    assert_eq!(trades[0].locked_at, Some(1_700_000_001));
+    assert_eq!(trades[0].closed_at, Some(1_700_000_003));
+    assert_eq!(
+        trades[1].status,
+        TradeStatus::Refunded {
+            reason: RefundReason::DeadlineExpired
+        }
+    );
+    let seller_trades = store.trade_history(&history.sellers[1]).unwrap();
+    assert_eq!(seller_trades.len(), 1);
+    assert_eq!(seller_trades[0].status, TradeStatus::Open);
+    assert_eq!(seller_trades[0].closed_at, None);
+
+    let rates = store.fill_rates().unwrap();
+    let rate = |seller: Pubkey| rates.iter().find(|rate| rate.seller == seller).unwrap();
+    assert_eq!(
+        *rate(history.sellers[0]),
+        FillRate {
+            seller: history.sellers[0],
+            settled: 1,
+            refunded: 1,
+            open: 0,
+        }
+    );
+    assert_eq!(rate(history.sellers[0]).rate(), Some(0.5));
+    assert_eq!(rate(history.sellers[1]).rate(), None);
+
+    // Only settled trades count as volume
+    assert_eq!(
+        store.volume_by_mint().unwrap(),
+        vec![MintVolume {
+            mint: history.mints[0],
+            trades: 1,
+            volume: 1_000_000,
+            fees: 5_000,
+        }]
+    );
+
+    let actions: Vec<_> = store
+        .admin_actions()
+        .unwrap()
+        .into_iter()
+        .map(|action| (action.change, action.authority))
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            (None, history.guardian),
+            (Some(ConfigChange::Unpause), history.guardian),
+            (
+                Some(ConfigChange::MinFee {
+                    mint: history.mints[1],
+                    old: 0,
+                    new: 1_000,
+                }),
+                history.admin
+            ),
+        ]
+    );
+    assert!(store.missing_sequences().unwrap().is_empty());
+}
+
+#[test]
+fn replay_sync_resumes_after_last_indexed_transaction() {
+    let history = History::new();
+    let dir = tempfile::tempdir().unwrap();
+    let replay = ReplayFile::new(dir.path().join("ledger.jsonl"));
+    let mut store = Store::open(dir.path().join("index.db")).unwrap();
+
+    replay.append(&history.transactions[..3]).unwrap();
+    assert_eq!(store.sync(&replay).unwrap(), 3);
+    assert_eq!(store.sync(&replay).unwrap(), 0);
+    assert_eq!(store.last_signature().unwrap().as_deref(), Some("tx-3"));
+
+    replay.append(&history.transactions[3..]).unwrap();
+    assert_eq!(store.sync(&replay).unwrap(), 4);
+    assert_eq!(store.trade_history(&history.buyer).unwrap().len(), 2);
+
+    // Indexing a transaction twice changes nothing
+    assert!(!store.index(&history.transactions[0]).unwrap());
+    let rates = store.fill_rates().unwrap();
+    assert_eq!(rates.iter().map(|rate| rate.settled).sum::<u64>(), 1);
+
+    // The database outlives the process, and the CLI reads it
+    drop(store);
+    let output = Command::new(env!("CARGO_BIN_EXE_trade-escrow-indexer"))
+        .args([
+            "--db",
+            dir.path().join("index.db").to_str().unwrap(),
+            "volume",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{} 1 trades, volume 1000000, fees 5000\n", history.mints[0])
+    );
+}
+
+#[test]
+fn only_program_events_from_successful_transactions_count() {
+    let buyer = Pubkey::new_unique();
+    let seller = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let mut store = Store::in_memory().unwrap();
+
+    // Another program logging the same bytes does not emit a program event
+    let forged = locked(1, Pubkey::new_unique(), buyer, seller, mint, 1_000);
+    let mut cpi = transaction(1, &[]);
+    cpi.logs.insert(3, forged.clone());
+    assert!(cpi.events().unwrap().is_empty());
+
+    let mut failed = transaction(2, &[forged]);
+    failed.failed = true;
+    store.index(&cpi).unwrap();
+    store.index(&failed).unwrap();
+    assert!(store.trade_history(&buyer).unwrap().is_empty());
+
+    // A program event that does not decode stops indexing instead of being skipped
+    let mut data = EscrowLocked::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&[1, 2, 3]);
+    let malformed = transaction(3, &[format!("Program data: {}", STANDARD.encode(data))]);
+    assert!(matches!(
+        store.index(&malformed),
+        Err(IndexerError::Decode {
+            event: "EscrowLocked",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn skipped_transactions_show_up_as_sequence_gaps() {
+    let history = History::new();
+    let mut store = Store::in_memory().unwrap();
+
+    // The source missed the settlement and the pause
+    for slot in [1, 2, 4, 6, 7] {
+        store.index(&history.transactions[slot - 1]).unwrap();
+    }
+    assert_eq!(
+        store.missing_sequences().unwrap(),
+        vec![
+            SequenceGap { first: 3, last: 3 },
+            SequenceGap { first: 5, last: 6 },
+        ]
+    );
+
+    // Replaying them closes the gaps
+    store.index(&history.transactions[2]).unwrap();
+    store.index(&history.transactions[4]).unwrap();
+    assert!(store.missing_sequences().unwrap().is_empty());
+}
+#[test]
+fn commodity_escrows_index_as_trades() {
+    let buyer = Pubkey::new_unique();
+    let seller = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let escrows = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut store = Store::in_memory().unwrap();
+
+    let transactions = [
+        transaction(
+            1,
+            &[commodity_locked(1, escrows[0], buyer, seller, mint, 50)],
+        ),
+        transaction(
+            2,
+            &[commodity_locked(2, escrows[1], buyer, seller, mint, 10)],
+        ),
+        transaction(
+            3,
+            &[data_log(&CommodityEscrowSettled {
+                escrow_id: escrows[0],
+                buyer,
+                seller,
+                delivered: 30,
+                quantity: 50,
+                amount: 3_000_000,
+                protocol_fee: 15_000,
+                refunded: 2_010_000,
+                oracle_count: 2,
+                sequence: 3,
+            })],
+        ),
+        transaction(4, &[refunded(4, escrows[1], buyer, 1_005_000)]),
+    ];
+    for transaction in &transactions {
+        store.index(transaction).unwrap();
+    }
+
+    // A partial settlement trades only the delivered items
+    let trades = store.trade_history(&buyer).unwrap();
+    assert_eq!(
+        trades
+            .iter()
+            .map(|trade| (trade.item.clone(), trade.amount, trade.status.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                TradedItem::Commodity {
+                    class: case(),
+                    quantity: 30
+                },
+                3_000_000,
+                TradeStatus::Settled
+            ),
+            (
+                TradedItem::Commodity {
+                    class: case(),
+                    quantity: 10
+                },
+                1_000_000,
+                TradeStatus::Refunded {
+                    reason: RefundReason::DeadlineExpired
+                }
+            ),
+        ]
+    );
+    assert_eq!(
+        store.volume_by_mint().unwrap(),
+        vec![MintVolume {
+            mint,
+            trades: 1,
+            volume: 3_000_000,
+            fees: 15_000,
+        }]
+    );
+    assert_eq!(store.fill_rates().unwrap()[0].rate(), Some(0.5));
+    assert!(store.missing_sequences().unwrap().is_empty());
+}
+
+#[test]
+fn amounts_above_i64_max_round_trip() {
+    let buyer = Pubkey::new_unique();
+    let seller = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let escrows = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut store = Store::in_memory().unwrap();
+
+    store
+        .index(&transaction(
+            1,
+            &[
+                locked(1, escrows[0], buyer, seller, mint, u64::MAX),
+                settled(2, escrows[0], buyer, seller, u64::MAX),
+            ],
+        ))
+        .unwrap();
+    let trades = store.trade_history(&buyer).unwrap();
+    assert_eq!(trades[0].amount, u64::MAX);
+    assert_eq!(trades[0].fee, u64::MAX / 200);
+    assert_eq!(
+        store.volume_by_mint().unwrap(),
+        vec![MintVolume {
+            mint,
+            trades: 1,
+            volume: u64::MAX,
+            fees: u64::MAX / 200,
+        }]
+    );
+
+    // A second trade still indexes, but its volume no longer fits a u64
+    store
+        .index(&transaction(
+            2,
+            &[
+                locked(3, escrows[1], buyer, seller, mint, 1),
+                settled(4, escrows[1], buyer, seller, 1),
+            ],
+        ))
+        .unwrap();
+    assert_eq!(store.trade_history(&buyer).unwrap().len(), 2);
+    assert!(matches!(
+        store.volume_by_mint(),
+        Err(IndexerError::VolumeOverflow(overflowed)) if overflowed == mint
+    ));
+}
@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum OracleError {
+    #[error("failed to load oracle keypair: {0}")]
+    Keypair(String),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("malformed observation: {0}")]
+    Observation(#[from] serde_json::Error),
+
+    #[error("http server error: {0}")]
+    Server(String),
+}
\ No newline at end of file
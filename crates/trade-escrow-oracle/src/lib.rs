@@ -0,0 +1,19 @@
+//! Oracle signer for trade-escrow.
+//!
+//! Turns trade observations from a pluggable source into receipts signed by
+//! the oracle key, in the exact message formats the program checks, and
+//! serves them over HTTP for aggregators to collect.
+
+pub mod error;
+pub mod observation;
+pub mod receipt;
+pub mod server;
+pub mod signer;
+pub mod source;
+
+pub use error::OracleError;
+pub use observation::{ItemCriteria, TradeObservation};
+pub use receipt::{Receipt, ReceiptKind, ReceiptStore};
+pub use server::ReceiptServer;
+pub use signer::OracleSigner;
+pub use source::{FileFeedSource, MockSteamSource, ObservationSource};
\ No newline at end of file
@@ -0,0 +1,61 @@
+use clap::Parser;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use trade_escrow_oracle::{
+    FileFeedSource, MockSteamSource, ObservationSource, OracleSigner, ReceiptServer, ReceiptStore,
+};
+
+/// Sign trade observations as a trade-escrow oracle and serve the receipts
+#[derive(Parser)]
+#[command(name = "trade-escrow-oracle", version)]
+struct Args {
+    /// Oracle keypair file (Solana CLI JSON format)
+    #[arg(long)]
+    keypair: PathBuf,
+
+    /// Address to serve receipts on
+    #[arg(long, default_value = "127.0.0.1:8701")]
+    listen: String,
+
+    /// JSON-lines observation feed to tail
+    #[arg(long, conflicts_with = "mock", required_unless_present = "mock")]
+    feed: Option<PathBuf>,
+
+    /// JSON array of observations to replay through the mock Steam source
+    #[arg(long)]
+    mock: Option<PathBuf>,
+
+    /// How often to poll the source, in milliseconds
+    #[arg(long, default_value_t = 2000)]
+    poll_interval_ms: u64,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    let signer = OracleSigner::from_file(&args.keypair)?;
+    let mut source: Box<dyn ObservationSource> = match (&args.feed, &args.mock) {
+        (Some(feed), _) => Box::new(FileFeedSource::new(feed)),
+        (None, Some(mock)) => Box::new(MockSteamSource::from_file(mock)?),
+        (None, None) => unreachable!("clap requires a source"),
+    };
+
+    let store = ReceiptStore::new();
+    let server = ReceiptServer::bind(&args.listen, signer.pubkey(), store.clone())?;
+    println!(
+        "oracle {} serving receipts on {}",
+        signer.pubkey(),
+        args.listen
+    );
+    server.spawn();
+
+    loop {
+        match signer.attest(source.as_mut(), &store) {
+            Ok(0) => {}
+            Ok(signed) => println!("signed {} receipt(s)", signed),
+            Err(e) => eprintln!("failed to poll observations: {}", e),
+        }
+        thread::sleep(Duration::from_millis(args.poll_interval_ms));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use trade_escrow::state::{CommodityEscrow, Escrow, ItemClass, SwapEscrow, WearBounds};
+
+use crate::receipt::ReceiptKind;
+
+/// Something an oracle saw happen to a trade on Steam
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TradeObservation {
+    /// The item of a single-item escrow arrived in the buyer's inventory
+    ItemDelivered {
+        #[serde(with = "pubkey_string")]
+        escrow: Pubkey,
+        asset_id: u64,
+        #[serde(with = "pubkey_string")]
+        buyer: Pubkey,
+        /// Buy order terms the item was checked against, when the escrow fills one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        criteria: Option<ItemCriteria>,
+    },
+    /// `delivered` of the `quantity` items of a commodity escrow arrived
+    CommodityDelivered {
+        #[serde(with = "pubkey_string")]
+        escrow: Pubkey,
+        appid: u32,
+        market_hash_name: String,
+        quantity: u32,
+        delivered: u32,
+        #[serde(with = "pubkey_string")]
+        buyer: Pubkey,
+    },
+    /// Both sides of a swap delivered their items
+    SwapCompleted {
+        #[serde(with = "pubkey_string")]
+        swap: Pubkey,
+        maker_items: Vec<u64>,
+        taker_items: Vec<u64>,
+    },
+    /// Either side of a swap failed to deliver
+    SwapFailed {
+        #[serde(with = "pubkey_string")]
+        swap: Pubkey,
+    },
+}
+
+/// Class and wear range of a buy order, as an oracle checked a delivered item
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemCriteria {
+    pub appid: u32,
+    pub market_hash_name: String,
+    /// Accepted `(min, max)` wear on the program's wear scale, if the order set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wear_bounds: Option<(u32, u32)>,
+}
+
+impl TradeObservation {
+    /// Escrow or swap account the observation is about
+    pub fn target(&self) -> Pubkey {
+        match self {
+            TradeObservation::ItemDelivered { escrow, .. }
+            | TradeObservation::CommodityDelivered { escrow, .. } => *escrow,
+            TradeObservation::SwapCompleted { swap, .. }
+            | TradeObservation::SwapFailed { swap } => *swap,
+        }
+    }
+
+    pub fn receipt_kind(&self) -> ReceiptKind {
+        match self {
+            TradeObservation::SwapFailed { .. } => ReceiptKind::Failure,
+            _ => ReceiptKind::Settlement,
+        }
+    }
+
+    /// Message the program expects the oracles to have signed.
+    ///
+    /// Built with the program's own formatting so the two cannot drift.
+    pub fn message(&self) -> String {
+        match self {
+            TradeObservation::ItemDelivered {
+                escrow,
+                asset_id,
+                buyer,
+                criteria,
+            } => Escrow {
+                asset_id: *asset_id,
+                buyer: *buyer,
+                item_class: criteria
+                    .as_ref()
+                    .map(|criteria| item_class(criteria.appid, &criteria.market_hash_name)),
+                wear_bounds: criteria
+                    .as_ref()
+                    .and_then(|criteria| criteria.wear_bounds)
+                    .map(|(min, max)| WearBounds { min, max }),
+                ..Default::default()
+            }
+            .settlement_message(escrow),
+            TradeObservation::CommodityDelivered {
+                escrow,
+                appid,
+                market_hash_name,
+                quantity,
+                delivered,
+                buyer,
+            } => CommodityEscrow {
+                commodity: item_class(*appid, market_hash_name),
+                quantity: *quantity,
+                buyer: *buyer,
+                ..Default::default()
+            }
+            .settlement_message(escrow, *delivered),
+            TradeObservation::SwapCompleted {
+                swap,
+                maker_items,
+                taker_items,
+            } => SwapEscrow {
+                maker_items: maker_items.clone(),
+                taker_items: taker_items.clone(),
+                ..Default::default()
+            }
+            .settlement_message(swap),
+            TradeObservation::SwapFailed { swap } => SwapEscrow::default().failure_message(swap),
+        }
+    }
+}
+
+/// Item class of a Steam market item, as the program keys commodities
+pub fn item_class(appid: u32, market_hash_name: &str) -> ItemClass {
+    ItemClass {
+        appid,
+        market_hash_name_hash: Sha256::digest(market_hash_name.as_bytes()).into(),
+    }
+}
+
+/// Base58 strings for pubkeys in JSON
+pub(crate) mod pubkey_string {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Pubkey::from_str(&value).map_err(D::Error::custom)
+    }
+}
\ No newline at end of file
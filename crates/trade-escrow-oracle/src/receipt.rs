@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::observation::pubkey_string;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptKind {
+    /// Attests delivery; accepted by `settle`, `settle_commodity` and `settle_swap`
+    Settlement,
+    /// Attests a failed delivery; accepted by `refund_swap` before the deadline
+    Failure,
+}
+
+/// A message signed by one oracle about one escrow or swap
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub kind: ReceiptKind,
+    /// Escrow or swap account the receipt is for
+    #[serde(with = "pubkey_string")]
+    pub target: Pubkey,
+    pub message: String,
+    #[serde(with = "pubkey_string")]
+    pub oracle: Pubkey,
+    #[serde(with = "signature_string")]
+    pub signature: Signature,
+}
+
+impl Receipt {
+    /// Whether the signature is the oracle's over the message
+    pub fn verify(&self) -> bool {
+        self.signature
+            .verify(self.oracle.as_ref(), self.message.as_bytes())
+    }
+
+    /// Signature as passed to the program's `oracle_signatures`
+    pub fn signature_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(self.signature.as_ref());
+        bytes
+    }
+}
+
+/// Latest receipt per escrow or swap, shared between the signer and the server
+#[derive(Clone, Default)]
+pub struct ReceiptStore {
+    receipts: Arc<RwLock<HashMap<Pubkey, Receipt>>>,
+}
+
+impl ReceiptStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, receipt: Receipt) {
+        self.receipts
+            .write()
+            .unwrap()
+            .insert(receipt.target, receipt);
+    }
+
+    pub fn get(&self, target: &Pubkey) -> Option<Receipt> {
+        self.receipts.read().unwrap().get(target).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.receipts.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+mod signature_string {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use solana_sdk::signature::Signature;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(
+        signature: &Signature,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(signature)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Signature, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Signature::from_str(&value).map_err(D::Error::custom)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,83 @@
+use solana_sdk::pubkey::Pubkey;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::error::OracleError;
+use crate::receipt::ReceiptStore;
+
+/// HTTP endpoint serving an oracle's receipts.
+///
+/// - `GET /health`
+/// - `GET /oracle` — the oracle's public key
+/// - `GET /receipts/<escrow or swap>` — the latest receipt, or 404
+pub struct ReceiptServer {
+    server: Server,
+    oracle: Pubkey,
+    store: ReceiptStore,
+}
+
+impl ReceiptServer {
+    pub fn bind(addr: &str, oracle: Pubkey, store: ReceiptStore) -> Result<Self, OracleError> {
+        let server = Server::http(addr).map_err(|e| OracleError::Server(e.to_string()))?;
+        Ok(Self {
+            server,
+            oracle,
+            store,
+        })
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Serve requests until the process exits
+    pub fn run(self) {
+        for request in self.server.incoming_requests() {
+            let response = self.route(&request);
+            let _ = request.respond(response);
+        }
+    }
+
+    pub fn spawn(self) -> JoinHandle<()> {
+        thread::spawn(move || self.run())
+    }
+
+    fn route(&self, request: &Request) -> Response<Cursor<Vec<u8>>> {
+        if request.method() != &Method::Get {
+            return text(405, "method not allowed");
+        }
+
+        let path = request.url().split('?').next().unwrap_or_default();
+        match path.trim_end_matches('/') {
+            "/health" => text(200, "ok"),
+            "/oracle" => json(
+                200,
+                &serde_json::json!({ "pubkey": self.oracle.to_string() }),
+            ),
+            path => match path.strip_prefix("/receipts/") {
+                Some(target) => match Pubkey::from_str(target) {
+                    Ok(target) => match self.store.get(&target) {
+                        Some(receipt) => json(200, &receipt),
+                        None => text(404, "no receipt"),
+                    },
+                    Err(_) => text(400, "invalid pubkey"),
+                },
+                None => text(404, "not found"),
+            },
+        }
+    }
+}
+
+fn text(status: u16, body: &str) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(body).with_status_code(status)
+}
+
+fn json(status: u16, body: &impl serde::Serialize) -> Response<Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    Response::from_data(serde_json::to_vec(body).unwrap_or_default())
+        .with_status_code(status)
+        .with_header(content_type)
+}
\ No newline at end of file
@@ -0,0 +1,56 @@
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use std::path::Path;
+
+use crate::error::OracleError;
+use crate::observation::TradeObservation;
+use crate::receipt::{Receipt, ReceiptStore};
+use crate::source::ObservationSource;
+
+/// Holds an oracle key and signs receipts with it
+pub struct OracleSigner {
+    keypair: Keypair,
+}
+
+impl OracleSigner {
+    pub fn new(keypair: Keypair) -> Self {
+        Self { keypair }
+    }
+
+    /// Load a keypair file in the Solana CLI's JSON format
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, OracleError> {
+        read_keypair_file(path)
+            .map(Self::new)
+            .map_err(|e| OracleError::Keypair(e.to_string()))
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn sign(&self, observation: &TradeObservation) -> Receipt {
+        let message = observation.message();
+        Receipt {
+            kind: observation.receipt_kind(),
+            target: observation.target(),
+            signature: self.keypair.sign_message(message.as_bytes()),
+            message,
+            oracle: self.pubkey(),
+        }
+    }
+
+    /// Sign everything `source` observed since the last poll into `store`.
+    ///
+    /// Returns the number of receipts signed.
+    pub fn attest(
+        &self,
+        source: &mut dyn ObservationSource,
+        store: &ReceiptStore,
+    ) -> Result<usize, OracleError> {
+        let observations = source.poll()?;
+        for observation in observations.iter() {
+            store.insert(self.sign(observation));
+        }
+        Ok(observations.len())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::OracleError;
+use crate::observation::TradeObservation;
+
+/// Where trade observations come from
+pub trait ObservationSource: Send {
+    /// Observations made since the last poll
+    fn poll(&mut self) -> Result<Vec<TradeObservation>, OracleError>;
+}
+
+/// Local stand-in for Steam, fed by tests or a JSON file.
+///
+/// Clones share one queue, so a test can keep a handle and push
+/// observations after the source has been handed to the service.
+#[derive(Clone, Default)]
+pub struct MockSteamSource {
+    pending: Arc<Mutex<VecDeque<TradeObservation>>>,
+}
+
+impl MockSteamSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Source preloaded with a JSON array of observations
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, OracleError> {
+        let observations: Vec<TradeObservation> =
+            serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        let source = Self::new();
+        for observation in observations {
+            source.push(observation);
+        }
+        Ok(source)
+    }
+
+    pub fn push(&self, observation: TradeObservation) {
+        self.pending.lock().unwrap().push_back(observation);
+    }
+}
+
+impl ObservationSource for MockSteamSource {
+    fn poll(&mut self) -> Result<Vec<TradeObservation>, OracleError> {
+        Ok(self.pending.lock().unwrap().drain(..).collect())
+    }
+}
+
+/// Tails a file of JSON lines, one observation per line, written by a watcher.
+///
+/// Lines that do not parse are logged and skipped.
+pub struct FileFeedSource {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl FileFeedSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            offset: 0,
+        }
+    }
+}
+
+impl ObservationSource for FileFeedSource {
+    fn poll(&mut self) -> Result<Vec<TradeObservation>, OracleError> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        file.seek(SeekFrom::Start(self.offset))?;
+
+        let mut reader = BufReader::new(file);
+        let mut observations = vec![];
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            // Leave a partially written last line for the next poll
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            self.offset += read as u64;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // The offset is already past this line, so failing here would lose the batch
+            match serde_json::from_str(&line) {
+                Ok(observation) => observations.push(observation),
+                Err(e) => eprintln!(
+                    "skipping malformed observation in {}: {}",
+                    self.path.display(),
+                    e
+                ),
+            }
+        }
+        Ok(observations)
+    }
+}
\ No newline at end of file
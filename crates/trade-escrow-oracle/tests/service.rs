@@ -0,0 +1,117 @@
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use std::io::Write;
+use trade_escrow::state::{Escrow, SwapEscrow};
+use trade_escrow_oracle::{
+    FileFeedSource, MockSteamSource, ObservationSource, OracleSigner, Receipt, ReceiptKind,
+    ReceiptServer, ReceiptStore, TradeObservation,
+};
+
+#[test]
+fn serves_signed_receipts_from_mock_steam() {
+    let signer = OracleSigner::new(Keypair::new());
+    let store = ReceiptStore::new();
+    let server = ReceiptServer::bind("127.0.0.1:0", signer.pubkey(), store.clone()).unwrap();
+    let base = format!("http://{}", server.local_addr().unwrap());
+    server.spawn();
+
+    let escrow = Pubkey::new_unique();
+    let buyer = Pubkey::new_unique();
+    let swap = Pubkey::new_unique();
+    let steam = MockSteamSource::new();
+    steam.push(TradeObservation::ItemDelivered {
+        escrow,
+        asset_id: 42,
+        buyer,
+        criteria: None,
+    });
+    steam.push(TradeObservation::SwapFailed { swap });
+
+    let mut source = steam.clone();
+    assert_eq!(signer.attest(&mut source, &store).unwrap(), 2);
+    assert_eq!(signer.attest(&mut source, &store).unwrap(), 0);
+
+    let receipt: Receipt = ureq::get(&format!("{}/receipts/{}", base, escrow))
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let expected = Escrow {
+        asset_id: 42,
+        buyer,
+        ..Default::default()
+    }
+    .settlement_message(&escrow);
+    assert_eq!(receipt.kind, ReceiptKind::Settlement);
+    assert_eq!(receipt.message, expected);
+    assert_eq!(receipt.oracle, signer.pubkey());
+    assert!(receipt.verify());
+
+    let receipt: Receipt = ureq::get(&format!("{}/receipts/{}", base, swap))
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(receipt.kind, ReceiptKind::Failure);
+    assert_eq!(
+        receipt.message,
+        SwapEscrow::default().failure_message(&swap)
+    );
+
+    let missing = ureq::get(&format!("{}/receipts/{}", base, Pubkey::new_unique())).call();
+    assert!(matches!(missing, Err(ureq::Error::Status(404, _))));
+}
+
+#[test]
+fn file_feed_skips_partial_lines() {
+    let path = std::env::temp_dir().join(format!("oracle-feed-{}.jsonl", Pubkey::new_unique()));
+    let swap = Pubkey::new_unique();
+    let line = serde_json::to_string(&TradeObservation::SwapFailed { swap }).unwrap();
+
+    let mut file = std::fs::File::create(&path).unwrap();
+    write!(file, "{}\n{}", line, &line[..10]).unwrap();
+
+    let mut source = FileFeedSource::new(&path);
+    assert_eq!(
+        source.poll().unwrap(),
+        vec![TradeObservation::SwapFailed { swap }]
+    );
+    assert!(source.poll().unwrap().is_empty());
+
+    writeln!(file, "{}", &line[10..]).unwrap();
+    assert_eq!(
+        source.poll().unwrap(),
+        vec![TradeObservation::SwapFailed { swap }]
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn file_feed_skips_malformed_lines() {
+    let path = std::env::temp_dir().join(format!("oracle-feed-{}.jsonl", Pubkey::new_unique()));
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    let line = |swap| serde_json::to_string(&TradeObservation::SwapFailed { swap }).unwrap();
+
+    let mut file = std::fs::File::create(&path).unwrap();
+    writeln!(
+        file,
+        "{}\n{{\"not\": \"an observation\"}}\n{}",
+        line(first),
+        line(second)
+    )
+    .unwrap();
+
+    let mut source = FileFeedSource::new(&path);
+    assert_eq!(
+        source.poll().unwrap(),
+        vec![
+            TradeObservation::SwapFailed { swap: first },
+            TradeObservation::SwapFailed { swap: second }
+        ]
+    );
+    assert!(source.poll().unwrap().is_empty());
+
+    std::fs::remove_file(path).unwrap();
+}
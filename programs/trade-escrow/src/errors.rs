@@ -41,6 +41,126 @@ pub enum TradeEscrowError {
     #[msg("Invalid signature format")]
     InvalidSignatureFormat,
     
-    #[msg("Signature verification failed")]
-    SignatureVerificationFailed,
+    #[msg("Trade amount exceeds the per-trade maximum")]
+    TradeLimitExceeded,
+    
+    #[msg("Trade would exceed the protocol-wide locked value ceiling")]
+    TvlLimitExceeded,
+    
+    #[msg("Trade would exceed the buyer's limit for the current window")]
+    UserLimitExceeded,
+    
+    #[msg("Invalid exposure limit parameters")]
+    InvalidLimits,
+    
+    #[msg("Unauthorized fee withdrawal")]
+    UnauthorizedFeeWithdrawal,
+    
+    #[msg("No fees to withdraw")]
+    NoFeesToWithdraw,
+    
+    #[msg("Invalid fee parameters")]
+    InvalidFeeBps,
+    
+    #[msg("Invalid referrer")]
+    InvalidReferrer,
+    
+    #[msg("Referrer token account missing or invalid")]
+    InvalidReferrerAccount,
+    
+    #[msg("Invalid listing parameters")]
+    InvalidListing,
+    
+    #[msg("Listing is not open")]
+    ListingNotOpen,
+    
+    #[msg("Listing has expired")]
+    ListingExpired,
+    
+    #[msg("Buyer is not allowed to fill this order")]
+    BuyerNotAllowed,
+    
+    #[msg("Unauthorized seller")]
+    UnauthorizedSeller,
+    
+    #[msg("Invalid buy order parameters")]
+    InvalidBuyOrder,
+    
+    #[msg("Buy order is already filled")]
+    BuyOrderFilled,
+    
+    #[msg("Buy order has expired")]
+    BuyOrderExpired,
+    
+    #[msg("Item does not match the order's criteria")]
+    ItemDoesNotMatch,
+    
+    #[msg("Unauthorized buyer")]
+    UnauthorizedBuyer,
+    
+    #[msg("Invalid auction parameters")]
+    InvalidAuction,
+    
+    #[msg("Auction has ended")]
+    AuctionEnded,
+    
+    #[msg("Auction has not ended yet")]
+    AuctionNotEnded,
+    
+    #[msg("Auction already has bids")]
+    AuctionHasBids,
+    
+    #[msg("Bid is below the reserve price or minimum increment")]
+    BidTooLow,
+    
+    #[msg("Auction has no winning bid")]
+    NoWinningBid,
+    
+    #[msg("Previous bidder token account missing or invalid")]
+    InvalidPreviousBidderAccount,
+    
+    #[msg("Invalid swap parameters")]
+    InvalidSwap,
+    
+    #[msg("Swap is not in the required state")]
+    InvalidSwapState,
+    
+    #[msg("Swap offer has expired")]
+    SwapExpired,
+    
+    #[msg("Unauthorized taker")]
+    UnauthorizedTaker,
+    
+    #[msg("Invalid commodity quantity")]
+    InvalidQuantity,
+    
+    #[msg("Unauthorized price publisher")]
+    UnauthorizedPricePublisher,
+    
+    #[msg("Invalid reference price")]
+    InvalidReferencePrice,
+    
+    #[msg("Price deviates from the reference price beyond the allowed band")]
+    PriceOutsideReferenceBand,
+    
+    #[msg("Missing or invalid price feed")]
+    InvalidPriceFeed,
+    
+    #[msg("Price feed is stale")]
+    StalePriceFeed,
+    
+    #[msg("Price feed confidence interval is too wide")]
+    PriceFeedUncertain,
+    
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    
+    #[msg("Receipt tree is full")]
+    ReceiptTreeFull,
+    
+    #[msg("Reference price is stale")]
+    StaleReferencePrice,
+    
+    #[msg("Listing is still open")]
+    ListingStillOpen,
 }
\ No newline at end of file
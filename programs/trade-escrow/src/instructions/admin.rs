@@ -48,6 +48,66 @@ pub struct UpdateOracles<'info> {
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateLimits<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = admin.key() == config.admin @ TradeEscrowError::UnauthorizedAdmin
+    )]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFees<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = admin.key() == config.admin @ TradeEscrowError::UnauthorizedAdmin
+    )]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = admin.key() == config.admin @ TradeEscrowError::UnauthorizedAdmin
+    )]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceChecks<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = admin.key() == config.admin @ TradeEscrowError::UnauthorizedAdmin
+    )]
+    pub admin: Signer<'info>,
+}
+
 pub fn pause(ctx: Context<Pause>) -> Result<()> {
     let config = &mut ctx.accounts.config;
     config.paused = true;
@@ -55,6 +115,7 @@ pub fn pause(ctx: Context<Pause>) -> Result<()> {
     emit!(EmergencyPause {
         triggered_by: ctx.accounts.guardian.key(),
         timestamp: Clock::get()?.unix_timestamp,
+        sequence: config.next_event_sequence()?,
     });
 
     Ok(())
@@ -66,8 +127,9 @@ pub fn unpause(ctx: Context<Unpause>) -> Result<()> {
 
     emit!(ConfigUpdated {
         updated_by: ctx.accounts.guardian.key(),
-        change_type: "unpause".to_string(),
+        change: ConfigChange::Unpause,
         timestamp: Clock::get()?.unix_timestamp,
+        sequence: config.next_event_sequence()?,
     });
 
     Ok(())
@@ -78,12 +140,132 @@ pub fn update_oracles(
     new_oracles: [Pubkey; 3],
 ) -> Result<()> {
     let config = &mut ctx.accounts.config;
+    let old = config.oracle_pubkeys;
     config.oracle_pubkeys = new_oracles;
 
     emit!(ConfigUpdated {
         updated_by: ctx.accounts.admin.key(),
-        change_type: "oracle_update".to_string(),
+        change: ConfigChange::Oracles { old, new: new_oracles },
+        timestamp: Clock::get()?.unix_timestamp,
+        sequence: config.next_event_sequence()?,
+    });
+
+    Ok(())
+}
+
+pub fn update_limits(
+    ctx: Context<UpdateLimits>,
+    max_tvl: u64,
+    max_trade_amount: u64,
+    user_limit_amount: u64,
+    user_limit_window: i64,
+) -> Result<()> {
+    require!(
+        (0..=MAX_USER_LIMIT_WINDOW).contains(&user_limit_window),
+        TradeEscrowError::InvalidLimits
+    );
+
+    let config = &mut ctx.accounts.config;
+    let old = config.limit_settings();
+    config.max_tvl = max_tvl;
+    config.max_trade_amount = max_trade_amount;
+    config.user_limit_amount = user_limit_amount;
+    config.user_limit_window = user_limit_window;
+
+    emit!(ConfigUpdated {
+        updated_by: ctx.accounts.admin.key(),
+        change: ConfigChange::Limits { old, new: config.limit_settings() },
+        timestamp: Clock::get()?.unix_timestamp,
+        sequence: config.next_event_sequence()?,
+    });
+
+    Ok(())
+}
+
+pub fn update_fees(
+    ctx: Context<UpdateFees>,
+    fee_bps: u16,
+    referral_share_bps: u16,
+    fee_payer: FeePayer,
+) -> Result<()> {
+    require!(
+        fee_bps <= 10000 && referral_share_bps <= 10000,
+        TradeEscrowError::InvalidFeeBps
+    );
+
+    let config = &mut ctx.accounts.config;
+    let old = config.fee_settings();
+    config.fee_bps = fee_bps;
+    config.referral_share_bps = referral_share_bps;
+    config.fee_payer = fee_payer;
+
+    emit!(ConfigUpdated {
+        updated_by: ctx.accounts.admin.key(),
+        change: ConfigChange::Fees { old, new: config.fee_settings() },
+        timestamp: Clock::get()?.unix_timestamp,
+        sequence: config.next_event_sequence()?,
+    });
+
+    Ok(())
+}
+
+pub fn update_fee_schedule(
+    ctx: Context<UpdateFeeSchedule>,
+    price_tiers: [PriceTier; FEE_TIER_COUNT],
+    volume_tiers: [VolumeTier; FEE_TIER_COUNT],
+) -> Result<()> {
+    require!(
+        price_tiers.iter().all(|tier| tier.fee_bps <= 10000)
+            && volume_tiers.iter().all(|tier| tier.discount_bps <= 10000),
+        TradeEscrowError::InvalidFeeBps
+    );
+
+    let config = &mut ctx.accounts.config;
+    let change = ConfigChange::FeeSchedule {
+        old_price_tiers: config.price_tiers,
+        new_price_tiers: price_tiers,
+        old_volume_tiers: config.volume_tiers,
+        new_volume_tiers: volume_tiers,
+    };
+    config.price_tiers = price_tiers;
+    config.volume_tiers = volume_tiers;
+
+    emit!(ConfigUpdated {
+        updated_by: ctx.accounts.admin.key(),
+        change,
+        timestamp: Clock::get()?.unix_timestamp,
+        sequence: config.next_event_sequence()?,
+    });
+
+    Ok(())
+}
+
+pub fn update_price_checks(
+    ctx: Context<UpdatePriceChecks>,
+    price_publisher: Pubkey,
+    price_band_bps: u16,
+    max_price_staleness: i64,
+    max_confidence_bps: u16,
+    max_reference_age: i64,
+) -> Result<()> {
+    require!(
+        max_price_staleness >= 0 && max_confidence_bps <= 10000 && max_reference_age >= 0,
+        TradeEscrowError::InvalidPriceFeed
+    );
+
+    let config = &mut ctx.accounts.config;
+    let old = config.price_check_settings();
+    config.price_publisher = price_publisher;
+    config.price_band_bps = price_band_bps;
+    config.max_price_staleness = max_price_staleness;
+    config.max_confidence_bps = max_confidence_bps;
+    config.max_reference_age = max_reference_age;
+
+    emit!(ConfigUpdated {
+        updated_by: ctx.accounts.admin.key(),
+        change: ConfigChange::PriceChecks { old, new: config.price_check_settings() },
         timestamp: Clock::get()?.unix_timestamp,
+        sequence: config.next_event_sequence()?,
     });
 
     Ok(())
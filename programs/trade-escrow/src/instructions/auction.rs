@@ -0,0 +1,495 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::*;
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(auction_id: u64)]
+pub struct CreateAuction<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = Auction::LEN,
+        seeds = [AUCTION_SEED, seller.key().as_ref(), &auction_id.to_le_bytes()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+
+    /// Vault holding the current high bid
+    #[account(
+        init,
+        payer = seller,
+        token::mint = mint,
+        token::authority = auction,
+        seeds = [AUCTION_VAULT_SEED, auction.key().as_ref()],
+        bump
+    )]
+    pub auction_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// Mint bids are made in
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(
+        mut,
+        seeds = [AUCTION_SEED, auction.seller.as_ref(), &auction.auction_id.to_le_bytes()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+
+    #[account(
+        mut,
+        seeds = [AUCTION_VAULT_SEED, auction.key().as_ref()],
+        bump
+    )]
+    pub auction_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Bidder's rolling limit tracker
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = UserStats::LEN,
+        seeds = [USER_STATS_SEED, bidder.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// Fee vault for the auction's mint, holding its minimum fee
+    #[account(
+        seeds = [FEE_VAULT_SEED, auction.mint.as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Bidder's token account funding the bid
+    #[account(
+        mut,
+        constraint = bidder_token_account.owner == bidder.key(),
+        constraint = bidder_token_account.mint == auction.mint
+    )]
+    pub bidder_token_account: Account<'info, TokenAccount>,
+
+    /// Previous high bidder's token account, required once the auction has a bid
+    #[account(
+        mut,
+        constraint = previous_bidder_token_account.mint == auction.mint
+    )]
+    pub previous_bidder_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Previous high bidder's limit tracker, required once another bidder holds the high bid
+    #[account(mut)]
+    pub previous_bidder_stats: Option<Account<'info, UserStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeAuction<'info> {
+    #[account(
+        mut,
+        close = seller,
+        seeds = [AUCTION_SEED, auction.seller.as_ref(), &auction.auction_id.to_le_bytes()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+
+    #[account(
+        mut,
+        seeds = [AUCTION_VAULT_SEED, auction.key().as_ref()],
+        bump
+    )]
+    pub auction_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Escrow::LEN,
+        seeds = [
+            ESCROW_SEED,
+            winner.key().as_ref(),
+            auction.seller.as_ref(),
+            &auction.asset_id.to_le_bytes(),
+            &Clock::get()?.unix_timestamp.to_le_bytes(), // Use timestamp as nonce
+        ],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// CHECK: Must be the auction's high bidder
+    #[account(
+        constraint = auction.highest_bidder == Some(winner.key()) @ TradeEscrowError::NoWinningBid
+    )]
+    pub winner: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the rent of the closed auction and its vault
+    #[account(
+        mut,
+        constraint = seller.key() == auction.seller @ TradeEscrowError::UnauthorizedSeller
+    )]
+    pub seller: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = mint.key() == auction.mint @ TradeEscrowError::InvalidAuction
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// Escrow token account (PDA)
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Anyone may finalize once bidding has closed
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller @ TradeEscrowError::UnauthorizedSeller,
+        constraint = auction.highest_bidder.is_none() @ TradeEscrowError::AuctionHasBids
+    )]
+    pub auction: Account<'info, Auction>,
+
+    #[account(
+        mut,
+        seeds = [AUCTION_VAULT_SEED, auction.key().as_ref()],
+        bump
+    )]
+    pub auction_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_auction(
+    ctx: Context<CreateAuction>,
+    auction_id: u64,
+    asset_id: u64,
+    reserve_price: u64,
+    min_increment: u64,
+    end_time: i64,
+    extension_window: i64,
+    delivery_window: i64,
+    fee_payer: Option<FeePayer>,
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    require!(!config.paused, TradeEscrowError::ContractPaused);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        asset_id > 0 && reserve_price > 0 && min_increment > 0 && end_time > now,
+        TradeEscrowError::InvalidAuction
+    );
+    require!(extension_window >= 0, TradeEscrowError::InvalidAuction);
+    require!(
+        delivery_window > 0 && delivery_window <= 600,
+        TradeEscrowError::InvalidDeadline
+    );
+
+    let auction = &mut ctx.accounts.auction;
+    auction.seller = ctx.accounts.seller.key();
+    auction.auction_id = auction_id;
+    auction.asset_id = asset_id;
+    auction.mint = ctx.accounts.mint.key();
+    auction.reserve_price = reserve_price;
+    auction.min_increment = min_increment;
+    auction.end_time = end_time;
+    auction.extension_window = extension_window;
+    auction.delivery_window = delivery_window;
+    auction.fee_payer = fee_payer.unwrap_or(config.fee_payer);
+    auction.highest_bidder = None;
+    auction.highest_bid = 0;
+    auction.highest_fee = 0;
+    auction.highest_fee_bps = 0;
+    auction.highest_deposit = 0;
+    auction.highest_bid_at = 0;
+    auction.bump = ctx.bumps.auction;
+
+    emit!(AuctionCreated {
+        auction: auction.key(),
+        seller: auction.seller,
+        asset_id,
+        mint: auction.mint,
+        reserve_price,
+        end_time,
+    });
+
+    Ok(())
+}
+
+pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+    require!(!ctx.accounts.config.paused, TradeEscrowError::ContractPaused);
+
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &ctx.accounts.auction;
+    let bidder = ctx.accounts.bidder.key();
+
+    require!(!auction.has_ended(now), TradeEscrowError::AuctionEnded);
+    require!(bidder != auction.seller, TradeEscrowError::InvalidAuction);
+    require!(amount >= auction.min_next_bid(), TradeEscrowError::BidTooLow);
+
+    let auction_id_bytes = auction.auction_id.to_le_bytes();
+    let bump = [auction.bump];
+    let seeds: &[&[u8]] = &[
+        AUCTION_SEED,
+        auction.seller.as_ref(),
+        &auction_id_bytes,
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+
+    // Refund the previous high bidder
+    if let Some(previous_bidder) = auction.highest_bidder {
+        let previous_bidder_token_account = ctx
+            .accounts
+            .previous_bidder_token_account
+            .as_ref()
+            .ok_or(TradeEscrowError::InvalidPreviousBidderAccount)?;
+        require!(
+            previous_bidder_token_account.owner == previous_bidder,
+            TradeEscrowError::InvalidPreviousBidderAccount
+        );
+
+        let refund_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.auction_vault.to_account_info(),
+                to: previous_bidder_token_account.to_account_info(),
+                authority: auction.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(refund_ctx, auction.highest_deposit)?;
+
+        // Their deposit no longer counts against the TVL or their own limit
+        let previous_bidder_stats = if previous_bidder == bidder {
+            require!(
+                ctx.accounts.previous_bidder_stats.is_none(),
+                TradeEscrowError::InvalidPreviousBidderAccount
+            );
+            &mut ctx.accounts.user_stats
+        } else {
+            let previous_bidder_stats = ctx
+                .accounts
+                .previous_bidder_stats
+                .as_mut()
+                .ok_or(TradeEscrowError::InvalidPreviousBidderAccount)?;
+            require!(
+                previous_bidder_stats.user == previous_bidder,
+                TradeEscrowError::InvalidPreviousBidderAccount
+            );
+            previous_bidder_stats
+        };
+        release_exposure(
+            &mut ctx.accounts.config,
+            previous_bidder_stats,
+            auction.highest_deposit,
+            auction.highest_bid_at,
+        )?;
+    }
+
+    // Quote the fee now so the winning deposit covers exactly what the escrow needs
+    let quote = reserve_exposure(
+        &mut ctx.accounts.config,
+        &mut ctx.accounts.user_stats,
+        &ctx.accounts.fee_vault,
+        bidder,
+        ctx.bumps.user_stats,
+        amount,
+        Some(auction.fee_payer),
+    )?;
+    require!(
+        ctx.accounts.bidder_token_account.amount >= quote.total_amount,
+        TradeEscrowError::InsufficientFunds
+    );
+
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.bidder_token_account.to_account_info(),
+            to: ctx.accounts.auction_vault.to_account_info(),
+            authority: ctx.accounts.bidder.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, quote.total_amount)?;
+
+    let auction = &mut ctx.accounts.auction;
+    auction.highest_bidder = Some(bidder);
+    auction.highest_bid = amount;
+    auction.highest_fee = quote.fee;
+    auction.highest_fee_bps = quote.fee_bps;
+    auction.highest_deposit = quote.total_amount;
+    auction.highest_bid_at = now;
+    auction.extend_for_bid(now);
+
+    emit!(BidPlaced {
+        auction: auction.key(),
+        bidder,
+        amount,
+        end_time: auction.end_time,
+    });
+
+    Ok(())
+}
+
+pub fn finalize_auction(ctx: Context<FinalizeAuction>) -> Result<()> {
+    let clock = Clock::get()?;
+    let auction = &ctx.accounts.auction;
+    require!(
+        auction.has_ended(clock.unix_timestamp),
+        TradeEscrowError::AuctionNotEnded
+    );
+
+    let auction_id_bytes = auction.auction_id.to_le_bytes();
+    let bump = [auction.bump];
+    let seeds: &[&[u8]] = &[
+        AUCTION_SEED,
+        auction.seller.as_ref(),
+        &auction_id_bytes,
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+
+    // Move the winning deposit into a regular trade escrow
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.auction_vault.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: auction.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, auction.highest_deposit)?;
+
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.auction_vault.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: auction.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::close_account(close_ctx)?;
+
+    // Exposure was already counted when the bid was placed
+    write_escrow(
+        &mut ctx.accounts.escrow,
+        &mut ctx.accounts.config,
+        &EscrowTerms {
+            buyer: ctx.accounts.winner.key(),
+            seller: auction.seller,
+            mint: ctx.accounts.mint.key(),
+            asset_id: auction.asset_id,
+            amount: auction.highest_bid,
+            deadline: clock.unix_timestamp + auction.delivery_window,
+            locked_at: auction.highest_bid_at,
+            nonce: clock.unix_timestamp as u64,
+            referrer: None,
+            fee_payer: Some(auction.fee_payer),
+            usd_rate: None,
+            item_class: None,
+            wear_bounds: None,
+            escrow_bump: ctx.bumps.escrow,
+            user_stats_bump: 0,
+        },
+        &FeeQuote {
+            fee_payer: auction.fee_payer,
+            fee: auction.highest_fee,
+            fee_bps: auction.highest_fee_bps,
+            total_amount: auction.highest_deposit,
+        },
+    )?;
+
+    emit!(AuctionFinalized {
+        auction: auction.key(),
+        winner: ctx.accounts.winner.key(),
+        escrow_id: ctx.accounts.escrow.key(),
+        amount: auction.highest_bid,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+
+    let auction_id_bytes = auction.auction_id.to_le_bytes();
+    let bump = [auction.bump];
+    let seeds: &[&[u8]] = &[
+        AUCTION_SEED,
+        auction.seller.as_ref(),
+        &auction_id_bytes,
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.auction_vault.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: auction.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::close_account(close_ctx)?;
+
+    emit!(AuctionCancelled {
+        auction: auction.key(),
+        seller: auction.seller,
+    });
+
+    Ok(())
+}
\ No newline at end of file
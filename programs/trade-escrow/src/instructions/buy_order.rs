@@ -0,0 +1,422 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::*;
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct CreateBuyOrder<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = BuyOrder::LEN,
+        seeds = [BUY_ORDER_SEED, buyer.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub buy_order: Account<'info, BuyOrder>,
+
+    /// Vault holding the bid's remaining funds
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = buy_order,
+        seeds = [BUY_ORDER_VAULT_SEED, buy_order.key().as_ref()],
+        bump
+    )]
+    pub buy_order_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's stats, used to quote the fee and count the deposit
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [USER_STATS_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// Fee vault for the bid's mint, holding its minimum fee
+    #[account(
+        seeds = [FEE_VAULT_SEED, mint.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Buyer's token account funding the bid
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key(),
+        constraint = buyer_token_account.mint == mint.key()
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(asset_id: u64)]
+pub struct FillBuyOrder<'info> {
+    #[account(
+        mut,
+        seeds = [BUY_ORDER_SEED, buy_order.buyer.as_ref(), &buy_order.order_id.to_le_bytes()],
+        bump = buy_order.bump,
+        constraint = buy_order.remaining() > 0 @ TradeEscrowError::BuyOrderFilled
+    )]
+    pub buy_order: Account<'info, BuyOrder>,
+
+    #[account(
+        mut,
+        seeds = [BUY_ORDER_VAULT_SEED, buy_order.key().as_ref()],
+        bump
+    )]
+    pub buy_order_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = seller,
+        space = Escrow::LEN,
+        seeds = [
+            ESCROW_SEED,
+            buy_order.buyer.as_ref(),
+            seller.key().as_ref(),
+            &asset_id.to_le_bytes(),
+            &Clock::get()?.unix_timestamp.to_le_bytes(), // Use timestamp as nonce
+        ],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        constraint = mint.key() == buy_order.mint @ TradeEscrowError::InvalidBuyOrder
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// Escrow token account (PDA)
+    #[account(
+        init,
+        payer = seller,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBuyOrder<'info> {
+    #[account(
+        mut,
+        close = buyer,
+        has_one = buyer @ TradeEscrowError::UnauthorizedBuyer,
+        seeds = [BUY_ORDER_SEED, buy_order.buyer.as_ref(), &buy_order.order_id.to_le_bytes()],
+        bump = buy_order.bump
+    )]
+    pub buy_order: Account<'info, BuyOrder>,
+
+    #[account(
+        mut,
+        seeds = [BUY_ORDER_VAULT_SEED, buy_order.key().as_ref()],
+        bump
+    )]
+    pub buy_order_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's rolling limit tracker, released of the unfilled deposit
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, buyer.key().as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Buyer's token account receiving the remaining balance
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key(),
+        constraint = buyer_token_account.mint == buy_order.mint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_buy_order(
+    ctx: Context<CreateBuyOrder>,
+    order_id: u64,
+    item_class: ItemClass,
+    wear_bounds: Option<WearBounds>,
+    unit_price: u64,
+    quantity: u32,
+    expiry: i64,
+    fee_payer: Option<FeePayer>,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(!config.paused, TradeEscrowError::ContractPaused);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        unit_price > 0 && quantity > 0 && expiry > now,
+        TradeEscrowError::InvalidBuyOrder
+    );
+    require!(
+        wear_bounds.iter().all(WearBounds::is_valid),
+        TradeEscrowError::InvalidBuyOrder
+    );
+
+    // Quote one item now; every fill is charged exactly this fee
+    let user_stats = &mut ctx.accounts.user_stats;
+    let fee_vault = &ctx.accounts.fee_vault;
+    let quote = quote_fee(config, user_stats, fee_vault, unit_price, fee_payer, now)?;
+    require!(
+        !config.exceeds_trade_limit(quote.total_amount),
+        TradeEscrowError::TradeLimitExceeded
+    );
+    let deposit = quote.total_amount
+        .checked_mul(quantity as u64)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    // The whole deposit counts against the limits until it is filled or cancelled
+    count_exposure(
+        config,
+        user_stats,
+        ctx.accounts.buyer.key(),
+        ctx.bumps.user_stats,
+        deposit,
+        now,
+    )?;
+    require!(
+        ctx.accounts.buyer_token_account.amount >= deposit,
+        TradeEscrowError::InsufficientFunds
+    );
+
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.buyer_token_account.to_account_info(),
+            to: ctx.accounts.buy_order_vault.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, deposit)?;
+
+    let buy_order = &mut ctx.accounts.buy_order;
+    buy_order.buyer = ctx.accounts.buyer.key();
+    buy_order.order_id = order_id;
+    buy_order.mint = ctx.accounts.mint.key();
+    buy_order.item_class = item_class;
+    buy_order.wear_bounds = wear_bounds;
+    buy_order.unit_price = unit_price;
+    buy_order.quantity = quantity;
+    buy_order.filled = 0;
+    buy_order.expiry = expiry;
+    buy_order.fee_payer = quote.fee_payer;
+    buy_order.unit_fee = quote.fee;
+    buy_order.unit_fee_bps = quote.fee_bps;
+    buy_order.created_at = now;
+    buy_order.bump = ctx.bumps.buy_order;
+
+    emit!(BuyOrderCreated {
+        buy_order: buy_order.key(),
+        buyer: buy_order.buyer,
+        item_class,
+        unit_price,
+        quantity,
+        deposit,
+        expiry,
+    });
+
+    Ok(())
+}
+
+pub fn fill_buy_order(
+    ctx: Context<FillBuyOrder>,
+    asset_id: u64,
+    wear: Option<u32>,
+    deadline_offset: i64,
+) -> Result<()> {
+    require!(!ctx.accounts.config.paused, TradeEscrowError::ContractPaused);
+
+    // Verify deadline is reasonable (max 10 minutes)
+    require!(
+        deadline_offset > 0 && deadline_offset <= 600,
+        TradeEscrowError::InvalidDeadline
+    );
+    require!(asset_id > 0, TradeEscrowError::InvalidBuyOrder);
+
+    let clock = Clock::get()?;
+    let buy_order = &ctx.accounts.buy_order;
+
+    require!(
+        !buy_order.is_expired(clock.unix_timestamp),
+        TradeEscrowError::BuyOrderExpired
+    );
+    // The seller's declared wear is checked here; oracles attest the delivered
+    // item against the class and wear range copied onto the escrow
+    require!(
+        buy_order.accepts_wear(wear),
+        TradeEscrowError::ItemDoesNotMatch
+    );
+
+    // Exposure and the fee were reserved when the bid was created
+    let total_amount = buy_order.unit_deposit()?;
+    write_escrow(
+        &mut ctx.accounts.escrow,
+        &mut ctx.accounts.config,
+        &EscrowTerms {
+            buyer: buy_order.buyer,
+            seller: ctx.accounts.seller.key(),
+            mint: ctx.accounts.mint.key(),
+            asset_id,
+            amount: buy_order.unit_price,
+            deadline: clock.unix_timestamp + deadline_offset,
+            locked_at: buy_order.created_at,
+            nonce: clock.unix_timestamp as u64,
+            referrer: None,
+            fee_payer: Some(buy_order.fee_payer),
+            usd_rate: None,
+            item_class: Some(buy_order.item_class),
+            wear_bounds: buy_order.wear_bounds,
+            escrow_bump: ctx.bumps.escrow,
+            user_stats_bump: 0,
+        },
+        &FeeQuote {
+            fee_payer: buy_order.fee_payer,
+            fee: buy_order.unit_fee,
+            fee_bps: buy_order.unit_fee_bps,
+            total_amount,
+        },
+    )?;
+
+    // Move one item's worth from the bid vault into the new escrow
+    let order_id_bytes = buy_order.order_id.to_le_bytes();
+    let bump = [buy_order.bump];
+    let seeds: &[&[u8]] = &[
+        BUY_ORDER_SEED,
+        buy_order.buyer.as_ref(),
+        &order_id_bytes,
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.buy_order_vault.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: buy_order.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, total_amount)?;
+
+    let buy_order = &mut ctx.accounts.buy_order;
+    buy_order.filled += 1;
+
+    emit!(BuyOrderFilled {
+        buy_order: buy_order.key(),
+        escrow_id: ctx.accounts.escrow.key(),
+        seller: ctx.accounts.seller.key(),
+        asset_id,
+        remaining: buy_order.remaining(),
+    });
+
+    Ok(())
+}
+
+pub fn cancel_buy_order(ctx: Context<CancelBuyOrder>) -> Result<()> {
+    let buy_order = &ctx.accounts.buy_order;
+    let refunded = ctx.accounts.buy_order_vault.amount;
+
+    // Filled items moved their share to escrows; the rest no longer counts
+    release_exposure(
+        &mut ctx.accounts.config,
+        &mut ctx.accounts.user_stats,
+        buy_order.unfilled_deposit()?,
+        buy_order.created_at,
+    )?;
+
+    let order_id_bytes = buy_order.order_id.to_le_bytes();
+    let bump = [buy_order.bump];
+    let seeds: &[&[u8]] = &[
+        BUY_ORDER_SEED,
+        buy_order.buyer.as_ref(),
+        &order_id_bytes,
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+
+    // Return whatever is left, including unused fee reserve
+    if refunded > 0 {
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.buy_order_vault.to_account_info(),
+                to: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: buy_order.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, refunded)?;
+    }
+
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.buy_order_vault.to_account_info(),
+            destination: ctx.accounts.buyer.to_account_info(),
+            authority: buy_order.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::close_account(close_ctx)?;
+
+    emit!(BuyOrderCancelled {
+        buy_order: buy_order.key(),
+        buyer: buy_order.buyer,
+        unfilled: buy_order.remaining(),
+        refunded,
+    });
+
+    Ok(())
+}
\ No newline at end of file
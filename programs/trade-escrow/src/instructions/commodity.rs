@@ -0,0 +1,527 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::*;
+use crate::utils::*;
+use crate::*;
+
+#[derive(Accounts)]
+pub struct LockCommodity<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = CommodityEscrow::LEN,
+        seeds = [
+            COMMODITY_ESCROW_SEED,
+            buyer.key().as_ref(),
+            seller.key().as_ref(),
+            &Clock::get()?.unix_timestamp.to_le_bytes(), // Use timestamp as nonce
+        ],
+        bump
+    )]
+    pub escrow: Account<'info, CommodityEscrow>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's rolling limit tracker
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [USER_STATS_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: Seller pubkey verified through signature
+    pub seller: UncheckedAccount<'info>,
+
+    /// Mint of the payment token (USDC/SOL)
+    pub mint: Account<'info, Mint>,
+
+    /// Fee vault for the payment mint, holding its minimum fee
+    #[account(
+        seeds = [FEE_VAULT_SEED, mint.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Buyer's token account (USDC/SOL)
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key(),
+        constraint = buyer_token_account.mint == mint.key()
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// Escrow token account (PDA)
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Instructions sysvar, read for the Ed25519 verify instructions
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SettleCommodity<'info> {
+    #[account(
+        mut,
+        constraint = !escrow.settled @ TradeEscrowError::CannotSettle
+    )]
+    pub escrow: Account<'info, CommodityEscrow>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Escrow token account
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Seller's token account to receive payment for delivered items
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == escrow.seller,
+        constraint = seller_token_account.mint == escrow_token_account.mint
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+
+    /// Buyer's token account to receive the undelivered remainder
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == escrow.buyer,
+        constraint = buyer_token_account.mint == escrow_token_account.mint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// Protocol fee vault for the escrow's mint
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, escrow_token_account.mint.as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Token account the fee is deposited into
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_TOKEN_SEED, escrow_token_account.mint.as_ref()],
+        bump
+    )]
+    pub fee_vault_token_account: Account<'info, TokenAccount>,
+
+    /// Buyer's stats, credited with the delivered volume
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, escrow.buyer.as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    /// Tree the trade's receipt is appended to
+    #[account(
+        mut,
+        seeds = [RECEIPT_TREE_SEED, escrow_token_account.mint.as_ref()],
+        bump = receipt_tree.bump
+    )]
+    pub receipt_tree: Box<Account<'info, ReceiptTree>>,
+
+    /// CHECK: Instructions sysvar, read for the Ed25519 verify instructions
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RefundCommodity<'info> {
+    #[account(
+        mut,
+        constraint = !escrow.settled @ TradeEscrowError::CannotRefund,
+        constraint = escrow.buyer == buyer.key() @ TradeEscrowError::UnauthorizedRefund
+    )]
+    pub escrow: Account<'info, CommodityEscrow>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Escrow token account
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Buyer's token account to receive refund
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == escrow.buyer,
+        constraint = buyer_token_account.mint == escrow_token_account.mint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// Buyer's rolling limit tracker, released of the refunded funding
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, escrow.buyer.as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    /// Tree the trade's receipt is appended to
+    #[account(
+        mut,
+        seeds = [RECEIPT_TREE_SEED, escrow_token_account.mint.as_ref()],
+        bump = receipt_tree.bump
+    )]
+    pub receipt_tree: Box<Account<'info, ReceiptTree>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Pay `amount` out of a commodity escrow vault, skipping zero transfers
+fn pay_from_escrow<'info>(
+    token_program: &Program<'info, Token>,
+    escrow_token_account: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    escrow: &Account<'info, CommodityEscrow>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let nonce_bytes = escrow.nonce.to_le_bytes();
+    let bump = [escrow.bump];
+    let seeds: &[&[u8]] = &[
+        COMMODITY_ESCROW_SEED,
+        escrow.buyer.as_ref(),
+        escrow.seller.as_ref(),
+        &nonce_bytes,
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: escrow_token_account.to_account_info(),
+            to: to.to_account_info(),
+            authority: escrow.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, amount)
+}
+
+/// Message the seller signs for a commodity ask
+pub fn commodity_ask_message(
+    commodity: &ItemClass,
+    quantity: u32,
+    unit_price: u64,
+    seller: &Pubkey,
+    deadline: i64,
+    nonce: u64,
+    fee_payer_override: Option<FeePayer>,
+) -> String {
+    let mut message = format!(
+        "{}:{}:{}:{}:{}:{}",
+        commodity.identifier(),
+        quantity,
+        unit_price,
+        seller,
+        deadline,
+        nonce
+    );
+    if let Some(fee_payer) = fee_payer_override {
+        message.push(':');
+        message.push_str(fee_payer.as_str());
+    }
+    message
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn lock_commodity(
+    ctx: Context<LockCommodity>,
+    commodity: ItemClass,
+    quantity: u32,
+    unit_price: u64,
+    price_max: u64,
+    ask_signature: [u8; 64],
+    deadline_offset: i64,
+    fee_payer_override: Option<FeePayer>,
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    require!(!config.paused, TradeEscrowError::ContractPaused);
+    require!(
+        deadline_offset > 0 && deadline_offset <= 600,
+        TradeEscrowError::InvalidDeadline
+    );
+    require!(quantity > 0, TradeEscrowError::InvalidQuantity);
+    let amount = unit_price
+        .checked_mul(quantity as u64)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    let clock = Clock::get()?;
+    let deadline = clock.unix_timestamp + deadline_offset;
+    let nonce = clock.unix_timestamp as u64;
+
+    // Verify seller's ask signature over the commodity, quantity and unit price
+    let ask_message = commodity_ask_message(
+        &commodity,
+        quantity,
+        unit_price,
+        &ctx.accounts.seller.key(),
+        deadline,
+        nonce,
+        fee_payer_override,
+    );
+    require!(
+        verify_signature(
+            &ctx.accounts.instructions_sysvar,
+            &ask_signature,
+            ask_message.as_bytes(),
+            &ctx.accounts.seller.key()
+        )?,
+        TradeEscrowError::InvalidAskSignature
+    );
+
+    require!(amount <= price_max, TradeEscrowError::PriceExceedsMaximum);
+
+    let quote = reserve_exposure(
+        &mut ctx.accounts.config,
+        &mut ctx.accounts.user_stats,
+        &ctx.accounts.fee_vault,
+        ctx.accounts.buyer.key(),
+        ctx.bumps.user_stats,
+        amount,
+        fee_payer_override,
+    )?;
+    require!(
+        ctx.accounts.buyer_token_account.amount >= quote.total_amount,
+        TradeEscrowError::InsufficientFunds
+    );
+
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.buyer_token_account.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, quote.total_amount)?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.buyer = ctx.accounts.buyer.key();
+    escrow.seller = ctx.accounts.seller.key();
+    escrow.commodity = commodity;
+    escrow.quantity = quantity;
+    escrow.unit_price = unit_price;
+    escrow.deadline = deadline;
+    escrow.locked_at = clock.unix_timestamp;
+    escrow.settled = false;
+    escrow.nonce = nonce;
+    escrow.fee_payer = quote.fee_payer;
+    escrow.fee_amount = quote.fee;
+    escrow.fee_bps = quote.fee_bps;
+    escrow.bump = ctx.bumps.escrow;
+
+    emit!(CommodityEscrowLocked {
+        escrow_id: escrow.key(),
+        buyer: escrow.buyer,
+        seller: escrow.seller,
+        mint: ctx.accounts.mint.key(),
+        commodity,
+        quantity,
+        unit_price,
+        fee: quote.fee,
+        deadline,
+        sequence: ctx.accounts.config.next_event_sequence()?,
+    });
+
+    Ok(())
+}
+
+/// Pay the seller for `delivered` items and refund the rest to the buyer
+pub fn settle_commodity(
+    ctx: Context<SettleCommodity>,
+    delivered: u32,
+    oracle_signatures: Vec<[u8; 64]>,
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let escrow = &ctx.accounts.escrow;
+
+    require!(!config.paused, TradeEscrowError::ContractPaused);
+    require!(
+        !escrow.is_expired(Clock::get()?.unix_timestamp),
+        TradeEscrowError::CannotSettle
+    );
+    require!(delivered <= escrow.quantity, TradeEscrowError::InvalidQuantity);
+
+    // Oracles attest how many of the items were delivered
+    require!(
+        oracle_signatures.len() >= 2,
+        TradeEscrowError::InsufficientOracleSignatures
+    );
+    let valid_signatures = count_oracle_signatures(
+        &ctx.accounts.instructions_sysvar,
+        &config.oracle_pubkeys,
+        &oracle_signatures,
+        escrow.settlement_message(&escrow.key(), delivered).as_bytes(),
+    )?;
+    require!(
+        valid_signatures >= 2,
+        TradeEscrowError::InvalidOracleSignatures
+    );
+
+    let (seller_amount, protocol_fee, refund_amount) = escrow.settlement_amounts(delivered)?;
+
+    pay_from_escrow(
+        &ctx.accounts.token_program,
+        &ctx.accounts.escrow_token_account,
+        &ctx.accounts.seller_token_account,
+        escrow,
+        seller_amount,
+    )?;
+    pay_from_escrow(
+        &ctx.accounts.token_program,
+        &ctx.accounts.escrow_token_account,
+        &ctx.accounts.fee_vault_token_account,
+        escrow,
+        protocol_fee,
+    )?;
+    pay_from_escrow(
+        &ctx.accounts.token_program,
+        &ctx.accounts.escrow_token_account,
+        &ctx.accounts.buyer_token_account,
+        escrow,
+        refund_amount,
+    )?;
+
+    let fee_vault = &mut ctx.accounts.fee_vault;
+    fee_vault.total_collected = fee_vault.total_collected
+        .checked_add(protocol_fee)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    let delivered_amount = escrow.unit_price
+        .checked_mul(delivered as u64)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+    ctx.accounts
+        .user_stats
+        .record_volume(delivered_amount, Clock::get()?.unix_timestamp);
+
+    // Items never delivered no longer count against the buyer's limit
+    let config = &mut ctx.accounts.config;
+    release_exposure(config, &mut ctx.accounts.user_stats, refund_amount, escrow.locked_at)?;
+    config.total_locked = config.total_locked
+        .checked_sub(
+            escrow.locked_total()?
+                .checked_sub(refund_amount)
+                .ok_or(TradeEscrowError::ArithmeticOverflow)?,
+        )
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.settled = true;
+
+    emit!(CommodityEscrowSettled {
+        escrow_id: escrow.key(),
+        buyer: escrow.buyer,
+        seller: escrow.seller,
+        delivered,
+        quantity: escrow.quantity,
+        amount: seller_amount,
+        protocol_fee,
+        refunded: refund_amount,
+        oracle_count: valid_signatures,
+        sequence: config.next_event_sequence()?,
+    });
+
+    let receipt = escrow.receipt(
+        escrow.key(),
+        delivered,
+        TradeOutcome::Settled,
+        Clock::get()?.unix_timestamp,
+    )?;
+    append_receipt(&mut ctx.accounts.receipt_tree, receipt)?;
+
+    Ok(())
+}
+
+pub fn refund_commodity(ctx: Context<RefundCommodity>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    require!(
+        escrow.is_expired(Clock::get()?.unix_timestamp),
+        TradeEscrowError::CannotRefund
+    );
+
+    let refund_amount = escrow.locked_total()?;
+    pay_from_escrow(
+        &ctx.accounts.token_program,
+        &ctx.accounts.escrow_token_account,
+        &ctx.accounts.buyer_token_account,
+        escrow,
+        refund_amount,
+    )?;
+
+    // The trade never happened, so the funding no longer counts against the buyer's limit
+    let config = &mut ctx.accounts.config;
+    release_exposure(config, &mut ctx.accounts.user_stats, refund_amount, escrow.locked_at)?;
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.settled = true;
+
+    emit!(EscrowRefunded {
+        escrow_id: escrow.key(),
+        buyer: escrow.buyer,
+        amount: refund_amount,
+        reason: RefundReason::DeadlineExpired,
+        sequence: config.next_event_sequence()?,
+    });
+
+    let receipt = escrow.receipt(
+        escrow.key(),
+        escrow.quantity,
+        TradeOutcome::Refunded,
+        Clock::get()?.unix_timestamp,
+    )?;
+    append_receipt(&mut ctx.accounts.receipt_tree, receipt)?;
+
+    Ok(())
+}
\ No newline at end of file
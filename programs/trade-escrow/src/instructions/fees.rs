@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::*;
+use crate::*;
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = FeeVault::LEN,
+        seeds = [FEE_VAULT_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Token account holding the accumulated fees
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = fee_vault,
+        seeds = [FEE_VAULT_TOKEN_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault_token_account: Account<'info, TokenAccount>,
+
+    /// Tree the mint's settled and refunded trades leave receipts in
+    #[account(
+        init,
+        payer = admin,
+        space = ReceiptTree::LEN,
+        seeds = [RECEIPT_TREE_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub receipt_tree: Box<Account<'info, ReceiptTree>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        constraint = admin.key() == config.admin @ TradeEscrowError::UnauthorizedAdmin
+    )]
+    pub admin: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, fee_vault.mint.as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_TOKEN_SEED, fee_vault.mint.as_ref()],
+        bump
+    )]
+    pub fee_vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Admin, or anyone sweeping to the configured fee recipient
+    #[account(
+        constraint = authority.key() == config.admin
+            || recipient_token_account.owner == config.fee_recipient
+            @ TradeEscrowError::UnauthorizedFeeWithdrawal
+    )]
+    pub authority: Signer<'info>,
+
+    /// Token account receiving the fees
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == fee_vault.mint
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMinFee<'info> {
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, fee_vault.mint.as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = admin.key() == config.admin @ TradeEscrowError::UnauthorizedAdmin
+    )]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetAccruedFees<'info> {
+    #[account(
+        seeds = [FEE_VAULT_SEED, fee_vault.mint.as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        seeds = [FEE_VAULT_TOKEN_SEED, fee_vault.mint.as_ref()],
+        bump
+    )]
+    pub fee_vault_token_account: Account<'info, TokenAccount>,
+}
+
+pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+    let fee_vault = &mut ctx.accounts.fee_vault;
+    fee_vault.mint = ctx.accounts.mint.key();
+    fee_vault.total_collected = 0;
+    fee_vault.total_withdrawn = 0;
+    fee_vault.min_fee = 0;
+    fee_vault.bump = ctx.bumps.fee_vault;
+
+    let receipt_tree = &mut ctx.accounts.receipt_tree;
+    receipt_tree.mint = ctx.accounts.mint.key();
+    receipt_tree.count = 0;
+    receipt_tree.root = EMPTY_RECEIPT_SUBTREES[RECEIPT_TREE_DEPTH];
+    receipt_tree.bump = ctx.bumps.receipt_tree;
+
+    Ok(())
+}
+
+pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+    let fee_vault = &mut ctx.accounts.fee_vault;
+
+    // Only fees the program collected; tokens sent to the vault directly stay put
+    let amount = fee_vault.accrued().min(ctx.accounts.fee_vault_token_account.amount);
+    require!(amount > 0, TradeEscrowError::NoFeesToWithdraw);
+
+    // Create signer seeds for fee vault PDA
+    let bump = [fee_vault.bump];
+    let seeds: &[&[u8]] = &[FEE_VAULT_SEED, fee_vault.mint.as_ref(), &bump];
+    let signer_seeds = &[seeds];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.fee_vault_token_account.to_account_info(),
+            to: ctx.accounts.recipient_token_account.to_account_info(),
+            authority: fee_vault.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, amount)?;
+
+    fee_vault.total_withdrawn = fee_vault.total_withdrawn
+        .checked_add(amount)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    emit!(FeesWithdrawn {
+        mint: fee_vault.mint,
+        recipient: ctx.accounts.recipient_token_account.key(),
+        amount,
+        withdrawn_by: ctx.accounts.authority.key(),
+    });
+
+    Ok(())
+}
+
+pub fn update_min_fee(ctx: Context<UpdateMinFee>, min_fee: u64) -> Result<()> {
+    let fee_vault = &mut ctx.accounts.fee_vault;
+    let old = fee_vault.min_fee;
+    fee_vault.min_fee = min_fee;
+
+    emit!(ConfigUpdated {
+        updated_by: ctx.accounts.admin.key(),
+        change: ConfigChange::MinFee { mint: fee_vault.mint, old, new: min_fee },
+        timestamp: Clock::get()?.unix_timestamp,
+        sequence: ctx.accounts.config.next_event_sequence()?,
+    });
+
+    Ok(())
+}
+
+/// Fees collected and not yet withdrawn for the vault's mint (read via simulation)
+pub fn get_accrued_fees(ctx: Context<GetAccruedFees>) -> Result<u64> {
+    Ok(ctx.accounts.fee_vault.accrued().min(ctx.accounts.fee_vault_token_account.amount))
+}
\ No newline at end of file
@@ -37,6 +37,19 @@ pub fn initialize(
     config.admin = ctx.accounts.admin.key();
     config.fee_bps = 50; // 0.5% default fee
     config.fee_recipient = ctx.accounts.fee_recipient.key();
+    config.referral_share_bps = 0;
+    config.fee_payer = FeePayer::Buyer;
+    config.max_tvl = 0; // no ceiling until set by admin
+    config.max_trade_amount = 0;
+    config.user_limit_amount = 0;
+    config.user_limit_window = 0;
+    config.price_publisher = ctx.accounts.admin.key();
+    config.price_band_bps = 0; // reference prices are not enforced until set by admin
+    config.max_price_staleness = 60;
+    config.max_confidence_bps = 100; // 1%
+    config.max_reference_age = 86400; // 1 day
+    config.total_locked = 0;
+    config.event_sequence = 0;
     config.bump = ctx.bumps.config;
 
     Ok(())
@@ -0,0 +1,357 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::*;
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(listing_id: u64)]
+pub struct CreateListing<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = Listing::LEN,
+        seeds = [LISTING_SEED, seller.key().as_ref(), &listing_id.to_le_bytes()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// Mint the listing is priced in
+    pub mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListingPrice<'info> {
+    #[account(
+        mut,
+        has_one = seller @ TradeEscrowError::UnauthorizedSeller,
+        constraint = listing.status == ListingStatus::Open @ TradeEscrowError::ListingNotOpen,
+        constraint = listing.dutch.is_none() @ TradeEscrowError::InvalidListing
+    )]
+    pub listing: Account<'info, Listing>,
+
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller @ TradeEscrowError::UnauthorizedSeller,
+        constraint = listing.status == ListingStatus::Open @ TradeEscrowError::ListingNotOpen
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseListing<'info> {
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller @ TradeEscrowError::UnauthorizedSeller,
+        constraint = listing.status == ListingStatus::Filled @ TradeEscrowError::ListingStillOpen
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyListing<'info> {
+    #[account(
+        mut,
+        seeds = [LISTING_SEED, listing.seller.as_ref(), &listing.listing_id.to_le_bytes()],
+        bump = listing.bump,
+        constraint = listing.status == ListingStatus::Open @ TradeEscrowError::ListingNotOpen
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = Escrow::LEN,
+        seeds = [
+            ESCROW_SEED,
+            buyer.key().as_ref(),
+            listing.seller.as_ref(),
+            &listing.asset_id.to_le_bytes(),
+            &Clock::get()?.unix_timestamp.to_le_bytes(), // Use timestamp as nonce
+        ],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's rolling limit tracker
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [USER_STATS_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: Optional integrator credited with a share of the fee
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    /// Mint the listing is priced in
+    #[account(
+        constraint = mint.key() == listing.mint @ TradeEscrowError::InvalidListing
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// Fee vault for the payment mint, holding its minimum fee
+    #[account(
+        seeds = [FEE_VAULT_SEED, mint.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Buyer's token account
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key(),
+        constraint = buyer_token_account.mint == mint.key()
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// Escrow token account (PDA)
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn create_listing(
+    ctx: Context<CreateListing>,
+    listing_id: u64,
+    asset_id: u64,
+    price: u64,
+    expiry: i64,
+    allowed_buyer: Option<Pubkey>,
+    fee_payer: Option<FeePayer>,
+) -> Result<()> {
+    require!(!ctx.accounts.config.paused, TradeEscrowError::ContractPaused);
+    require!(asset_id > 0 && price > 0, TradeEscrowError::InvalidListing);
+    require!(
+        expiry > Clock::get()?.unix_timestamp,
+        TradeEscrowError::InvalidListing
+    );
+
+    let listing = &mut ctx.accounts.listing;
+    listing.seller = ctx.accounts.seller.key();
+    listing.listing_id = listing_id;
+    listing.asset_id = asset_id;
+    listing.mint = ctx.accounts.mint.key();
+    listing.price = price;
+    listing.dutch = None;
+    listing.expiry = expiry;
+    listing.allowed_buyer = allowed_buyer;
+    listing.fee_payer = fee_payer;
+    listing.status = ListingStatus::Open;
+    listing.bump = ctx.bumps.listing;
+
+    emit!(ListingCreated {
+        listing: listing.key(),
+        seller: listing.seller,
+        asset_id,
+        mint: listing.mint,
+        price,
+        expiry,
+    });
+
+    Ok(())
+}
+
+pub fn create_dutch_listing(
+    ctx: Context<CreateListing>,
+    listing_id: u64,
+    asset_id: u64,
+    pricing: DutchPricing,
+    expiry: i64,
+    allowed_buyer: Option<Pubkey>,
+    fee_payer: Option<FeePayer>,
+) -> Result<()> {
+    require!(!ctx.accounts.config.paused, TradeEscrowError::ContractPaused);
+    require!(asset_id > 0 && pricing.is_valid(), TradeEscrowError::InvalidListing);
+    require!(
+        expiry > Clock::get()?.unix_timestamp && expiry >= pricing.start_time,
+        TradeEscrowError::InvalidListing
+    );
+
+    let listing = &mut ctx.accounts.listing;
+    listing.seller = ctx.accounts.seller.key();
+    listing.listing_id = listing_id;
+    listing.asset_id = asset_id;
+    listing.mint = ctx.accounts.mint.key();
+    listing.price = pricing.start_price;
+    listing.dutch = Some(pricing);
+    listing.expiry = expiry;
+    listing.allowed_buyer = allowed_buyer;
+    listing.fee_payer = fee_payer;
+    listing.status = ListingStatus::Open;
+    listing.bump = ctx.bumps.listing;
+
+    emit!(ListingCreated {
+        listing: listing.key(),
+        seller: listing.seller,
+        asset_id,
+        mint: listing.mint,
+        price: pricing.start_price,
+        expiry,
+    });
+
+    Ok(())
+}
+
+pub fn update_listing_price(ctx: Context<UpdateListingPrice>, new_price: u64) -> Result<()> {
+    require!(new_price > 0, TradeEscrowError::InvalidListing);
+
+    let listing = &mut ctx.accounts.listing;
+    let old_price = listing.price;
+    listing.price = new_price;
+
+    emit!(ListingPriceUpdated {
+        listing: listing.key(),
+        old_price,
+        new_price,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
+    emit!(ListingCancelled {
+        listing: ctx.accounts.listing.key(),
+        seller: ctx.accounts.seller.key(),
+    });
+
+    Ok(())
+}
+
+pub fn close_listing(ctx: Context<CloseListing>) -> Result<()> {
+    emit!(ListingClosed {
+        listing: ctx.accounts.listing.key(),
+        seller: ctx.accounts.seller.key(),
+    });
+
+    Ok(())
+}
+
+pub fn buy_listing(
+    ctx: Context<BuyListing>,
+    price_max: u64,
+    deadline_offset: i64,
+) -> Result<()> {
+    require!(!ctx.accounts.config.paused, TradeEscrowError::ContractPaused);
+
+    // Verify deadline is reasonable (max 10 minutes)
+    require!(
+        deadline_offset > 0 && deadline_offset <= 600,
+        TradeEscrowError::InvalidDeadline
+    );
+
+    let clock = Clock::get()?;
+    let listing = &ctx.accounts.listing;
+    let buyer = ctx.accounts.buyer.key();
+
+    require!(
+        !listing.is_expired(clock.unix_timestamp),
+        TradeEscrowError::ListingExpired
+    );
+    require!(listing.can_buy(&buyer), TradeEscrowError::BuyerNotAllowed);
+
+    // Guard against a price update landing before this transaction
+    let price = listing.current_price(clock.unix_timestamp)?;
+    require!(price <= price_max, TradeEscrowError::PriceExceedsMaximum);
+
+    let referrer = ctx.accounts.referrer.as_ref().map(|r| r.key());
+    require!(referrer != Some(buyer), TradeEscrowError::InvalidReferrer);
+
+    let total_amount = open_escrow(
+        &mut ctx.accounts.escrow,
+        &mut ctx.accounts.config,
+        &mut ctx.accounts.user_stats,
+        &ctx.accounts.fee_vault,
+        EscrowTerms {
+            buyer,
+            seller: listing.seller,
+            mint: ctx.accounts.mint.key(),
+            asset_id: listing.asset_id,
+            amount: price,
+            deadline: clock.unix_timestamp + deadline_offset,
+            locked_at: clock.unix_timestamp,
+            nonce: clock.unix_timestamp as u64,
+            referrer,
+            fee_payer: listing.fee_payer,
+            usd_rate: None,
+            item_class: None,
+            wear_bounds: None,
+            escrow_bump: ctx.bumps.escrow,
+            user_stats_bump: ctx.bumps.user_stats,
+        },
+    )?;
+    require!(
+        ctx.accounts.buyer_token_account.amount >= total_amount,
+        TradeEscrowError::InsufficientFunds
+    );
+
+    // Transfer tokens to escrow
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.buyer_token_account.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, total_amount)?;
+
+    // Mark the listing filled
+    let listing = &mut ctx.accounts.listing;
+    listing.status = ListingStatus::Filled;
+
+    emit!(ListingFilled {
+        listing: listing.key(),
+        escrow_id: ctx.accounts.escrow.key(),
+        buyer,
+        price,
+    });
+
+    Ok(())
+}
\ No newline at end of file
@@ -1,12 +1,12 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use crate::state::*;
 use crate::errors::*;
 use crate::utils::*;
 use crate::*;
 
 #[derive(Accounts)]
-#[instruction(asset_id: u64, amount: u64, deadline_offset: i64)]
+#[instruction(asset_id: u64, item_class: ItemClass)]
 pub struct Lock<'info> {
     #[account(
         init,
@@ -24,22 +24,65 @@ pub struct Lock<'info> {
     pub escrow: Account<'info, Escrow>,
 
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    /// Buyer's rolling limit tracker
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [USER_STATS_SEED, buyer.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
     #[account(mut)]
     pub buyer: Signer<'info>,
 
     /// CHECK: Seller pubkey verified through signature
     pub seller: UncheckedAccount<'info>,
 
+    /// CHECK: Optional integrator credited with a share of the fee
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    /// Mint of the payment token (USDC/SOL)
+    pub mint: Account<'info, Mint>,
+
+    /// Fee vault for the payment mint, holding its minimum fee
+    #[account(
+        seeds = [FEE_VAULT_SEED, mint.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// CHECK: Reference price for the ask's item class, checked against the trade
+    /// amount once published; empty until then
+    #[account(
+        seeds = [
+            PRICE_REFERENCE_SEED,
+            mint.key().as_ref(),
+            &item_class.appid.to_le_bytes(),
+            &item_class.market_hash_name_hash,
+        ],
+        bump
+    )]
+    pub price_reference: UncheckedAccount<'info>,
+
+    /// USD price of the payment mint, required for USD-denominated asks
+    #[account(
+        constraint = price_feed.mint == mint.key() @ TradeEscrowError::InvalidPriceFeed
+    )]
+    pub price_feed: Option<Account<'info, PriceFeed>>,
+
     /// Buyer's token account (USDC/SOL)
     #[account(
         mut,
         constraint = buyer_token_account.owner == buyer.key(),
-        constraint = buyer_token_account.amount >= amount @ TradeEscrowError::InsufficientFunds
+        constraint = buyer_token_account.mint == mint.key()
     )]
     pub buyer_token_account: Account<'info, TokenAccount>,
 
@@ -47,25 +90,140 @@ pub struct Lock<'info> {
     #[account(
         init,
         payer = buyer,
-        token::mint = buyer_token_account.mint,
+        token::mint = mint,
         token::authority = escrow,
-        seeds = [b"escrow_vault", escrow.key().as_ref()],
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
         bump
     )]
     pub escrow_token_account: Account<'info, TokenAccount>,
 
+    /// CHECK: Instructions sysvar, read for the Ed25519 verify instructions
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+/// Price an ask was signed in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AskPrice {
+    /// Amount of the payment mint
+    Tokens(u64),
+    /// USD amount scaled by 10^USD_DECIMALS, converted through the price feed
+    Usd(u64),
+}
+
+impl AskPrice {
+    /// Price as it appears in the signed ask message
+    pub fn label(&self) -> String {
+        match self {
+            AskPrice::Tokens(amount) => amount.to_string(),
+            AskPrice::Usd(usd_amount) => format!("{}usd", usd_amount),
+        }
+    }
+}
+
+/// Message the seller signs for an ask, covering the fee payer when
+/// overridden and the buyer when the ask is private
+#[allow(clippy::too_many_arguments)]
+pub fn ask_message(
+    asset_id: u64,
+    item_class: &ItemClass,
+    seller: &Pubkey,
+    price: &AskPrice,
+    deadline: i64,
+    nonce: u64,
+    fee_payer_override: Option<FeePayer>,
+    allowed_buyer: Option<Pubkey>,
+) -> String {
+    let mut message = format!(
+        "{}:{}:{}:{}:{}:{}",
+        asset_id,
+        item_class.identifier(),
+        seller,
+        price.label(),
+        deadline,
+        nonce
+    );
+    if let Some(fee_payer) = fee_payer_override {
+        message.push(':');
+        message.push_str(fee_payer.as_str());
+    }
+    if let Some(allowed_buyer) = allowed_buyer {
+        message.push(':');
+        message.push_str(&allowed_buyer.to_string());
+    }
+    message
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn lock(
     ctx: Context<Lock>,
     asset_id: u64,
+    item_class: ItemClass,
     amount: u64,
     price_max: u64,
     ask_signature: [u8; 64],
     deadline_offset: i64,
+    fee_payer_override: Option<FeePayer>,
+    allowed_buyer: Option<Pubkey>,
+    price_override: bool,
+) -> Result<()> {
+    lock_escrow(
+        ctx,
+        asset_id,
+        item_class,
+        AskPrice::Tokens(amount),
+        price_max,
+        ask_signature,
+        deadline_offset,
+        fee_payer_override,
+        allowed_buyer,
+        price_override,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn lock_usd(
+    ctx: Context<Lock>,
+    asset_id: u64,
+    item_class: ItemClass,
+    usd_amount: u64,
+    price_max: u64,
+    ask_signature: [u8; 64],
+    deadline_offset: i64,
+    fee_payer_override: Option<FeePayer>,
+    allowed_buyer: Option<Pubkey>,
+    price_override: bool,
+) -> Result<()> {
+    lock_escrow(
+        ctx,
+        asset_id,
+        item_class,
+        AskPrice::Usd(usd_amount),
+        price_max,
+        ask_signature,
+        deadline_offset,
+        fee_payer_override,
+        allowed_buyer,
+        price_override,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn lock_escrow(
+    ctx: Context<Lock>,
+    asset_id: u64,
+    item_class: ItemClass,
+    ask_price: AskPrice,
+    price_max: u64,
+    ask_signature: [u8; 64],
+    deadline_offset: i64,
+    fee_payer_override: Option<FeePayer>,
+    allowed_buyer: Option<Pubkey>,
+    price_override: bool,
 ) -> Result<()> {
     let config = &ctx.accounts.config;
     
@@ -82,31 +240,111 @@ pub fn lock(
     let deadline = clock.unix_timestamp + deadline_offset;
     let nonce = clock.unix_timestamp as u64;
 
+    // Resolve the token amount, converting USD asks at the feed rate
+    let (amount, usd_rate) = match ask_price {
+        AskPrice::Tokens(amount) => (amount, None),
+        AskPrice::Usd(usd_amount) => {
+            let price_feed = ctx
+                .accounts
+                .price_feed
+                .as_ref()
+                .ok_or(TradeEscrowError::InvalidPriceFeed)?;
+            require!(
+                !price_feed.is_stale(clock.unix_timestamp, config.max_price_staleness),
+                TradeEscrowError::StalePriceFeed
+            );
+            require!(
+                price_feed.is_confident(config.max_confidence_bps),
+                TradeEscrowError::PriceFeedUncertain
+            );
+            let amount = price_feed
+                .usd_to_tokens(usd_amount, ctx.accounts.mint.decimals)
+                .ok_or(TradeEscrowError::InvalidPriceFeed)?;
+            (amount, Some(price_feed.price))
+        }
+    };
+
     // Verify seller's ask signature
-    let ask_message = format!(
-        "{}:{}:{}:{}:{}",
+    let ask_message = ask_message(
         asset_id,
-        ctx.accounts.seller.key(),
-        amount,
+        &item_class,
+        &ctx.accounts.seller.key(),
+        &ask_price,
         deadline,
-        nonce
+        nonce,
+        fee_payer_override,
+        allowed_buyer,
     );
     
     require!(
         verify_signature(
+            &ctx.accounts.instructions_sysvar,
             &ask_signature,
-            &ask_message.as_bytes(),
+            ask_message.as_bytes(),
             &ctx.accounts.seller.key()
         )?,
         TradeEscrowError::InvalidAskSignature
     );
 
+    // Private asks can only be filled by the named buyer
+    require!(
+        allowed_buyer.is_none() || allowed_buyer == Some(ctx.accounts.buyer.key()),
+        TradeEscrowError::BuyerNotAllowed
+    );
+
     // Verify price doesn't exceed maximum
     require!(amount <= price_max, TradeEscrowError::PriceExceedsMaximum);
 
-    // Calculate and include protocol fee
-    let fee = config.calculate_fee(amount);
-    let total_amount = amount + fee;
+    // Reject prices far from the market unless a party explicitly overrides
+    let reference = published_reference(&ctx.accounts.price_reference)?
+        .filter(|_| config.price_band_bps > 0);
+    if let Some(reference) = &reference {
+        require!(
+            !reference.is_stale(clock.unix_timestamp, config.max_reference_age),
+            TradeEscrowError::StaleReferencePrice
+        );
+    }
+    let deviation = reference
+        .filter(|reference| reference.deviates(amount, config.price_band_bps))
+        .map(|reference| reference.price);
+    require!(
+        deviation.is_none() || price_override,
+        TradeEscrowError::PriceOutsideReferenceBand
+    );
+
+    let referrer = ctx.accounts.referrer.as_ref().map(|r| r.key());
+    require!(
+        referrer != Some(ctx.accounts.buyer.key()),
+        TradeEscrowError::InvalidReferrer
+    );
+
+    let total_amount = open_escrow(
+        &mut ctx.accounts.escrow,
+        &mut ctx.accounts.config,
+        &mut ctx.accounts.user_stats,
+        &ctx.accounts.fee_vault,
+        EscrowTerms {
+            buyer: ctx.accounts.buyer.key(),
+            seller: ctx.accounts.seller.key(),
+            mint: ctx.accounts.mint.key(),
+            asset_id,
+            amount,
+            deadline,
+            locked_at: clock.unix_timestamp,
+            nonce,
+            referrer,
+            fee_payer: fee_payer_override,
+            usd_rate,
+            item_class: None,
+            wear_bounds: None,
+            escrow_bump: ctx.bumps.escrow,
+            user_stats_bump: ctx.bumps.user_stats,
+        },
+    )?;
+    require!(
+        ctx.accounts.buyer_token_account.amount >= total_amount,
+        TradeEscrowError::InsufficientFunds
+    );
 
     // Transfer tokens to escrow
     let transfer_ctx = CpiContext::new(
@@ -119,25 +357,219 @@ pub fn lock(
     );
     token::transfer(transfer_ctx, total_amount)?;
 
+    if let Some(reference_price) = deviation {
+        emit!(PriceDeviationFlagged {
+            escrow_id: ctx.accounts.escrow.key(),
+            reference_price,
+            amount,
+            band_bps: ctx.accounts.config.price_band_bps,
+        });
+    }
+
+    Ok(())
+}
+
+/// Reference price at `account`, or `None` if none was published for the class yet
+fn published_reference(account: &AccountInfo) -> Result<Option<PriceReference>> {
+    if account.owner != &crate::ID {
+        return Ok(None);
+    }
+    let data = account.try_borrow_data()?;
+    Ok(Some(PriceReference::try_deserialize(&mut &data[..])?))
+}
+
+/// Terms of a new escrow, however it was matched
+pub struct EscrowTerms {
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    /// Mint the escrow is paid in
+    pub mint: Pubkey,
+    pub asset_id: u64,
+    pub amount: u64,
+    pub deadline: i64,
+    /// When the buyer's funding was counted against the limits
+    pub locked_at: i64,
+    pub nonce: u64,
+    pub referrer: Option<Pubkey>,
+    /// Seller-approved override of the config fee payer
+    pub fee_payer: Option<FeePayer>,
+    /// Feed price used to convert a USD ask, if any
+    pub usd_rate: Option<u64>,
+    /// Item class the oracles must attest, for buy order fills
+    pub item_class: Option<ItemClass>,
+    /// Wear range the oracles must attest, for buy order fills
+    pub wear_bounds: Option<WearBounds>,
+    pub escrow_bump: u8,
+    pub user_stats_bump: u8,
+}
+
+/// Fee and funding owed for a trade
+pub struct FeeQuote {
+    pub fee_payer: FeePayer,
+    pub fee: u64,
+    pub fee_bps: u16,
+    /// Amount the buyer deposits: the price plus the buyer's share of the fee
+    pub total_amount: u64,
+}
+
+/// Charge the fee, enforce exposure limits and record a new escrow.
+///
+/// Returns the amount the caller must move into the escrow vault.
+pub fn open_escrow(
+    escrow: &mut Account<Escrow>,
+    config: &mut Config,
+    user_stats: &mut UserStats,
+    fee_vault: &FeeVault,
+    terms: EscrowTerms,
+) -> Result<u64> {
+    let quote = reserve_exposure(
+        config,
+        user_stats,
+        fee_vault,
+        terms.buyer,
+        terms.user_stats_bump,
+        terms.amount,
+        terms.fee_payer,
+    )?;
+    write_escrow(escrow, config, &terms, &quote)?;
+
+    Ok(quote.total_amount)
+}
+
+/// Quote the fee for a trade and count its funding against the exposure limits
+pub fn reserve_exposure(
+    config: &mut Config,
+    user_stats: &mut UserStats,
+    fee_vault: &FeeVault,
+    buyer: Pubkey,
+    user_stats_bump: u8,
+    amount: u64,
+    fee_payer: Option<FeePayer>,
+) -> Result<FeeQuote> {
+    let now = Clock::get()?.unix_timestamp;
+    let quote = quote_fee(config, user_stats, fee_vault, amount, fee_payer, now)?;
+
+    // Enforce exposure limits
+    require!(
+        !config.exceeds_trade_limit(quote.total_amount),
+        TradeEscrowError::TradeLimitExceeded
+    );
+    count_exposure(config, user_stats, buyer, user_stats_bump, quote.total_amount, now)?;
+
+    Ok(quote)
+}
+
+/// Fee and funding owed for a trade of `amount` under the current fee schedule
+pub fn quote_fee(
+    config: &Config,
+    user_stats: &UserStats,
+    fee_vault: &FeeVault,
+    amount: u64,
+    fee_payer: Option<FeePayer>,
+    now: i64,
+) -> Result<FeeQuote> {
+    // Calculate and include protocol fee
+    let fee = config.calculate_fee(amount, fee_vault, user_stats, now)?;
+    let fee_payer = fee_payer.unwrap_or(config.fee_payer);
+    let (buyer_fee, _) = fee_payer.split(fee);
+    let total_amount = amount
+        .checked_add(buyer_fee)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    Ok(FeeQuote {
+        fee_payer,
+        fee,
+        fee_bps: effective_fee_bps(amount, fee),
+        total_amount,
+    })
+}
+
+/// Count funding against the TVL and per-user limits
+pub fn count_exposure(
+    config: &mut Config,
+    user_stats: &mut UserStats,
+    buyer: Pubkey,
+    user_stats_bump: u8,
+    total_amount: u64,
+    now: i64,
+) -> Result<()> {
+    require!(
+        !config.exceeds_tvl(total_amount),
+        TradeEscrowError::TvlLimitExceeded
+    );
+
+    user_stats.ensure_initialized(buyer, user_stats_bump);
+    if config.has_user_limit() {
+        let window_volume = user_stats.current_window_volume(now, config.user_limit_window);
+        require!(
+            window_volume.saturating_add(total_amount) <= config.user_limit_amount,
+            TradeEscrowError::UserLimitExceeded
+        );
+    }
+    user_stats.record_lock(total_amount, now, config.user_limit_window);
+
+    config.total_locked = config.total_locked
+        .checked_add(total_amount)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// Release funding counted at `locked_at` that is returned without a trade
+pub fn release_exposure(
+    config: &mut Config,
+    user_stats: &mut UserStats,
+    total_amount: u64,
+    locked_at: i64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    user_stats.release_lock(total_amount, locked_at, now, config.user_limit_window);
+
+    config.total_locked = config.total_locked
+        .checked_sub(total_amount)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// Record an escrow whose funding and fee are already settled, and announce it
+pub fn write_escrow(
+    escrow: &mut Account<Escrow>,
+    config: &mut Config,
+    terms: &EscrowTerms,
+    quote: &FeeQuote,
+) -> Result<()> {
     // Initialize escrow state
-    let escrow = &mut ctx.accounts.escrow;
-    escrow.buyer = ctx.accounts.buyer.key();
-    escrow.seller = ctx.accounts.seller.key();
-    escrow.asset_id = asset_id;
-    escrow.amount = amount;
-    escrow.deadline = deadline;
+    escrow.buyer = terms.buyer;
+    escrow.seller = terms.seller;
+    escrow.asset_id = terms.asset_id;
+    escrow.amount = terms.amount;
+    escrow.deadline = terms.deadline;
+    escrow.locked_at = terms.locked_at;
     escrow.settled = false;
-    escrow.nonce = nonce;
-    escrow.bump = ctx.bumps.escrow;
+    escrow.nonce = terms.nonce;
+    escrow.referrer = terms.referrer;
+    escrow.fee_payer = quote.fee_payer;
+    escrow.fee_amount = quote.fee;
+    escrow.fee_bps = quote.fee_bps;
+    escrow.referral_share_bps = config.referral_share_bps;
+    escrow.usd_rate = terms.usd_rate;
+    escrow.item_class = terms.item_class;
+    escrow.wear_bounds = terms.wear_bounds;
+    escrow.bump = terms.escrow_bump;
 
     // Emit event
     emit!(EscrowLocked {
         escrow_id: escrow.key(),
         buyer: escrow.buyer,
         seller: escrow.seller,
-        asset_id,
-        amount,
-        deadline,
+        mint: terms.mint,
+        asset_id: escrow.asset_id,
+        amount: escrow.amount,
+        fee: quote.fee,
+        deadline: escrow.deadline,
+        usd_rate: escrow.usd_rate,
+        sequence: config.next_event_sequence()?,
     });
 
     Ok(())
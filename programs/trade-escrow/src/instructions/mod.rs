@@ -3,9 +3,25 @@ pub mod lock;
 pub mod settle;
 pub mod refund;
 pub mod admin;
+pub mod fees;
+pub mod listing;
+pub mod buy_order;
+pub mod auction;
+pub mod swap;
+pub mod commodity;
+pub mod price_reference;
+pub mod price_feed;
 
 pub use initialize::*;
 pub use lock::*;
 pub use settle::*;
 pub use refund::*;
-pub use admin::*;
\ No newline at end of file
+pub use admin::*;
+pub use fees::*;
+pub use listing::*;
+pub use buy_order::*;
+pub use auction::*;
+pub use swap::*;
+pub use commodity::*;
+pub use price_reference::*;
+pub use price_feed::*;
\ No newline at end of file
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::state::*;
+use crate::errors::*;
+use crate::*;
+
+#[derive(Accounts)]
+pub struct PublishPriceFeed<'info> {
+    #[account(
+        init_if_needed,
+        payer = publisher,
+        space = PriceFeed::LEN,
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        constraint = publisher.key() == config.price_publisher @ TradeEscrowError::UnauthorizedPricePublisher
+    )]
+    pub publisher: Signer<'info>,
+
+    /// Mint being priced
+    pub mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn publish_price_feed(
+    ctx: Context<PublishPriceFeed>,
+    price: u64,
+    confidence: u64,
+) -> Result<()> {
+    require!(price > 0, TradeEscrowError::InvalidPriceFeed);
+
+    let now = Clock::get()?.unix_timestamp;
+    let price_feed = &mut ctx.accounts.price_feed;
+    price_feed.mint = ctx.accounts.mint.key();
+    price_feed.price = price;
+    price_feed.confidence = confidence;
+    price_feed.publish_time = now;
+    price_feed.bump = ctx.bumps.price_feed;
+
+    emit!(PriceFeedPublished {
+        price_feed: price_feed.key(),
+        mint: price_feed.mint,
+        price,
+        confidence,
+        timestamp: now,
+    });
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::state::*;
+use crate::errors::*;
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(item_class: ItemClass)]
+pub struct PublishReferencePrice<'info> {
+    #[account(
+        init_if_needed,
+        payer = publisher,
+        space = PriceReference::LEN,
+        seeds = [
+            PRICE_REFERENCE_SEED,
+            mint.key().as_ref(),
+            &item_class.appid.to_le_bytes(),
+            &item_class.market_hash_name_hash,
+        ],
+        bump
+    )]
+    pub price_reference: Account<'info, PriceReference>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        constraint = publisher.key() == config.price_publisher @ TradeEscrowError::UnauthorizedPricePublisher
+    )]
+    pub publisher: Signer<'info>,
+
+    /// Mint the price is quoted in
+    pub mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn publish_reference_price(
+    ctx: Context<PublishReferencePrice>,
+    item_class: ItemClass,
+    price: u64,
+) -> Result<()> {
+    require!(price > 0, TradeEscrowError::InvalidReferencePrice);
+
+    let now = Clock::get()?.unix_timestamp;
+    let price_reference = &mut ctx.accounts.price_reference;
+    price_reference.item_class = item_class;
+    price_reference.mint = ctx.accounts.mint.key();
+    price_reference.price = price;
+    price_reference.updated_at = now;
+    price_reference.bump = ctx.bumps.price_reference;
+
+    emit!(ReferencePricePublished {
+        price_reference: price_reference.key(),
+        item_class,
+        mint: price_reference.mint,
+        price,
+        timestamp: now,
+    });
+
+    Ok(())
+}
\ No newline at end of file
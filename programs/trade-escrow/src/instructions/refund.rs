@@ -14,6 +14,7 @@ pub struct Refund<'info> {
     pub escrow: Account<'info, Escrow>,
 
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump = config.bump
     )]
@@ -25,7 +26,7 @@ pub struct Refund<'info> {
     /// Escrow token account
     #[account(
         mut,
-        seeds = [b"escrow_vault", escrow.key().as_ref()],
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
         bump
     )]
     pub escrow_token_account: Account<'info, TokenAccount>,
@@ -38,29 +39,48 @@ pub struct Refund<'info> {
     )]
     pub buyer_token_account: Account<'info, TokenAccount>,
 
+    /// Buyer's rolling limit tracker, released of the refunded funding
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, escrow.buyer.as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    /// Tree the trade's receipt is appended to
+    #[account(
+        mut,
+        seeds = [RECEIPT_TREE_SEED, escrow_token_account.mint.as_ref()],
+        bump = receipt_tree.bump
+    )]
+    pub receipt_tree: Box<Account<'info, ReceiptTree>>,
+
     pub token_program: Program<'info, Token>,
 }
 
 pub fn refund(ctx: Context<Refund>) -> Result<()> {
     let escrow = &mut ctx.accounts.escrow;
-    let config = &ctx.accounts.config;
+    let config = &mut ctx.accounts.config;
 
     // Check if paused (allow refunds even when paused)
     // require!(!config.paused, TradeEscrowError::ContractPaused);
 
-    // Calculate refund amount (include fee in refund)
-    let fee = config.calculate_fee(escrow.amount);
-    let refund_amount = escrow.amount + fee;
+    // Refund everything locked, using the fee snapshotted at lock
+    let refund_amount = escrow.locked_total()?;
 
     // Create signer seeds for escrow PDA
-    let signer_seeds = &[
+    let asset_id_bytes = escrow.asset_id.to_le_bytes();
+    let nonce_bytes = escrow.nonce.to_le_bytes();
+    let bump = [escrow.bump];
+    let seeds: &[&[u8]] = &[
         ESCROW_SEED,
         escrow.buyer.as_ref(),
         escrow.seller.as_ref(),
-        &escrow.asset_id.to_le_bytes(),
-        &escrow.nonce.to_le_bytes(),
-        &[escrow.bump],
+        &asset_id_bytes,
+        &nonce_bytes,
+        &bump,
     ];
+    let signer_seeds = &[seeds];
 
     // Transfer refund to buyer
     let transfer_ctx = CpiContext::new_with_signer(
@@ -70,20 +90,30 @@ pub fn refund(ctx: Context<Refund>) -> Result<()> {
             to: ctx.accounts.buyer_token_account.to_account_info(),
             authority: escrow.to_account_info(),
         },
-        &[signer_seeds],
+        signer_seeds,
     );
     token::transfer(transfer_ctx, refund_amount)?;
 
     // Mark as settled (to prevent double refund)
     escrow.settled = true;
+    // The trade never happened, so the funding no longer counts against the buyer's limit
+    release_exposure(config, &mut ctx.accounts.user_stats, refund_amount, escrow.locked_at)?;
 
     // Emit event
     emit!(EscrowRefunded {
         escrow_id: escrow.key(),
         buyer: escrow.buyer,
         amount: refund_amount,
-        reason: "Deadline expired".to_string(),
+        reason: RefundReason::DeadlineExpired,
+        sequence: config.next_event_sequence()?,
     });
 
+    let receipt = escrow.receipt(
+        escrow.key(),
+        TradeOutcome::Refunded,
+        Clock::get()?.unix_timestamp,
+    );
+    append_receipt(&mut ctx.accounts.receipt_tree, receipt)?;
+
     Ok(())
 }
\ No newline at end of file
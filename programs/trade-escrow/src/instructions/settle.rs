@@ -14,6 +14,7 @@ pub struct Settle<'info> {
     pub escrow: Account<'info, Escrow>,
 
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump = config.bump
     )]
@@ -22,7 +23,7 @@ pub struct Settle<'info> {
     /// Escrow token account
     #[account(
         mut,
-        seeds = [b"escrow_vault", escrow.key().as_ref()],
+        seeds = [ESCROW_VAULT_SEED, escrow.key().as_ref()],
         bump
     )]
     pub escrow_token_account: Account<'info, TokenAccount>,
@@ -35,13 +36,48 @@ pub struct Settle<'info> {
     )]
     pub seller_token_account: Account<'info, TokenAccount>,
 
-    /// Fee recipient account
+    /// Protocol fee vault for the escrow's mint
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, escrow_token_account.mint.as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Token account the fee is deposited into
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_TOKEN_SEED, escrow_token_account.mint.as_ref()],
+        bump
+    )]
+    pub fee_vault_token_account: Account<'info, TokenAccount>,
+
+    /// Referrer's token account, required when the escrow has a referrer
+    #[account(
+        mut,
+        constraint = referrer_token_account.mint == escrow_token_account.mint
+    )]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Buyer's stats, credited with the settled volume
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, escrow.buyer.as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    /// Tree the trade's receipt is appended to
     #[account(
         mut,
-        constraint = fee_recipient_account.owner == config.fee_recipient,
-        constraint = fee_recipient_account.mint == escrow_token_account.mint
+        seeds = [RECEIPT_TREE_SEED, escrow_token_account.mint.as_ref()],
+        bump = receipt_tree.bump
     )]
-    pub fee_recipient_account: Account<'info, TokenAccount>,
+    pub receipt_tree: Box<Account<'info, ReceiptTree>>,
+
+    /// CHECK: Instructions sysvar, read for the Ed25519 verify instructions
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
 
     pub token_program: Program<'info, Token>,
 }
@@ -51,7 +87,7 @@ pub fn settle(
     oracle_signatures: Vec<[u8; 64]>,
 ) -> Result<()> {
     let escrow = &mut ctx.accounts.escrow;
-    let config = &ctx.accounts.config;
+    let config = &mut ctx.accounts.config;
 
     // Check if paused
     require!(!config.paused, TradeEscrowError::ContractPaused);
@@ -63,22 +99,14 @@ pub fn settle(
     );
 
     // Verify oracle signatures
-    let settlement_message = format!(
-        "settle:{}:{}:{}",
-        escrow.asset_id,
-        escrow.buyer,
-        escrow.key()
-    );
+    let settlement_message = escrow.settlement_message(&escrow.key());
 
-    let mut valid_signatures = 0;
-    for signature in oracle_signatures.iter() {
-        for oracle_pubkey in config.oracle_pubkeys.iter() {
-            if verify_signature(signature, settlement_message.as_bytes(), oracle_pubkey)? {
-                valid_signatures += 1;
-                break;
-            }
-        }
-    }
+    let valid_signatures = count_oracle_signatures(
+        &ctx.accounts.instructions_sysvar,
+        &config.oracle_pubkeys,
+        &oracle_signatures,
+        settlement_message.as_bytes(),
+    )?;
 
     require!(
         valid_signatures >= 2,
@@ -86,19 +114,36 @@ pub fn settle(
     );
 
     // Calculate amounts
-    let fee = config.calculate_fee(escrow.amount);
-    let seller_amount = escrow.amount;
+    // Use the fee and referral share snapshotted at lock so config changes cannot
+    // unbalance the vault or reprice the referrer's cut
+    let seller_amount = escrow.seller_amount()?;
+    let (protocol_fee, referral_fee) = escrow.split_fee()?;
+
+    if let Some(referrer) = escrow.referrer {
+        let referrer_token_account = ctx
+            .accounts
+            .referrer_token_account
+            .as_ref()
+            .ok_or(TradeEscrowError::InvalidReferrerAccount)?;
+        require!(
+            referrer_token_account.owner == referrer,
+            TradeEscrowError::InvalidReferrerAccount
+        );
+    }
 
     // Create signer seeds for escrow PDA
-    let escrow_key = escrow.key();
-    let signer_seeds = &[
+    let asset_id_bytes = escrow.asset_id.to_le_bytes();
+    let nonce_bytes = escrow.nonce.to_le_bytes();
+    let bump = [escrow.bump];
+    let seeds: &[&[u8]] = &[
         ESCROW_SEED,
         escrow.buyer.as_ref(),
         escrow.seller.as_ref(),
-        &escrow.asset_id.to_le_bytes(),
-        &escrow.nonce.to_le_bytes(),
-        &[escrow.bump],
+        &asset_id_bytes,
+        &nonce_bytes,
+        &bump,
     ];
+    let signer_seeds = &[seeds];
 
     // Transfer payment to seller
     let transfer_to_seller_ctx = CpiContext::new_with_signer(
@@ -108,26 +153,51 @@ pub fn settle(
             to: ctx.accounts.seller_token_account.to_account_info(),
             authority: escrow.to_account_info(),
         },
-        &[signer_seeds],
+        signer_seeds,
     );
     token::transfer(transfer_to_seller_ctx, seller_amount)?;
 
-    // Transfer fee to protocol
-    if fee > 0 {
+    // Deposit protocol share into the fee vault
+    if protocol_fee > 0 {
         let transfer_fee_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.escrow_token_account.to_account_info(),
-                to: ctx.accounts.fee_recipient_account.to_account_info(),
+                to: ctx.accounts.fee_vault_token_account.to_account_info(),
                 authority: escrow.to_account_info(),
             },
-            &[signer_seeds],
+            signer_seeds,
         );
-        token::transfer(transfer_fee_ctx, fee)?;
+        token::transfer(transfer_fee_ctx, protocol_fee)?;
+
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        fee_vault.total_collected = fee_vault.total_collected
+            .checked_add(protocol_fee)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+    }
+
+    // Pay referral share
+    if referral_fee > 0 {
+        if let Some(referrer_token_account) = ctx.accounts.referrer_token_account.as_ref() {
+            let transfer_referral_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: referrer_token_account.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer_seeds,
+            );
+            token::transfer(transfer_referral_ctx, referral_fee)?;
+        }
     }
 
     // Mark as settled
     escrow.settled = true;
+    ctx.accounts.user_stats.record_volume(escrow.amount, Clock::get()?.unix_timestamp);
+    config.total_locked = config.total_locked
+        .checked_sub(escrow.locked_total()?)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
 
     // Emit event
     emit!(EscrowSettled {
@@ -135,7 +205,32 @@ pub fn settle(
         buyer: escrow.buyer,
         seller: escrow.seller,
         amount: seller_amount,
+        protocol_fee,
+        referrer: escrow.referrer,
+        referral_fee,
         oracle_count: valid_signatures,
+        sequence: config.next_event_sequence()?,
+    });
+
+    let receipt = escrow.receipt(
+        escrow.key(),
+        TradeOutcome::Settled,
+        Clock::get()?.unix_timestamp,
+    );
+    append_receipt(&mut ctx.accounts.receipt_tree, receipt)?;
+
+    Ok(())
+}
+
+/// Append the receipt of a closed trade to the receipt tree, and announce it
+pub fn append_receipt(receipt_tree: &mut ReceiptTree, receipt: Receipt) -> Result<()> {
+    let index = receipt_tree.append(&receipt)?;
+
+    emit!(ReceiptAppended {
+        mint: receipt_tree.mint,
+        index,
+        receipt,
+        root: receipt_tree.root,
     });
 
     Ok(())
@@ -0,0 +1,660 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::errors::*;
+use crate::utils::*;
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(swap_id: u64)]
+pub struct ProposeSwap<'info> {
+    #[account(
+        init,
+        payer = maker,
+        space = SwapEscrow::LEN,
+        seeds = [SWAP_SEED, maker.key().as_ref(), &swap_id.to_le_bytes()],
+        bump
+    )]
+    pub swap: Account<'info, SwapEscrow>,
+
+    /// Vault holding both cash legs
+    #[account(
+        init,
+        payer = maker,
+        token::mint = mint,
+        token::authority = swap,
+        seeds = [SWAP_VAULT_SEED, swap.key().as_ref()],
+        bump
+    )]
+    pub swap_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Maker's rolling limit tracker
+    #[account(
+        init_if_needed,
+        payer = maker,
+        space = UserStats::LEN,
+        seeds = [USER_STATS_SEED, maker.key().as_ref()],
+        bump
+    )]
+    pub maker_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// CHECK: Counterparty the swap is offered to
+    pub taker: UncheckedAccount<'info>,
+
+    /// Mint of the cash legs
+    pub mint: Account<'info, Mint>,
+
+    /// Maker's token account funding their cash leg
+    #[account(
+        mut,
+        constraint = maker_token_account.owner == maker.key(),
+        constraint = maker_token_account.mint == mint.key()
+    )]
+    pub maker_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptSwap<'info> {
+    #[account(
+        mut,
+        seeds = [SWAP_SEED, swap.maker.as_ref(), &swap.swap_id.to_le_bytes()],
+        bump = swap.bump,
+        has_one = taker @ TradeEscrowError::UnauthorizedTaker,
+        constraint = swap.status == SwapStatus::Proposed @ TradeEscrowError::InvalidSwapState
+    )]
+    pub swap: Account<'info, SwapEscrow>,
+
+    #[account(
+        mut,
+        seeds = [SWAP_VAULT_SEED, swap.key().as_ref()],
+        bump
+    )]
+    pub swap_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Taker's rolling limit tracker
+    #[account(
+        init_if_needed,
+        payer = taker,
+        space = UserStats::LEN,
+        seeds = [USER_STATS_SEED, swap.taker.as_ref()],
+        bump
+    )]
+    pub taker_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// Taker's token account funding their cash leg
+    #[account(
+        mut,
+        constraint = taker_token_account.owner == taker.key(),
+        constraint = taker_token_account.mint == swap.mint
+    )]
+    pub taker_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteSwap<'info> {
+    #[account(
+        mut,
+        close = maker,
+        seeds = [SWAP_SEED, swap.maker.as_ref(), &swap.swap_id.to_le_bytes()],
+        bump = swap.bump,
+        constraint = swap.status == SwapStatus::Locked @ TradeEscrowError::InvalidSwapState
+    )]
+    pub swap: Account<'info, SwapEscrow>,
+
+    #[account(
+        mut,
+        seeds = [SWAP_VAULT_SEED, swap.key().as_ref()],
+        bump
+    )]
+    pub swap_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the rent of the closed swap and its vault
+    #[account(
+        mut,
+        constraint = maker.key() == swap.maker @ TradeEscrowError::UnauthorizedSeller
+    )]
+    pub maker: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = maker_token_account.owner == swap.maker,
+        constraint = maker_token_account.mint == swap.mint
+    )]
+    pub maker_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = taker_token_account.owner == swap.taker,
+        constraint = taker_token_account.mint == swap.mint
+    )]
+    pub taker_token_account: Account<'info, TokenAccount>,
+
+    /// Tree the swap's receipt is appended to
+    #[account(
+        mut,
+        seeds = [RECEIPT_TREE_SEED, swap.mint.as_ref()],
+        bump = receipt_tree.bump
+    )]
+    pub receipt_tree: Box<Account<'info, ReceiptTree>>,
+
+    /// CHECK: Instructions sysvar, read for the Ed25519 verify instructions
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RefundSwap<'info> {
+    #[account(
+        mut,
+        close = maker,
+        seeds = [SWAP_SEED, swap.maker.as_ref(), &swap.swap_id.to_le_bytes()],
+        bump = swap.bump,
+        constraint = swap.status == SwapStatus::Locked @ TradeEscrowError::InvalidSwapState
+    )]
+    pub swap: Account<'info, SwapEscrow>,
+
+    #[account(
+        mut,
+        seeds = [SWAP_VAULT_SEED, swap.key().as_ref()],
+        bump
+    )]
+    pub swap_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the rent of the closed swap and its vault
+    #[account(
+        mut,
+        constraint = maker.key() == swap.maker @ TradeEscrowError::UnauthorizedSeller
+    )]
+    pub maker: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = maker_token_account.owner == swap.maker,
+        constraint = maker_token_account.mint == swap.mint
+    )]
+    pub maker_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = taker_token_account.owner == swap.taker,
+        constraint = taker_token_account.mint == swap.mint
+    )]
+    pub taker_token_account: Account<'info, TokenAccount>,
+
+    /// Maker's rolling limit tracker, released of the maker's cash leg
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, swap.maker.as_ref()],
+        bump = maker_stats.bump
+    )]
+    pub maker_stats: Account<'info, UserStats>,
+
+    /// Taker's rolling limit tracker, released of the taker's cash leg
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, swap.taker.as_ref()],
+        bump = taker_stats.bump
+    )]
+    pub taker_stats: Account<'info, UserStats>,
+
+    /// Tree the swap's receipt is appended to
+    #[account(
+        mut,
+        seeds = [RECEIPT_TREE_SEED, swap.mint.as_ref()],
+        bump = receipt_tree.bump
+    )]
+    pub receipt_tree: Box<Account<'info, ReceiptTree>>,
+
+    /// CHECK: Instructions sysvar, read for the Ed25519 verify instructions
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSwap<'info> {
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ TradeEscrowError::UnauthorizedSeller,
+        seeds = [SWAP_SEED, swap.maker.as_ref(), &swap.swap_id.to_le_bytes()],
+        bump = swap.bump,
+        constraint = swap.status == SwapStatus::Proposed @ TradeEscrowError::InvalidSwapState
+    )]
+    pub swap: Account<'info, SwapEscrow>,
+
+    #[account(
+        mut,
+        seeds = [SWAP_VAULT_SEED, swap.key().as_ref()],
+        bump
+    )]
+    pub swap_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Maker's rolling limit tracker, released of the cash leg
+    #[account(
+        mut,
+        seeds = [USER_STATS_SEED, maker.key().as_ref()],
+        bump = maker_stats.bump
+    )]
+    pub maker_stats: Account<'info, UserStats>,
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = maker_token_account.owner == maker.key(),
+        constraint = maker_token_account.mint == swap.mint
+    )]
+    pub maker_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Pay `amount` out of the swap vault, skipping empty legs
+fn pay_from_vault<'info>(
+    token_program: &Program<'info, Token>,
+    swap_vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    swap: &Account<'info, SwapEscrow>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let swap_id_bytes = swap.swap_id.to_le_bytes();
+    let bump = [swap.bump];
+    let seeds: &[&[u8]] = &[SWAP_SEED, swap.maker.as_ref(), &swap_id_bytes, &bump];
+    let signer_seeds = &[seeds];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: swap_vault.to_account_info(),
+            to: to.to_account_info(),
+            authority: swap.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, amount)
+}
+
+/// Close the emptied swap vault, returning its rent to the maker
+fn close_vault<'info>(
+    token_program: &Program<'info, Token>,
+    swap_vault: &Account<'info, TokenAccount>,
+    maker: &AccountInfo<'info>,
+    swap: &Account<'info, SwapEscrow>,
+) -> Result<()> {
+    let swap_id_bytes = swap.swap_id.to_le_bytes();
+    let bump = [swap.bump];
+    let seeds: &[&[u8]] = &[SWAP_SEED, swap.maker.as_ref(), &swap_id_bytes, &bump];
+    let signer_seeds = &[seeds];
+
+    let close_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: swap_vault.to_account_info(),
+            destination: maker.clone(),
+            authority: swap.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::close_account(close_ctx)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn propose_swap(
+    ctx: Context<ProposeSwap>,
+    swap_id: u64,
+    maker_items: Vec<u64>,
+    taker_items: Vec<u64>,
+    maker_cash: u64,
+    taker_cash: u64,
+    expiry: i64,
+    delivery_window: i64,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(!config.paused, TradeEscrowError::ContractPaused);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !maker_items.is_empty()
+            && !taker_items.is_empty()
+            && maker_items.len() <= MAX_SWAP_ITEMS
+            && taker_items.len() <= MAX_SWAP_ITEMS
+            && maker_items.iter().chain(taker_items.iter()).all(|&item| item > 0),
+        TradeEscrowError::InvalidSwap
+    );
+    require!(
+        expiry > now && ctx.accounts.taker.key() != ctx.accounts.maker.key(),
+        TradeEscrowError::InvalidSwap
+    );
+    require!(
+        delivery_window > 0 && delivery_window <= 600,
+        TradeEscrowError::InvalidDeadline
+    );
+
+    // The maker's cash leg counts against the exposure limits like any escrow
+    let total_cash = maker_cash
+        .checked_add(taker_cash)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+    require!(
+        !config.exceeds_trade_limit(total_cash),
+        TradeEscrowError::TradeLimitExceeded
+    );
+    count_exposure(
+        config,
+        &mut ctx.accounts.maker_stats,
+        ctx.accounts.maker.key(),
+        ctx.bumps.maker_stats,
+        maker_cash,
+        now,
+    )?;
+    require!(
+        ctx.accounts.maker_token_account.amount >= maker_cash,
+        TradeEscrowError::InsufficientFunds
+    );
+
+    if maker_cash > 0 {
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.maker_token_account.to_account_info(),
+                to: ctx.accounts.swap_vault.to_account_info(),
+                authority: ctx.accounts.maker.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, maker_cash)?;
+    }
+
+    let swap = &mut ctx.accounts.swap;
+    swap.maker = ctx.accounts.maker.key();
+    swap.taker = ctx.accounts.taker.key();
+    swap.swap_id = swap_id;
+    swap.maker_items = maker_items;
+    swap.taker_items = taker_items;
+    swap.mint = ctx.accounts.mint.key();
+    swap.maker_cash = maker_cash;
+    swap.taker_cash = taker_cash;
+    swap.expiry = expiry;
+    swap.delivery_window = delivery_window;
+    swap.deadline = 0;
+    swap.proposed_at = now;
+    swap.accepted_at = 0;
+    swap.status = SwapStatus::Proposed;
+    swap.bump = ctx.bumps.swap;
+
+    emit!(SwapProposed {
+        swap: swap.key(),
+        maker: swap.maker,
+        taker: swap.taker,
+        maker_items: swap.maker_items.clone(),
+        taker_items: swap.taker_items.clone(),
+        maker_cash,
+        taker_cash,
+    });
+
+    Ok(())
+}
+
+pub fn accept_swap(ctx: Context<AcceptSwap>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(!config.paused, TradeEscrowError::ContractPaused);
+
+    let now = Clock::get()?.unix_timestamp;
+    let swap = &mut ctx.accounts.swap;
+    require!(now <= swap.expiry, TradeEscrowError::SwapExpired);
+
+    count_exposure(
+        config,
+        &mut ctx.accounts.taker_stats,
+        ctx.accounts.taker.key(),
+        ctx.bumps.taker_stats,
+        swap.taker_cash,
+        now,
+    )?;
+    require!(
+        ctx.accounts.taker_token_account.amount >= swap.taker_cash,
+        TradeEscrowError::InsufficientFunds
+    );
+
+    if swap.taker_cash > 0 {
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.taker_token_account.to_account_info(),
+                to: ctx.accounts.swap_vault.to_account_info(),
+                authority: ctx.accounts.taker.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, swap.taker_cash)?;
+    }
+
+    swap.deadline = now + swap.delivery_window;
+    swap.accepted_at = now;
+    swap.status = SwapStatus::Locked;
+
+    emit!(SwapAccepted {
+        swap: swap.key(),
+        taker: swap.taker,
+        deadline: swap.deadline,
+    });
+
+    Ok(())
+}
+
+pub fn settle_swap(ctx: Context<CompleteSwap>, oracle_signatures: Vec<[u8; 64]>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    require!(!config.paused, TradeEscrowError::ContractPaused);
+
+    let swap = &ctx.accounts.swap;
+    require!(
+        !swap.is_expired(Clock::get()?.unix_timestamp),
+        TradeEscrowError::CannotSettle
+    );
+
+    // Oracles attest delivery in both directions in one receipt
+    require!(
+        oracle_signatures.len() >= 2,
+        TradeEscrowError::InsufficientOracleSignatures
+    );
+    let valid_signatures = count_oracle_signatures(
+        &ctx.accounts.instructions_sysvar,
+        &config.oracle_pubkeys,
+        &oracle_signatures,
+        swap.settlement_message(&swap.key()).as_bytes(),
+    )?;
+    require!(
+        valid_signatures >= 2,
+        TradeEscrowError::InvalidOracleSignatures
+    );
+
+    // Each cash leg goes to the other side
+    pay_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.taker_token_account,
+        swap,
+        swap.maker_cash,
+    )?;
+    pay_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.maker_token_account,
+        swap,
+        swap.taker_cash,
+    )?;
+    close_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.maker.to_account_info(),
+        swap,
+    )?;
+
+    let config = &mut ctx.accounts.config;
+    config.total_locked = config.total_locked
+        .checked_sub(swap.total_cash()?)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+    emit!(SwapSettled {
+        swap: swap.key(),
+        maker: swap.maker,
+        taker: swap.taker,
+        oracle_count: valid_signatures,
+    });
+
+    let receipt = swap.receipt(
+        swap.key(),
+        TradeOutcome::Settled,
+        Clock::get()?.unix_timestamp,
+    )?;
+    append_receipt(&mut ctx.accounts.receipt_tree, receipt)?;
+
+    Ok(())
+}
+
+/// Return both cash legs after the deadline, or early on an oracle failure receipt
+pub fn refund_swap(ctx: Context<RefundSwap>, oracle_signatures: Vec<[u8; 64]>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let swap = &ctx.accounts.swap;
+
+    let expired = swap.is_expired(Clock::get()?.unix_timestamp);
+    if !expired {
+        let valid_signatures = count_oracle_signatures(
+            &ctx.accounts.instructions_sysvar,
+            &config.oracle_pubkeys,
+            &oracle_signatures,
+            swap.failure_message(&swap.key()).as_bytes(),
+        )?;
+        require!(valid_signatures >= 2, TradeEscrowError::CannotRefund);
+    }
+
+    pay_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.maker_token_account,
+        swap,
+        swap.maker_cash,
+    )?;
+    pay_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.taker_token_account,
+        swap,
+        swap.taker_cash,
+    )?;
+    close_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.maker.to_account_info(),
+        swap,
+    )?;
+
+    // Neither cash leg traded, so both stop counting against their owner's limit
+    let config = &mut ctx.accounts.config;
+    release_exposure(config, &mut ctx.accounts.maker_stats, swap.maker_cash, swap.proposed_at)?;
+    release_exposure(config, &mut ctx.accounts.taker_stats, swap.taker_cash, swap.accepted_at)?;
+
+    emit!(SwapRefunded {
+        swap: swap.key(),
+        maker: swap.maker,
+        taker: swap.taker,
+        reason: if expired { RefundReason::DeadlineExpired } else { RefundReason::DeliveryFailed },
+    });
+
+    let receipt = swap.receipt(
+        swap.key(),
+        TradeOutcome::Refunded,
+        Clock::get()?.unix_timestamp,
+    )?;
+    append_receipt(&mut ctx.accounts.receipt_tree, receipt)?;
+
+    Ok(())
+}
+
+pub fn cancel_swap(ctx: Context<CancelSwap>) -> Result<()> {
+    let swap = &ctx.accounts.swap;
+
+    pay_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.maker_token_account,
+        swap,
+        swap.maker_cash,
+    )?;
+
+    close_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.swap_vault,
+        &ctx.accounts.maker.to_account_info(),
+        swap,
+    )?;
+
+    // The swap never traded, so the maker's cash no longer counts against their limit
+    release_exposure(
+        &mut ctx.accounts.config,
+        &mut ctx.accounts.maker_stats,
+        swap.maker_cash,
+        swap.proposed_at,
+    )?;
+
+    emit!(SwapCancelled {
+        swap: swap.key(),
+        maker: swap.maker,
+    });
+
+    Ok(())
+}
\ No newline at end of file
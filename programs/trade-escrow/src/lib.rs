@@ -1,5 +1,4 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 declare_id!("TradeEscrow11111111111111111111111111111111");
 
@@ -10,7 +9,6 @@ pub mod utils;
 
 use instructions::*;
 use state::*;
-use errors::*;
 
 #[program]
 pub mod trade_escrow {
@@ -22,15 +20,59 @@ pub mod trade_escrow {
     }
 
     /// Lock funds in escrow for a trade
+    #[allow(clippy::too_many_arguments)]
     pub fn lock(
         ctx: Context<Lock>,
         asset_id: u64,
+        item_class: ItemClass, // seller-signed
         amount: u64,
         price_max: u64,
         ask_signature: [u8; 64],
         deadline_offset: i64, // seconds from now
+        fee_payer_override: Option<FeePayer>, // seller-signed
+        allowed_buyer: Option<Pubkey>, // seller-signed
+        price_override: bool, // accept a price outside the reference band
     ) -> Result<()> {
-        instructions::lock(ctx, asset_id, amount, price_max, ask_signature, deadline_offset)
+        instructions::lock(
+            ctx,
+            asset_id,
+            item_class,
+            amount,
+            price_max,
+            ask_signature,
+            deadline_offset,
+            fee_payer_override,
+            allowed_buyer,
+            price_override,
+        )
+    }
+
+    /// Lock funds for a USD-priced ask, converted through the mint's price feed
+    #[allow(clippy::too_many_arguments)]
+    pub fn lock_usd(
+        ctx: Context<Lock>,
+        asset_id: u64,
+        item_class: ItemClass, // seller-signed
+        usd_amount: u64, // scaled by 10^USD_DECIMALS
+        price_max: u64,
+        ask_signature: [u8; 64],
+        deadline_offset: i64,
+        fee_payer_override: Option<FeePayer>,
+        allowed_buyer: Option<Pubkey>,
+        price_override: bool,
+    ) -> Result<()> {
+        instructions::lock_usd(
+            ctx,
+            asset_id,
+            item_class,
+            usd_amount,
+            price_max,
+            ask_signature,
+            deadline_offset,
+            fee_payer_override,
+            allowed_buyer,
+            price_override,
+        )
     }
 
     /// Settle escrow with oracle receipt
@@ -46,6 +88,218 @@ pub mod trade_escrow {
         instructions::refund(ctx)
     }
 
+    /// List an item for sale on-chain
+    pub fn create_listing(
+        ctx: Context<CreateListing>,
+        listing_id: u64,
+        asset_id: u64,
+        price: u64,
+        expiry: i64,
+        allowed_buyer: Option<Pubkey>,
+        fee_payer: Option<FeePayer>,
+    ) -> Result<()> {
+        instructions::create_listing(ctx, listing_id, asset_id, price, expiry, allowed_buyer, fee_payer)
+    }
+
+    /// List an item with a price that decays over time
+    pub fn create_dutch_listing(
+        ctx: Context<CreateListing>,
+        listing_id: u64,
+        asset_id: u64,
+        pricing: DutchPricing,
+        expiry: i64,
+        allowed_buyer: Option<Pubkey>,
+        fee_payer: Option<FeePayer>,
+    ) -> Result<()> {
+        instructions::create_dutch_listing(ctx, listing_id, asset_id, pricing, expiry, allowed_buyer, fee_payer)
+    }
+
+    /// Change the price of an open fixed-price listing (seller only)
+    pub fn update_listing_price(ctx: Context<UpdateListingPrice>, new_price: u64) -> Result<()> {
+        instructions::update_listing_price(ctx, new_price)
+    }
+
+    /// Withdraw an open listing (seller only)
+    pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
+        instructions::cancel_listing(ctx)
+    }
+
+    /// Reclaim the rent of a filled listing (seller only)
+    pub fn close_listing(ctx: Context<CloseListing>) -> Result<()> {
+        instructions::close_listing(ctx)
+    }
+
+    /// Buy a listing, locking its current price in a new escrow
+    pub fn buy_listing(
+        ctx: Context<BuyListing>,
+        price_max: u64,
+        deadline_offset: i64, // seconds from now
+    ) -> Result<()> {
+        instructions::buy_listing(ctx, price_max, deadline_offset)
+    }
+
+    /// Post a funded bid for any item matching the criteria
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_buy_order(
+        ctx: Context<CreateBuyOrder>,
+        order_id: u64,
+        item_class: ItemClass,
+        wear_bounds: Option<WearBounds>,
+        unit_price: u64,
+        quantity: u32,
+        expiry: i64,
+        fee_payer: Option<FeePayer>,
+    ) -> Result<()> {
+        instructions::create_buy_order(
+            ctx,
+            order_id,
+            item_class,
+            wear_bounds,
+            unit_price,
+            quantity,
+            expiry,
+            fee_payer,
+        )
+    }
+
+    /// Fill one item of a bid, opening an escrow funded by the bid
+    pub fn fill_buy_order(
+        ctx: Context<FillBuyOrder>,
+        asset_id: u64,
+        wear: Option<u32>,
+        deadline_offset: i64, // seconds from now
+    ) -> Result<()> {
+        instructions::fill_buy_order(ctx, asset_id, wear, deadline_offset)
+    }
+
+    /// Cancel a bid and return its remaining balance (buyer only)
+    pub fn cancel_buy_order(ctx: Context<CancelBuyOrder>) -> Result<()> {
+        instructions::cancel_buy_order(ctx)
+    }
+
+    /// Start an English auction for an item
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        auction_id: u64,
+        asset_id: u64,
+        reserve_price: u64,
+        min_increment: u64,
+        end_time: i64,
+        extension_window: i64,
+        delivery_window: i64,
+        fee_payer: Option<FeePayer>,
+    ) -> Result<()> {
+        instructions::create_auction(
+            ctx,
+            auction_id,
+            asset_id,
+            reserve_price,
+            min_increment,
+            end_time,
+            extension_window,
+            delivery_window,
+            fee_payer,
+        )
+    }
+
+    /// Outbid the current high bidder, refunding them
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        instructions::place_bid(ctx, amount)
+    }
+
+    /// Turn the winning bid into a trade escrow once bidding has closed
+    pub fn finalize_auction(ctx: Context<FinalizeAuction>) -> Result<()> {
+        instructions::finalize_auction(ctx)
+    }
+
+    /// Withdraw an auction that has no bids (seller only)
+    pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+        instructions::cancel_auction(ctx)
+    }
+
+    /// Offer an item-for-item swap, with optional cash on either side
+    #[allow(clippy::too_many_arguments)]
+    pub fn propose_swap(
+        ctx: Context<ProposeSwap>,
+        swap_id: u64,
+        maker_items: Vec<u64>,
+        taker_items: Vec<u64>,
+        maker_cash: u64,
+        taker_cash: u64,
+        expiry: i64,
+        delivery_window: i64,
+    ) -> Result<()> {
+        instructions::propose_swap(
+            ctx,
+            swap_id,
+            maker_items,
+            taker_items,
+            maker_cash,
+            taker_cash,
+            expiry,
+            delivery_window,
+        )
+    }
+
+    /// Accept a swap, funding the taker's cash leg (taker only)
+    pub fn accept_swap(ctx: Context<AcceptSwap>) -> Result<()> {
+        instructions::accept_swap(ctx)
+    }
+
+    /// Release both cash legs with an oracle receipt covering both directions
+    pub fn settle_swap(ctx: Context<CompleteSwap>, oracle_signatures: Vec<[u8; 64]>) -> Result<()> {
+        instructions::settle_swap(ctx, oracle_signatures)
+    }
+
+    /// Return both cash legs after the deadline or on an oracle failure receipt
+    pub fn refund_swap(ctx: Context<RefundSwap>, oracle_signatures: Vec<[u8; 64]>) -> Result<()> {
+        instructions::refund_swap(ctx, oracle_signatures)
+    }
+
+    /// Withdraw a swap offer that has not been accepted (maker only)
+    pub fn cancel_swap(ctx: Context<CancelSwap>) -> Result<()> {
+        instructions::cancel_swap(ctx)
+    }
+
+    /// Lock funds for a quantity of a fungible commodity
+    #[allow(clippy::too_many_arguments)]
+    pub fn lock_commodity(
+        ctx: Context<LockCommodity>,
+        commodity: ItemClass,
+        quantity: u32,
+        unit_price: u64,
+        price_max: u64,
+        ask_signature: [u8; 64],
+        deadline_offset: i64,
+        fee_payer_override: Option<FeePayer>,
+    ) -> Result<()> {
+        instructions::lock_commodity(
+            ctx,
+            commodity,
+            quantity,
+            unit_price,
+            price_max,
+            ask_signature,
+            deadline_offset,
+            fee_payer_override,
+        )
+    }
+
+    /// Settle the delivered part of a commodity escrow and refund the rest
+    pub fn settle_commodity(
+        ctx: Context<SettleCommodity>,
+        delivered: u32,
+        oracle_signatures: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        instructions::settle_commodity(ctx, delivered, oracle_signatures)
+    }
+
+    /// Refund an expired commodity escrow (buyer only)
+    pub fn refund_commodity(ctx: Context<RefundCommodity>) -> Result<()> {
+        instructions::refund_commodity(ctx)
+    }
+
     /// Emergency pause (guardian only)
     pub fn pause(ctx: Context<Pause>) -> Result<()> {
         instructions::pause(ctx)
@@ -60,17 +314,116 @@ pub mod trade_escrow {
     pub fn update_oracles(ctx: Context<UpdateOracles>, new_oracles: [Pubkey; 3]) -> Result<()> {
         instructions::update_oracles(ctx, new_oracles)
     }
+
+    /// Update exposure limits (admin only)
+    pub fn update_limits(
+        ctx: Context<UpdateLimits>,
+        max_tvl: u64,
+        max_trade_amount: u64,
+        user_limit_amount: u64,
+        user_limit_window: i64,
+    ) -> Result<()> {
+        instructions::update_limits(ctx, max_tvl, max_trade_amount, user_limit_amount, user_limit_window)
+    }
+
+    /// Update protocol fee, referral share and fee payer policy (admin only)
+    pub fn update_fees(
+        ctx: Context<UpdateFees>,
+        fee_bps: u16,
+        referral_share_bps: u16,
+        fee_payer: FeePayer,
+    ) -> Result<()> {
+        instructions::update_fees(ctx, fee_bps, referral_share_bps, fee_payer)
+    }
+
+    /// Replace the price and volume fee tiers; volume is the buyer's (admin only)
+    pub fn update_fee_schedule(
+        ctx: Context<UpdateFeeSchedule>,
+        price_tiers: [PriceTier; FEE_TIER_COUNT],
+        volume_tiers: [VolumeTier; FEE_TIER_COUNT],
+    ) -> Result<()> {
+        instructions::update_fee_schedule(ctx, price_tiers, volume_tiers)
+    }
+
+    /// Set the price publisher, reference price band and price feed limits (admin only)
+    pub fn update_price_checks(
+        ctx: Context<UpdatePriceChecks>,
+        price_publisher: Pubkey,
+        price_band_bps: u16,
+        max_price_staleness: i64,
+        max_confidence_bps: u16,
+        max_reference_age: i64,
+    ) -> Result<()> {
+        instructions::update_price_checks(
+            ctx,
+            price_publisher,
+            price_band_bps,
+            max_price_staleness,
+            max_confidence_bps,
+            max_reference_age,
+        )
+    }
+
+    /// Publish the USD price of a mint (price publisher only)
+    pub fn publish_price_feed(
+        ctx: Context<PublishPriceFeed>,
+        price: u64,
+        confidence: u64,
+    ) -> Result<()> {
+        instructions::publish_price_feed(ctx, price, confidence)
+    }
+
+    /// Publish the reference price for an item class (price publisher only)
+    pub fn publish_reference_price(
+        ctx: Context<PublishReferencePrice>,
+        item_class: ItemClass,
+        price: u64,
+    ) -> Result<()> {
+        instructions::publish_reference_price(ctx, item_class, price)
+    }
+
+    /// Create the fee vault and receipt tree for a mint (admin only)
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        instructions::initialize_fee_vault(ctx)
+    }
+
+    /// Set the minimum fee for a mint (admin only)
+    pub fn update_min_fee(ctx: Context<UpdateMinFee>, min_fee: u64) -> Result<()> {
+        instructions::update_min_fee(ctx, min_fee)
+    }
+
+    /// Sweep accumulated fees (admin, or to the configured fee recipient)
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+        instructions::withdraw_fees(ctx)
+    }
+
+    /// Fees accumulated for a mint
+    pub fn get_accrued_fees(ctx: Context<GetAccruedFees>) -> Result<u64> {
+        instructions::get_accrued_fees(ctx)
+    }
 }
 
 // Event emissions
+//
+// Escrow lifecycle events (`EscrowLocked`, `EscrowSettled`, `EscrowRefunded`,
+// `CommodityEscrowLocked`, `CommodityEscrowSettled`) and config events
+// (`EmergencyPause`, `ConfigUpdated`) carry a `sequence` from
+// `Config::next_event_sequence`, so indexers of them can detect gaps. The
+// other events are not numbered: listings, orders, auctions and swaps are
+// followed through their own accounts, and numbering every event would make
+// each marketplace transaction write the config.
 #[event]
 pub struct EscrowLocked {
     pub escrow_id: Pubkey,
     pub buyer: Pubkey,
     pub seller: Pubkey,
+    pub mint: Pubkey,
     pub asset_id: u64,
     pub amount: u64,
+    pub fee: u64,
     pub deadline: i64,
+    pub usd_rate: Option<u64>,
+    pub sequence: u64,
 }
 
 #[event]
@@ -79,7 +432,11 @@ pub struct EscrowSettled {
     pub buyer: Pubkey,
     pub seller: Pubkey,
     pub amount: u64,
+    pub protocol_fee: u64,
+    pub referrer: Option<Pubkey>,
+    pub referral_fee: u64,
     pub oracle_count: u8,
+    pub sequence: u64,
 }
 
 #[event]
@@ -87,18 +444,228 @@ pub struct EscrowRefunded {
     pub escrow_id: Pubkey,
     pub buyer: Pubkey,
     pub amount: u64,
-    pub reason: String,
+    pub reason: RefundReason,
+    pub sequence: u64,
+}
+
+#[event]
+pub struct ReceiptAppended {
+    pub mint: Pubkey,
+    pub index: u64,
+    pub receipt: Receipt,
+    pub root: [u8; 32],
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub withdrawn_by: Pubkey,
+}
+
+#[event]
+pub struct ListingCreated {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub asset_id: u64,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub expiry: i64,
+}
+
+#[event]
+pub struct ListingPriceUpdated {
+    pub listing: Pubkey,
+    pub old_price: u64,
+    pub new_price: u64,
+}
+
+#[event]
+pub struct ListingCancelled {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct ListingClosed {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct ListingFilled {
+    pub listing: Pubkey,
+    pub escrow_id: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct BuyOrderCreated {
+    pub buy_order: Pubkey,
+    pub buyer: Pubkey,
+    pub item_class: ItemClass,
+    pub unit_price: u64,
+    pub quantity: u32,
+    pub deposit: u64,
+    pub expiry: i64,
+}
+
+#[event]
+pub struct BuyOrderFilled {
+    pub buy_order: Pubkey,
+    pub escrow_id: Pubkey,
+    pub seller: Pubkey,
+    pub asset_id: u64,
+    pub remaining: u32,
+}
+
+#[event]
+pub struct BuyOrderCancelled {
+    pub buy_order: Pubkey,
+    pub buyer: Pubkey,
+    pub unfilled: u32,
+    pub refunded: u64,
+}
+
+#[event]
+pub struct AuctionCreated {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub asset_id: u64,
+    pub mint: Pubkey,
+    pub reserve_price: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct AuctionFinalized {
+    pub auction: Pubkey,
+    pub winner: Pubkey,
+    pub escrow_id: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AuctionCancelled {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct SwapProposed {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub maker_items: Vec<u64>,
+    pub taker_items: Vec<u64>,
+    pub maker_cash: u64,
+    pub taker_cash: u64,
+}
+
+#[event]
+pub struct SwapAccepted {
+    pub swap: Pubkey,
+    pub taker: Pubkey,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct SwapSettled {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub oracle_count: u8,
+}
+
+#[event]
+pub struct SwapRefunded {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub reason: RefundReason,
+}
+
+#[event]
+pub struct SwapCancelled {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+}
+
+#[event]
+pub struct CommodityEscrowLocked {
+    pub escrow_id: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub commodity: ItemClass,
+    pub quantity: u32,
+    pub unit_price: u64,
+    pub fee: u64,
+    pub deadline: i64,
+    pub sequence: u64,
+}
+
+#[event]
+pub struct CommodityEscrowSettled {
+    pub escrow_id: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub delivered: u32,
+    pub quantity: u32,
+    pub amount: u64,
+    pub protocol_fee: u64,
+    pub refunded: u64,
+    pub oracle_count: u8,
+    pub sequence: u64,
+}
+
+#[event]
+pub struct ReferencePricePublished {
+    pub price_reference: Pubkey,
+    pub item_class: ItemClass,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceDeviationFlagged {
+    pub escrow_id: Pubkey,
+    pub reference_price: u64,
+    pub amount: u64,
+    pub band_bps: u16,
+}
+
+#[event]
+pub struct PriceFeedPublished {
+    pub price_feed: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub confidence: u64,
+    pub timestamp: i64,
 }
 
 #[event]
 pub struct EmergencyPause {
     pub triggered_by: Pubkey,
     pub timestamp: i64,
+    pub sequence: u64,
 }
 
 #[event]
 pub struct ConfigUpdated {
     pub updated_by: Pubkey,
-    pub change_type: String,
+    pub change: ConfigChange,
     pub timestamp: i64,
+    pub sequence: u64,
 }
\ No newline at end of file
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use crate::state::FeePayer;
+
+#[account]
+#[derive(Default)]
+pub struct Auction {
+    /// Seller's wallet address
+    pub seller: Pubkey,
+    /// Seller-chosen identifier, unique per seller
+    pub auction_id: u64,
+    /// Steam asset ID being auctioned
+    pub asset_id: u64,
+    /// Mint bids are made in
+    pub mint: Pubkey,
+    /// Lowest acceptable first bid
+    pub reserve_price: u64,
+    /// Minimum raise over the current high bid
+    pub min_increment: u64,
+    /// Bidding closes at this time (Unix timestamp)
+    pub end_time: i64,
+    /// A bid this close to the end pushes the end back by the same amount (seconds)
+    pub extension_window: i64,
+    /// Seconds the seller has to deliver once the auction is finalized
+    pub delivery_window: i64,
+    /// Party bearing the fee, fixed when the auction is created
+    pub fee_payer: FeePayer,
+    /// Current high bidder, if any
+    pub highest_bidder: Option<Pubkey>,
+    /// Current high bid (the item price)
+    pub highest_bid: u64,
+    /// Fee quoted to the high bidder
+    pub highest_fee: u64,
+    /// Fee rate quoted to the high bidder, in basis points
+    pub highest_fee_bps: u16,
+    /// Amount the high bidder holds in the auction vault
+    pub highest_deposit: u64,
+    /// When the high bid was placed and its deposit counted against the limits
+    pub highest_bid_at: i64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl Auction {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // seller
+        8 +  // auction_id
+        8 +  // asset_id
+        32 + // mint
+        8 +  // reserve_price
+        8 +  // min_increment
+        8 +  // end_time
+        8 +  // extension_window
+        8 +  // delivery_window
+        1 +  // fee_payer
+        1 + 32 + // highest_bidder
+        8 +  // highest_bid
+        8 +  // highest_fee
+        2 +  // highest_fee_bps
+        8 +  // highest_deposit
+        8 +  // highest_bid_at
+        1;   // bump
+
+    pub fn has_ended(&self, now: i64) -> bool {
+        now >= self.end_time
+    }
+
+    /// Lowest bid the auction accepts next
+    pub fn min_next_bid(&self) -> u64 {
+        match self.highest_bidder {
+            Some(_) => self.highest_bid.saturating_add(self.min_increment),
+            None => self.reserve_price,
+        }
+    }
+
+    /// Push the end time back when a bid lands inside the extension window
+    pub fn extend_for_bid(&mut self, now: i64) {
+        let extended = now.saturating_add(self.extension_window);
+        if extended > self.end_time {
+            self.end_time = extended;
+        }
+    }
+}
+
+/// Seeds for auction PDA
+pub const AUCTION_SEED: &[u8] = b"auction";
+
+/// Seeds for the auction's bid vault
+pub const AUCTION_VAULT_SEED: &[u8] = b"auction_vault";
+
+/// Generate auction PDA
+pub fn get_auction_pda(seller: &Pubkey, auction_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AUCTION_SEED, seller.as_ref(), &auction_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Generate auction vault PDA
+pub fn get_auction_vault_pda(auction: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUCTION_VAULT_SEED, auction.as_ref()], program_id)
+}
\ No newline at end of file
@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+use crate::errors::TradeEscrowError;
+use crate::state::{FeePayer, ItemClass, WearBounds};
+
+#[account]
+#[derive(Default)]
+pub struct BuyOrder {
+    /// Buyer's wallet address
+    pub buyer: Pubkey,
+    /// Buyer-chosen identifier, unique per buyer
+    pub order_id: u64,
+    /// Mint the bid is funded in
+    pub mint: Pubkey,
+    /// Item class any fill must belong to
+    pub item_class: ItemClass,
+    /// Accepted wear range, if the buyer cares
+    pub wear_bounds: Option<WearBounds>,
+    /// Price paid per item (in token units)
+    pub unit_price: u64,
+    /// Number of items wanted
+    pub quantity: u32,
+    /// Number of items filled so far
+    pub filled: u32,
+    /// Bid can no longer be filled after this time (Unix timestamp)
+    pub expiry: i64,
+    /// Party bearing the fee, fixed when the bid is created
+    pub fee_payer: FeePayer,
+    /// Fee reserved per item at creation, charged on every fill
+    pub unit_fee: u64,
+    /// Fee rate of `unit_fee`, in basis points
+    pub unit_fee_bps: u16,
+    /// When the bid was created and its deposit counted against the limits
+    pub created_at: i64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl BuyOrder {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // buyer
+        8 +  // order_id
+        32 + // mint
+        ItemClass::LEN + // item_class
+        1 + WearBounds::LEN + // wear_bounds
+        8 +  // unit_price
+        4 +  // quantity
+        4 +  // filled
+        8 +  // expiry
+        1 +  // fee_payer
+        8 +  // unit_fee
+        2 +  // unit_fee_bps
+        8 +  // created_at
+        1;   // bump
+
+    pub fn remaining(&self) -> u32 {
+        self.quantity.saturating_sub(self.filled)
+    }
+
+    /// Amount moved into the escrow of each fill: the price plus the buyer's share of the fee
+    pub fn unit_deposit(&self) -> Result<u64> {
+        let (buyer_fee, _) = self.fee_payer.split(self.unit_fee);
+        Ok(self.unit_price
+            .checked_add(buyer_fee)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    /// Deposit still held for the unfilled items
+    pub fn unfilled_deposit(&self) -> Result<u64> {
+        Ok(self.unit_deposit()?
+            .checked_mul(self.remaining() as u64)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expiry
+    }
+
+    /// Whether an item with `wear` satisfies the bid's condition bounds
+    pub fn accepts_wear(&self, wear: Option<u32>) -> bool {
+        match (self.wear_bounds, wear) {
+            (None, _) => true,
+            (Some(bounds), Some(wear)) => bounds.contains(wear),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Seeds for buy order PDA
+pub const BUY_ORDER_SEED: &[u8] = b"buy_order";
+
+/// Seeds for the buy order's funding vault
+pub const BUY_ORDER_VAULT_SEED: &[u8] = b"buy_order_vault";
+
+/// Generate buy order PDA
+pub fn get_buy_order_pda(buyer: &Pubkey, order_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BUY_ORDER_SEED, buyer.as_ref(), &order_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Generate buy order vault PDA
+pub fn get_buy_order_vault_pda(buy_order: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BUY_ORDER_VAULT_SEED, buy_order.as_ref()], program_id)
+}
\ No newline at end of file
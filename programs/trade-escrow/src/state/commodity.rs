@@ -0,0 +1,154 @@
+use anchor_lang::prelude::*;
+use crate::errors::TradeEscrowError;
+use crate::state::{FeePayer, ItemClass, Receipt, TradeOutcome, TradedItem};
+
+/// Escrow for a quantity of a fungible item, e.g. 50 of the same case
+#[account]
+#[derive(Default)]
+pub struct CommodityEscrow {
+    /// Buyer's wallet address
+    pub buyer: Pubkey,
+    /// Seller's wallet address
+    pub seller: Pubkey,
+    /// Commodity being traded
+    pub commodity: ItemClass,
+    /// Number of items bought
+    pub quantity: u32,
+    /// Price per item
+    pub unit_price: u64,
+    /// Deadline for trade completion (Unix timestamp)
+    pub deadline: i64,
+    /// When the buyer's funding was counted against the limits (Unix timestamp)
+    pub locked_at: i64,
+    /// Whether the escrow has been settled or refunded
+    pub settled: bool,
+    /// Nonce for uniqueness
+    pub nonce: u64,
+    /// Party bearing the fee for this trade
+    pub fee_payer: FeePayer,
+    /// Fee charged at lock time for the full quantity
+    pub fee_amount: u64,
+    /// Effective fee rate charged at lock time, in basis points of the amount
+    pub fee_bps: u16,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl CommodityEscrow {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // buyer
+        32 + // seller
+        ItemClass::LEN + // commodity
+        4 +  // quantity
+        8 +  // unit_price
+        8 +  // deadline
+        8 +  // locked_at
+        1 +  // settled
+        8 +  // nonce
+        1 +  // fee_payer
+        8 +  // fee_amount
+        2 +  // fee_bps
+        1;   // bump
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.deadline
+    }
+
+    /// Price of the full quantity
+    pub fn amount(&self) -> Result<u64> {
+        Ok(self.unit_price
+            .checked_mul(self.quantity as u64)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    /// Total deposited into the vault at lock
+    pub fn locked_total(&self) -> Result<u64> {
+        let (buyer_fee, _) = self.fee_payer.split(self.fee_amount);
+        Ok(self.amount()?
+            .checked_add(buyer_fee)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    /// Fee owed when `delivered` of the items arrived, pro rata
+    pub fn fee_for(&self, delivered: u32) -> Result<u64> {
+        if self.quantity == 0 {
+            return Ok(0);
+        }
+        let fee = self.fee_amount as u128 * delivered as u128 / self.quantity as u128;
+        Ok(u64::try_from(fee).map_err(|_| TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    /// Split of the vault for a partial delivery: (seller, protocol fee, buyer refund)
+    pub fn settlement_amounts(&self, delivered: u32) -> Result<(u64, u64, u64)> {
+        let delivered_amount = self.unit_price
+            .checked_mul(delivered as u64)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        let fee = self.fee_for(delivered)?;
+        let (buyer_fee, seller_fee) = self.fee_payer.split(fee);
+        let seller_amount = delivered_amount
+            .checked_sub(seller_fee)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        let refund = self.locked_total()?
+            .checked_sub(delivered_amount)
+            .and_then(|refund| refund.checked_sub(buyer_fee))
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        Ok((seller_amount, fee, refund))
+    }
+
+    /// Receipt of the escrow at `escrow` closing with `quantity` of the items
+    /// paid for on settlement, or returned on refund
+    pub fn receipt(
+        &self,
+        escrow: Pubkey,
+        quantity: u32,
+        outcome: TradeOutcome,
+        timestamp: i64,
+    ) -> Result<Receipt> {
+        let amount = self.unit_price
+            .checked_mul(quantity as u64)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        Ok(Receipt {
+            escrow,
+            buyer: self.buyer,
+            seller: self.seller,
+            item: TradedItem::Commodity { class: self.commodity, quantity },
+            amount,
+            outcome,
+            timestamp,
+        })
+    }
+
+    /// Message the oracles sign to attest `delivered` of the items arrived
+    pub fn settlement_message(&self, escrow: &Pubkey, delivered: u32) -> String {
+        format!(
+            "settle_commodity:{}:{}:{}:{}:{}",
+            self.commodity.identifier(),
+            delivered,
+            self.quantity,
+            self.buyer,
+            escrow
+        )
+    }
+}
+
+/// Seeds for PDA derivation
+pub const COMMODITY_ESCROW_SEED: &[u8] = b"commodity_escrow";
+
+/// Generate commodity escrow PDA
+pub fn get_commodity_escrow_pda(
+    buyer: &Pubkey,
+    seller: &Pubkey,
+    nonce: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            COMMODITY_ESCROW_SEED,
+            buyer.as_ref(),
+            seller.as_ref(),
+            &nonce.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
\ No newline at end of file
@@ -1,6 +1,125 @@
 use anchor_lang::prelude::*;
+use crate::errors::TradeEscrowError;
+use crate::state::{FeeVault, UserStats};
+
+/// Number of price and volume tiers in the fee schedule
+pub const FEE_TIER_COUNT: usize = 4;
+
+/// Fee rate applied to trades at or above `min_amount`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PriceTier {
+    /// Trade amount where this tier starts (0 = unused)
+    pub min_amount: u64,
+    /// Fee in basis points for this tier
+    pub fee_bps: u16,
+}
+
+/// Fee discount for buyers whose 30-day volume reaches `min_volume`.
+///
+/// Only purchases count as volume, and the discount follows the buyer even
+/// when the seller bears some or all of the fee.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct VolumeTier {
+    /// 30-day volume where this tier starts (0 = unused)
+    pub min_volume: u64,
+    /// Discount in basis points of the fee
+    pub discount_bps: u16,
+}
+
+/// Which party bears the protocol fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FeePayer {
+    /// Buyer locks `amount + fee`, seller receives `amount`
+    #[default]
+    Buyer,
+    /// Buyer locks `amount`, seller receives `amount - fee`
+    Seller,
+    /// Fee shared evenly, with the odd unit charged to the seller
+    Split,
+}
+
+impl FeePayer {
+    /// Split a fee into (buyer share, seller share)
+    pub fn split(&self, fee: u64) -> (u64, u64) {
+        match self {
+            FeePayer::Buyer => (fee, 0),
+            FeePayer::Seller => (0, fee),
+            FeePayer::Split => (fee / 2, fee - fee / 2),
+        }
+    }
+
+    /// Label used in signed ask messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeePayer::Buyer => "buyer",
+            FeePayer::Seller => "seller",
+            FeePayer::Split => "split",
+        }
+    }
+}
+
+/// Fee settings changed together by `update_fees`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FeeSettings {
+    pub fee_bps: u16,
+    pub referral_share_bps: u16,
+    pub fee_payer: FeePayer,
+}
+
+/// Exposure limits changed together by `update_limits`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LimitSettings {
+    pub max_tvl: u64,
+    pub max_trade_amount: u64,
+    pub user_limit_amount: u64,
+    pub user_limit_window: i64,
+}
+
+/// Price check settings changed together by `update_price_checks`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PriceCheckSettings {
+    pub price_publisher: Pubkey,
+    pub price_band_bps: u16,
+    pub max_price_staleness: i64,
+    pub max_confidence_bps: u16,
+    pub max_reference_age: i64,
+}
+
+/// What a `ConfigUpdated` event changed, with the values before and after
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum ConfigChange {
+    Unpause,
+    Oracles {
+        old: [Pubkey; 3],
+        new: [Pubkey; 3],
+    },
+    Limits {
+        old: LimitSettings,
+        new: LimitSettings,
+    },
+    Fees {
+        old: FeeSettings,
+        new: FeeSettings,
+    },
+    FeeSchedule {
+        old_price_tiers: [PriceTier; FEE_TIER_COUNT],
+        new_price_tiers: [PriceTier; FEE_TIER_COUNT],
+        old_volume_tiers: [VolumeTier; FEE_TIER_COUNT],
+        new_volume_tiers: [VolumeTier; FEE_TIER_COUNT],
+    },
+    MinFee {
+        mint: Pubkey,
+        old: u64,
+        new: u64,
+    },
+    PriceChecks {
+        old: PriceCheckSettings,
+        new: PriceCheckSettings,
+    },
+}
 
 #[account]
+#[derive(Default)]
 pub struct Config {
     /// Oracle public keys (3 total, need 2-of-3 signatures)
     pub oracle_pubkeys: [Pubkey; 3],
@@ -14,6 +133,36 @@ pub struct Config {
     pub fee_bps: u16,
     /// Fee recipient
     pub fee_recipient: Pubkey,
+    /// Share of the protocol fee paid to the referrer, in basis points of the fee
+    pub referral_share_bps: u16,
+    /// Default fee payer for asks that do not override it
+    pub fee_payer: FeePayer,
+    /// Fee rates by trade amount, overriding `fee_bps` above each breakpoint
+    pub price_tiers: [PriceTier; FEE_TIER_COUNT],
+    /// Fee discounts by the buyer's 30-day settled volume, whoever pays the fee
+    pub volume_tiers: [VolumeTier; FEE_TIER_COUNT],
+    /// Maximum value held across all escrows (0 = no ceiling)
+    pub max_tvl: u64,
+    /// Maximum value of a single trade including fee (0 = no cap)
+    pub max_trade_amount: u64,
+    /// Maximum a single buyer may lock within any rolling window (0 = no limit)
+    pub user_limit_amount: u64,
+    /// Length of the per-buyer rolling limit window in seconds
+    pub user_limit_window: i64,
+    /// Key allowed to publish reference prices
+    pub price_publisher: Pubkey,
+    /// Allowed deviation from the reference price, in basis points (0 = no check)
+    pub price_band_bps: u16,
+    /// Maximum age of a price feed used to convert USD asks, in seconds
+    pub max_price_staleness: i64,
+    /// Maximum price feed confidence interval, in basis points of the price
+    pub max_confidence_bps: u16,
+    /// Maximum age of a reference price checked against an ask, in seconds
+    pub max_reference_age: i64,
+    /// Value currently held across all open escrows
+    pub total_locked: u64,
+    /// Sequence number of the last escrow lifecycle or config event emitted
+    pub event_sequence: u64,
     /// Bump seed for PDA derivation
     pub bump: u8,
 }
@@ -27,15 +176,146 @@ impl Config {
         32 +   // admin
         2 +    // fee_bps
         32 +   // fee_recipient
+        2 +    // referral_share_bps
+        1 +    // fee_payer
+        (8 + 2) * FEE_TIER_COUNT + // price_tiers
+        (8 + 2) * FEE_TIER_COUNT + // volume_tiers
+        8 +    // max_tvl
+        8 +    // max_trade_amount
+        8 +    // user_limit_amount
+        8 +    // user_limit_window
+        32 +   // price_publisher
+        2 +    // price_band_bps
+        8 +    // max_price_staleness
+        2 +    // max_confidence_bps
+        8 +    // max_reference_age
+        8 +    // total_locked
+        8 +    // event_sequence
         1;     // bump
 
     pub fn is_oracle(&self, pubkey: &Pubkey) -> bool {
         self.oracle_pubkeys.contains(pubkey)
     }
 
-    pub fn calculate_fee(&self, amount: u64) -> u64 {
-        (amount as u128 * self.fee_bps as u128 / 10000) as u64
+    /// Number the next escrow or config event, so indexers can detect gaps
+    pub fn next_event_sequence(&mut self) -> Result<u64> {
+        self.event_sequence = self
+            .event_sequence
+            .checked_add(1)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        Ok(self.event_sequence)
+    }
+
+    pub fn fee_settings(&self) -> FeeSettings {
+        FeeSettings {
+            fee_bps: self.fee_bps,
+            referral_share_bps: self.referral_share_bps,
+            fee_payer: self.fee_payer,
+        }
+    }
+
+    pub fn limit_settings(&self) -> LimitSettings {
+        LimitSettings {
+            max_tvl: self.max_tvl,
+            max_trade_amount: self.max_trade_amount,
+            user_limit_amount: self.user_limit_amount,
+            user_limit_window: self.user_limit_window,
+        }
+    }
+
+    pub fn price_check_settings(&self) -> PriceCheckSettings {
+        PriceCheckSettings {
+            price_publisher: self.price_publisher,
+            price_band_bps: self.price_band_bps,
+            max_price_staleness: self.max_price_staleness,
+            max_confidence_bps: self.max_confidence_bps,
+            max_reference_age: self.max_reference_age,
+        }
+    }
+
+    /// Fee for a trade of `amount` in the vault's mint by a buyer with `user_stats`.
+    ///
+    /// The volume discount is the buyer's for every `FeePayer`, since only
+    /// the buyer's stats are at hand when the fee is fixed at lock.
+    pub fn calculate_fee(
+        &self,
+        amount: u64,
+        fee_vault: &FeeVault,
+        user_stats: &UserStats,
+        now: i64,
+    ) -> Result<u64> {
+        let fee_bps = self.price_tier_bps(amount) as u128;
+        let discount_bps = self.volume_discount_bps(user_stats.volume_30d(now)) as u128;
+
+        let fee = (amount as u128)
+            .checked_mul(fee_bps)
+            .map(|fee| fee / 10000)
+            .and_then(|fee| fee.checked_sub(fee.checked_mul(discount_bps)? / 10000))
+            .and_then(|fee| u64::try_from(fee).ok())
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+
+        // The minimum fee never pushes the fee above the trade itself
+        Ok(fee.max(fee_vault.min_fee).min(amount))
+    }
+
+    /// Fee rate for `amount`: the highest price tier reached, or the base rate
+    pub fn price_tier_bps(&self, amount: u64) -> u16 {
+        self.price_tiers
+            .iter()
+            .filter(|tier| tier.min_amount > 0 && amount >= tier.min_amount)
+            .max_by_key(|tier| tier.min_amount)
+            .map_or(self.fee_bps, |tier| tier.fee_bps)
+    }
+
+    /// Discount for a 30-day volume: the highest volume tier reached
+    pub fn volume_discount_bps(&self, volume: u64) -> u16 {
+        self.volume_tiers
+            .iter()
+            .filter(|tier| tier.min_volume > 0 && volume >= tier.min_volume)
+            .max_by_key(|tier| tier.min_volume)
+            .map_or(0, |tier| tier.discount_bps)
+    }
+
+    /// Split a fee into (protocol, referral) parts at the current referral share
+    pub fn split_fee(&self, fee: u64) -> Result<(u64, u64)> {
+        split_referral_fee(fee, self.referral_share_bps)
+    }
+
+    pub fn exceeds_trade_limit(&self, total_amount: u64) -> bool {
+        self.max_trade_amount > 0 && total_amount > self.max_trade_amount
+    }
+
+    pub fn exceeds_tvl(&self, total_amount: u64) -> bool {
+        self.max_tvl > 0 && self.total_locked.saturating_add(total_amount) > self.max_tvl
+    }
+
+    pub fn has_user_limit(&self) -> bool {
+        self.user_limit_amount > 0 && self.user_limit_window > 0
+    }
+}
+
+/// Split a fee into (protocol, referral) parts, paying `referral_share_bps` of it to the referrer
+pub fn split_referral_fee(fee: u64, referral_share_bps: u16) -> Result<(u64, u64)> {
+    let referral = (fee as u128)
+        .checked_mul(referral_share_bps as u128)
+        .map(|referral| referral / 10000)
+        .and_then(|referral| u64::try_from(referral).ok())
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+    let protocol = fee
+        .checked_sub(referral)
+        .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+    Ok((protocol, referral))
+}
+
+/// Rate a `fee` charged on `amount` works out to, in basis points rounded up.
+///
+/// Reflects volume discounts and the per-mint minimum fee, unlike the tier rate.
+pub fn effective_fee_bps(amount: u64, fee: u64) -> u16 {
+    if amount == 0 {
+        return 0;
     }
+    let bps = (fee as u128 * 10000).div_ceil(amount as u128);
+    bps.min(10000) as u16
 }
 
 /// Seeds for config PDA
@@ -1,6 +1,20 @@
 use anchor_lang::prelude::*;
+use crate::errors::TradeEscrowError;
+use crate::state::{
+    split_referral_fee, FeePayer, ItemClass, Receipt, TradeOutcome, TradedItem, WearBounds,
+};
+
+/// Why an escrow's funds went back to the buyer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RefundReason {
+    /// The delivery deadline passed without a settlement
+    DeadlineExpired,
+    /// The oracles attested that delivery failed
+    DeliveryFailed,
+}
 
 #[account]
+#[derive(Default)]
 pub struct Escrow {
     /// Buyer's wallet address
     pub buyer: Pubkey,
@@ -12,10 +26,28 @@ pub struct Escrow {
     pub amount: u64,
     /// Deadline for trade completion (Unix timestamp)
     pub deadline: i64,
+    /// When the buyer's funding was counted against the limits (Unix timestamp)
+    pub locked_at: i64,
     /// Whether the escrow has been settled
     pub settled: bool,
     /// Nonce for uniqueness
     pub nonce: u64,
+    /// Integrator that referred the trade, if any
+    pub referrer: Option<Pubkey>,
+    /// Party bearing the fee for this trade
+    pub fee_payer: FeePayer,
+    /// Fee charged at lock time
+    pub fee_amount: u64,
+    /// Effective fee rate charged at lock time, in basis points of the amount
+    pub fee_bps: u16,
+    /// Share of the fee owed to the referrer, as configured at lock time
+    pub referral_share_bps: u16,
+    /// USD price of the mint used to convert a USD-denominated ask
+    pub usd_rate: Option<u64>,
+    /// Item class the delivered item must belong to, for buy order fills
+    pub item_class: Option<ItemClass>,
+    /// Wear range the delivered item must fall in, for buy order fills
+    pub wear_bounds: Option<WearBounds>,
     /// Bump seed for PDA derivation
     pub bump: u8,
 }
@@ -28,10 +60,29 @@ impl Escrow {
         8 +  // asset_id
         8 +  // amount
         8 +  // deadline
+        8 +  // locked_at
         1 +  // settled
         8 +  // nonce
+        1 + 32 + // referrer
+        1 +  // fee_payer
+        8 +  // fee_amount
+        2 +  // fee_bps
+        2 +  // referral_share_bps
+        1 + 8 + // usd_rate
+        1 + ItemClass::LEN + // item_class
+        1 + WearBounds::LEN + // wear_bounds
         1;   // bump
 
+    /// Offsets of the fields `getProgramAccounts` filters on, in the account data
+    pub const BUYER_OFFSET: usize = 8; // discriminator
+    pub const SELLER_OFFSET: usize = Self::BUYER_OFFSET + 32; // buyer
+    pub const SETTLED_OFFSET: usize = Self::SELLER_OFFSET +
+        32 + // seller
+        8 +  // asset_id
+        8 +  // amount
+        8 +  // deadline
+        8;   // locked_at
+
     pub fn is_expired(&self) -> bool {
         Clock::get().unwrap().unix_timestamp > self.deadline
     }
@@ -43,11 +94,69 @@ impl Escrow {
     pub fn can_refund(&self) -> bool {
         !self.settled && self.is_expired()
     }
+
+    /// Total deposited into the vault at lock, and returned on refund
+    pub fn locked_total(&self) -> Result<u64> {
+        let (buyer_fee, _) = self.fee_payer.split(self.fee_amount);
+        Ok(self.amount
+            .checked_add(buyer_fee)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    /// Amount paid to the seller on settlement
+    pub fn seller_amount(&self) -> Result<u64> {
+        let (_, seller_fee) = self.fee_payer.split(self.fee_amount);
+        Ok(self.amount
+            .checked_sub(seller_fee)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    /// Split of the fee into (protocol, referral) parts, using the share snapshotted at lock
+    pub fn split_fee(&self) -> Result<(u64, u64)> {
+        match self.referrer {
+            Some(_) => split_referral_fee(self.fee_amount, self.referral_share_bps),
+            None => Ok((self.fee_amount, 0)),
+        }
+    }
+
+    /// Receipt of the escrow at `escrow` closing with `outcome` at `timestamp`
+    pub fn receipt(&self, escrow: Pubkey, outcome: TradeOutcome, timestamp: i64) -> Receipt {
+        Receipt {
+            escrow,
+            buyer: self.buyer,
+            seller: self.seller,
+            item: TradedItem::Asset(self.asset_id),
+            amount: self.amount,
+            outcome,
+            timestamp,
+        }
+    }
+
+    /// Message the oracles sign to attest the item was delivered, and for
+    /// buy order fills that it matched the order's class and wear range
+    pub fn settlement_message(&self, escrow: &Pubkey) -> String {
+        match self.item_class {
+            Some(item_class) => format!(
+                "settle:{}:{}:{}:{}:{}",
+                self.asset_id,
+                self.buyer,
+                escrow,
+                item_class.identifier(),
+                self.wear_bounds
+                    .map(|bounds| format!("{}-{}", bounds.min, bounds.max))
+                    .unwrap_or_else(|| "any".to_string())
+            ),
+            None => format!("settle:{}:{}:{}", self.asset_id, self.buyer, escrow),
+        }
+    }
 }
 
 /// Seeds for PDA derivation
 pub const ESCROW_SEED: &[u8] = b"escrow";
 
+/// Seed of the token vault owned by an escrow
+pub const ESCROW_VAULT_SEED: &[u8] = b"escrow_vault";
+
 /// Generate escrow PDA
 pub fn get_escrow_pda(
     buyer: &Pubkey,
@@ -66,4 +175,9 @@ pub fn get_escrow_pda(
         ],
         program_id,
     )
+}
+
+/// Generate the token vault PDA of an escrow (single-item or commodity)
+pub fn get_escrow_vault_pda(escrow: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ESCROW_VAULT_SEED, escrow.as_ref()], program_id)
 }
\ No newline at end of file
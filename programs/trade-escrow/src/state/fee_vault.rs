@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(Default)]
+pub struct FeeVault {
+    /// Mint whose fees accumulate in this vault
+    pub mint: Pubkey,
+    /// Lifetime fees deposited by settlements
+    pub total_collected: u64,
+    /// Lifetime fees swept out by withdrawals
+    pub total_withdrawn: u64,
+    /// Minimum absolute fee per trade in this mint
+    pub min_fee: u64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl FeeVault {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // mint
+        8 +  // total_collected
+        8 +  // total_withdrawn
+        8 +  // min_fee
+        1;   // bump
+
+    /// Fees collected but not yet withdrawn
+    pub fn accrued(&self) -> u64 {
+        self.total_collected.saturating_sub(self.total_withdrawn)
+    }
+}
+
+/// Seeds for fee vault PDA
+pub const FEE_VAULT_SEED: &[u8] = b"fee_vault";
+
+/// Seeds for the fee vault's token account
+pub const FEE_VAULT_TOKEN_SEED: &[u8] = b"fee_vault_tokens";
+
+/// Generate fee vault PDA
+pub fn get_fee_vault_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Generate fee vault token account PDA
+pub fn get_fee_vault_token_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_VAULT_TOKEN_SEED, mint.as_ref()], program_id)
+}
\ No newline at end of file
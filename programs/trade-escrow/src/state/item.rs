@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+/// Scale of wear (float) values: 1.0 == `WEAR_SCALE`
+pub const WEAR_SCALE: u32 = 1_000_000_000;
+
+/// A class of interchangeable Steam items, e.g. every "AK-47 | Redline (Field-Tested)"
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ItemClass {
+    /// Steam app ID (730 = CS2)
+    pub appid: u32,
+    /// SHA-256 of the item's market_hash_name
+    pub market_hash_name_hash: [u8; 32],
+}
+
+impl ItemClass {
+    pub const LEN: usize = 4 + 32;
+
+    /// Identifier used in signed messages: `{appid}:{hex hash}`
+    pub fn identifier(&self) -> String {
+        let hash: String = self
+            .market_hash_name_hash
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("{}:{}", self.appid, hash)
+    }
+}
+
+/// Inclusive wear bounds, scaled by `WEAR_SCALE`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct WearBounds {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl WearBounds {
+    pub const LEN: usize = 4 + 4;
+
+    pub fn is_valid(&self) -> bool {
+        self.min <= self.max && self.max <= WEAR_SCALE
+    }
+
+    pub fn contains(&self, wear: u32) -> bool {
+        (self.min..=self.max).contains(&wear)
+    }
+}
\ No newline at end of file
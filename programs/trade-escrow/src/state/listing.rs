@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use crate::errors::TradeEscrowError;
+use crate::state::FeePayer;
+
+/// Lifecycle of an on-chain listing
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ListingStatus {
+    #[default]
+    Open,
+    Filled,
+}
+
+/// How a Dutch listing's price falls between its start and end time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DutchCurve {
+    /// Price falls continuously
+    Linear,
+    /// Price falls along the same line, but only every `interval` seconds
+    Step { interval: i64 },
+}
+
+/// Descending price schedule for a Dutch listing
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DutchPricing {
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub curve: DutchCurve,
+}
+
+impl DutchPricing {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 1 + 8;
+
+    pub fn is_valid(&self) -> bool {
+        let curve_valid = match self.curve {
+            DutchCurve::Linear => true,
+            DutchCurve::Step { interval } => interval > 0,
+        };
+        // The span must fit in an i64 so `price_at` can measure elapsed time within it
+        let span_valid = matches!(self.end_time.checked_sub(self.start_time), Some(span) if span > 0);
+        self.floor_price > 0
+            && self.start_price >= self.floor_price
+            && span_valid
+            && curve_valid
+    }
+
+    /// Price at `now`: the start price before the start time, the floor price after the end
+    pub fn price_at(&self, now: i64) -> Result<u64> {
+        if now <= self.start_time {
+            return Ok(self.start_price);
+        }
+        if now >= self.end_time {
+            return Ok(self.floor_price);
+        }
+
+        let since_start = now
+            .checked_sub(self.start_time)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        let elapsed = match self.curve {
+            DutchCurve::Linear => since_start,
+            DutchCurve::Step { interval } => since_start / interval * interval,
+        };
+        let duration = self.end_time
+            .checked_sub(self.start_time)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        let range = self.start_price
+            .checked_sub(self.floor_price)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?;
+        let drop = u64::try_from(range as u128 * elapsed as u128 / duration as u128)
+            .map_err(|_| TradeEscrowError::ArithmeticOverflow)?;
+
+        Ok(self.start_price
+            .checked_sub(drop)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct Listing {
+    /// Seller's wallet address
+    pub seller: Pubkey,
+    /// Seller-chosen identifier, unique per seller
+    pub listing_id: u64,
+    /// Steam asset ID being sold
+    pub asset_id: u64,
+    /// Mint the price is denominated in
+    pub mint: Pubkey,
+    /// Asking price (in token units); the start price for Dutch listings
+    pub price: u64,
+    /// Descending price schedule, for Dutch listings
+    pub dutch: Option<DutchPricing>,
+    /// Listing can no longer be bought after this time (Unix timestamp)
+    pub expiry: i64,
+    /// Only this wallet may buy, if set
+    pub allowed_buyer: Option<Pubkey>,
+    /// Seller-chosen override of the config fee payer
+    pub fee_payer: Option<FeePayer>,
+    /// Whether the listing is still available
+    pub status: ListingStatus,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl Listing {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // seller
+        8 +  // listing_id
+        8 +  // asset_id
+        32 + // mint
+        8 +  // price
+        1 + DutchPricing::LEN + // dutch
+        8 +  // expiry
+        1 + 32 + // allowed_buyer
+        1 + 1 + // fee_payer
+        1 +  // status
+        1;   // bump
+
+    /// Price a buyer pays at `now`
+    pub fn current_price(&self, now: i64) -> Result<u64> {
+        match self.dutch {
+            Some(dutch) => dutch.price_at(now),
+            None => Ok(self.price),
+        }
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expiry
+    }
+
+    pub fn can_buy(&self, buyer: &Pubkey) -> bool {
+        self.allowed_buyer.is_none() || self.allowed_buyer == Some(*buyer)
+    }
+}
+
+/// Seeds for listing PDA
+pub const LISTING_SEED: &[u8] = b"listing";
+
+/// Generate listing PDA
+pub fn get_listing_pda(seller: &Pubkey, listing_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LISTING_SEED, seller.as_ref(), &listing_id.to_le_bytes()],
+        program_id,
+    )
+}
\ No newline at end of file
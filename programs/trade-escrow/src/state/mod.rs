@@ -1,5 +1,27 @@
 pub mod escrow;
 pub mod config;
+pub mod user_stats;
+pub mod fee_vault;
+pub mod listing;
+pub mod item;
+pub mod buy_order;
+pub mod auction;
+pub mod swap;
+pub mod commodity;
+pub mod price_reference;
+pub mod price_feed;
+pub mod receipt;
 
 pub use escrow::*;
-pub use config::*;
\ No newline at end of file
+pub use config::*;
+pub use user_stats::*;
+pub use fee_vault::*;
+pub use listing::*;
+pub use item::*;
+pub use buy_order::*;
+pub use auction::*;
+pub use swap::*;
+pub use commodity::*;
+pub use price_reference::*;
+pub use price_feed::*;
+pub use receipt::*;
\ No newline at end of file
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+/// Decimals of USD amounts and feed prices: 1 USD == 10^6
+pub const USD_DECIMALS: u32 = 6;
+
+/// USD price of a mint, published by `Config.price_publisher`.
+///
+/// Mirrors the price/confidence/publish-time fields of a Pyth price account
+/// with a fixed exponent of `-USD_DECIMALS`.
+#[account]
+#[derive(Default)]
+pub struct PriceFeed {
+    /// Mint being priced
+    pub mint: Pubkey,
+    /// USD price of one whole token, scaled by 10^USD_DECIMALS
+    pub price: u64,
+    /// Confidence interval around `price`, in the same units
+    pub confidence: u64,
+    /// When the price was published (Unix timestamp)
+    pub publish_time: i64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl PriceFeed {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // mint
+        8 +  // price
+        8 +  // confidence
+        8 +  // publish_time
+        1;   // bump
+
+    /// Whether the price is older than `max_staleness` seconds at `now`
+    pub fn is_stale(&self, now: i64, max_staleness: i64) -> bool {
+        now.saturating_sub(self.publish_time) > max_staleness
+    }
+
+    /// Whether the confidence interval is within `max_confidence_bps` of the price
+    pub fn is_confident(&self, max_confidence_bps: u16) -> bool {
+        self.price > 0
+            && self.confidence as u128 * 10000 <= self.price as u128 * max_confidence_bps as u128
+    }
+
+    /// Token amount worth `usd_amount` for a mint with `decimals`, or None on overflow
+    pub fn usd_to_tokens(&self, usd_amount: u64, decimals: u8) -> Option<u64> {
+        if self.price == 0 {
+            return None;
+        }
+        let scale = 10u128.checked_pow(decimals as u32)?;
+        let tokens = (usd_amount as u128).checked_mul(scale)? / self.price as u128;
+        u64::try_from(tokens).ok()
+    }
+}
+
+/// Seeds for PDA derivation
+pub const PRICE_FEED_SEED: &[u8] = b"price_feed";
+
+/// Generate price feed PDA
+pub fn get_price_feed_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PRICE_FEED_SEED, mint.as_ref()], program_id)
+}
\ No newline at end of file
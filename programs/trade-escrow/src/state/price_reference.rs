@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use crate::state::ItemClass;
+
+/// Market reference price for an item class, published by `Config.price_publisher`
+#[account]
+#[derive(Default)]
+pub struct PriceReference {
+    /// Item class the price applies to
+    pub item_class: ItemClass,
+    /// Mint the price is quoted in
+    pub mint: Pubkey,
+    /// Reference price in token units
+    pub price: u64,
+    /// When the price was last published (Unix timestamp)
+    pub updated_at: i64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl PriceReference {
+    pub const LEN: usize =
+        8 +  // discriminator
+        ItemClass::LEN + // item_class
+        32 + // mint
+        8 +  // price
+        8 +  // updated_at
+        1;   // bump
+
+    /// Whether the price is older than `max_age` seconds at `now`
+    pub fn is_stale(&self, now: i64, max_age: i64) -> bool {
+        now.saturating_sub(self.updated_at) > max_age
+    }
+
+    /// Whether `amount` is more than `band_bps` away from the reference price
+    pub fn deviates(&self, amount: u64, band_bps: u16) -> bool {
+        if band_bps == 0 || self.price == 0 {
+            return false;
+        }
+        let difference = self.price.abs_diff(amount) as u128;
+        difference * 10000 > self.price as u128 * band_bps as u128
+    }
+}
+
+/// Seeds for PDA derivation
+pub const PRICE_REFERENCE_SEED: &[u8] = b"price_reference";
+
+/// Generate price reference PDA
+pub fn get_price_reference_pda(
+    mint: &Pubkey,
+    item_class: &ItemClass,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PRICE_REFERENCE_SEED,
+            mint.as_ref(),
+            &item_class.appid.to_le_bytes(),
+            &item_class.market_hash_name_hash,
+        ],
+        program_id,
+    )
+}
\ No newline at end of file
@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use crate::errors::TradeEscrowError;
+use crate::state::ItemClass;
+
+/// Height of the receipt tree, which holds up to 2^32 receipts
+pub const RECEIPT_TREE_DEPTH: usize = 32;
+
+/// Hash of a leaf no receipt has been appended to yet
+pub const EMPTY_RECEIPT_LEAF: [u8; 32] = [0; 32];
+
+/// Domain prefixes keeping leaf and node hashes apart
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// How a trade ended
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TradeOutcome {
+    /// The seller was paid
+    Settled,
+    /// The buyer got their funds back
+    Refunded,
+}
+
+/// What changed hands in a trade
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum TradedItem {
+    /// A single Steam asset
+    Asset(u64),
+    /// Items of a commodity class: those delivered on settlement, all of them on refund
+    Commodity { class: ItemClass, quantity: u32 },
+    /// Steam asset IDs each side of a swap sends
+    Swap { maker_items: Vec<u64>, taker_items: Vec<u64> },
+}
+
+impl TradedItem {
+    /// Bytes the receipt leaf commits to: a variant tag, then its fields
+    fn leaf_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            TradedItem::Asset(asset_id) => {
+                bytes.push(0);
+                bytes.extend_from_slice(&asset_id.to_le_bytes());
+            }
+            TradedItem::Commodity { class, quantity } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&class.appid.to_le_bytes());
+                bytes.extend_from_slice(&class.market_hash_name_hash);
+                bytes.extend_from_slice(&quantity.to_le_bytes());
+            }
+            TradedItem::Swap { maker_items, taker_items } => {
+                bytes.push(2);
+                for items in [maker_items, taker_items] {
+                    bytes.extend_from_slice(&(items.len() as u32).to_le_bytes());
+                    for asset_id in items {
+                        bytes.extend_from_slice(&asset_id.to_le_bytes());
+                    }
+                }
+            }
+        }
+        bytes
+    }
+}
+
+/// Record of a closed trade, committed to as one leaf of the receipt tree
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Receipt {
+    /// Escrow, commodity escrow or swap account of the trade
+    pub escrow: Pubkey,
+    /// Buyer, or the taker of a swap
+    pub buyer: Pubkey,
+    /// Seller, or the maker of a swap
+    pub seller: Pubkey,
+    pub item: TradedItem,
+    /// Trade amount excluding fees; for a swap, both cash legs together
+    pub amount: u64,
+    pub outcome: TradeOutcome,
+    /// When the trade was settled or refunded (Unix timestamp)
+    pub timestamp: i64,
+}
+
+impl Receipt {
+    /// Leaf hash committing to every field of the receipt
+    pub fn leaf(&self) -> [u8; 32] {
+        hashv(&[
+            LEAF_PREFIX,
+            self.escrow.as_ref(),
+            self.buyer.as_ref(),
+            self.seller.as_ref(),
+            &self.item.leaf_bytes(),
+            &self.amount.to_le_bytes(),
+            &[self.outcome as u8],
+            &self.timestamp.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Parent of two nodes in the receipt tree
+pub fn hash_receipt_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Roots of empty subtrees, indexed by height: each is `hash_receipt_nodes`
+/// of the one below with itself, precomputed so appends hash only their path
+pub const EMPTY_RECEIPT_SUBTREES: [[u8; 32]; RECEIPT_TREE_DEPTH + 1] = [
+    EMPTY_RECEIPT_LEAF,
+    [
+        0xae, 0x07, 0x98, 0xd0, 0xec, 0xae, 0xd2, 0xb7, 0x78, 0xed, 0xde, 0xbf, 0x18, 0xf0, 0x71, 0xa5,
+        0x61, 0xc5, 0x36, 0x58, 0xc0, 0x5e, 0x76, 0xce, 0xde, 0xcc, 0x27, 0xca, 0xfb, 0xdb, 0xc5, 0x77,
+    ],
+    [
+        0x90, 0x53, 0x4f, 0xe0, 0xaf, 0xf6, 0xdb, 0x9e, 0xdb, 0x29, 0xee, 0xe7, 0x4e, 0x78, 0xa3, 0x86,
+        0x91, 0x6a, 0x58, 0x1c, 0x8e, 0x64, 0x65, 0x34, 0x94, 0x93, 0xe1, 0xa6, 0xc8, 0x72, 0x41, 0xe1,
+    ],
+    [
+        0xbe, 0xa1, 0x61, 0x62, 0x72, 0x1b, 0xca, 0x4b, 0x6e, 0x17, 0x82, 0xcb, 0xdc, 0x69, 0x5a, 0x47,
+        0x15, 0x22, 0x15, 0x7c, 0x67, 0x16, 0xf5, 0x08, 0xdb, 0x47, 0xc5, 0x99, 0x19, 0x53, 0x40, 0xf4,
+    ],
+    [
+        0x30, 0x76, 0x5f, 0xef, 0x34, 0x1b, 0xdf, 0xe7, 0x49, 0xc3, 0x91, 0xbf, 0x95, 0x6a, 0x9f, 0x03,
+        0xd3, 0x63, 0x94, 0x1b, 0x2e, 0xb8, 0xf8, 0x5a, 0xb1, 0x6b, 0xb6, 0xeb, 0x0d, 0x3c, 0x4d, 0xef,
+    ],
+    [
+        0xf1, 0xa0, 0xa7, 0x1a, 0x65, 0x50, 0xc4, 0x1b, 0xc8, 0xd4, 0xda, 0xc4, 0xf1, 0x86, 0xb6, 0xd2,
+        0x74, 0xa8, 0x39, 0xf2, 0xf9, 0x5a, 0xd9, 0xe3, 0xbb, 0x65, 0x1d, 0x45, 0x81, 0x0c, 0x5a, 0x1f,
+    ],
+    [
+        0xc6, 0x98, 0x49, 0x07, 0xd2, 0xe5, 0x34, 0x96, 0x43, 0x51, 0x39, 0x3b, 0xc8, 0x5f, 0x04, 0x37,
+        0x40, 0x65, 0xb6, 0x38, 0x70, 0xcc, 0x85, 0x9c, 0x68, 0x19, 0x2d, 0xef, 0x09, 0x0c, 0x10, 0x17,
+    ],
+    [
+        0xdc, 0x06, 0x06, 0xb9, 0x06, 0x23, 0x8d, 0xd1, 0x57, 0xe6, 0x9c, 0xb2, 0x61, 0xe7, 0x56, 0x96,
+        0x00, 0x7e, 0x4d, 0x9a, 0x3f, 0x70, 0x7a, 0x7c, 0xe1, 0x0c, 0xe4, 0x10, 0xd0, 0x8b, 0xcf, 0xec,
+    ],
+    [
+        0x8e, 0x4b, 0x37, 0x45, 0xe5, 0xf2, 0xf7, 0xd4, 0x8e, 0x36, 0xb1, 0x92, 0xcb, 0x39, 0x24, 0x2f,
+        0xa0, 0xf7, 0xa7, 0x6f, 0xac, 0x1e, 0x36, 0xa5, 0x19, 0xd8, 0xeb, 0xe0, 0x0f, 0x3e, 0x21, 0xfb,
+    ],
+    [
+        0xbd, 0x75, 0x2b, 0x8e, 0x76, 0xf5, 0xf8, 0x91, 0xe5, 0xd3, 0xa1, 0x03, 0x52, 0xdb, 0xf3, 0xac,
+        0x25, 0x12, 0x3b, 0x6e, 0xb4, 0x8a, 0x3a, 0xd1, 0x54, 0x02, 0x0c, 0xd0, 0xc8, 0x4d, 0x21, 0x76,
+    ],
+    [
+        0x29, 0x39, 0x8d, 0x48, 0xe1, 0xa1, 0xa9, 0xf3, 0xc8, 0xe0, 0xf9, 0x7b, 0x0f, 0x8c, 0x06, 0x6d,
+        0x2b, 0xea, 0xf8, 0x8a, 0x31, 0x9b, 0xae, 0xf0, 0x2a, 0x54, 0x82, 0xd6, 0x15, 0x7e, 0xbd, 0x2b,
+    ],
+    [
+        0x76, 0xdc, 0x94, 0xf1, 0x87, 0x36, 0x25, 0x90, 0xf6, 0xfd, 0x2f, 0x1e, 0x2b, 0x1b, 0x8c, 0x8f,
+        0x06, 0xd7, 0x43, 0x90, 0x80, 0xb4, 0xa4, 0x99, 0xae, 0x6b, 0x26, 0x7a, 0xb1, 0xb5, 0xcc, 0x31,
+    ],
+    [
+        0xd1, 0x00, 0x39, 0x9f, 0x60, 0x7b, 0xa9, 0x56, 0xd6, 0x87, 0x37, 0x04, 0x5e, 0xa9, 0xeb, 0x3e,
+        0x75, 0x47, 0x5d, 0xfa, 0x63, 0x3b, 0x7c, 0xab, 0xec, 0xf0, 0xaf, 0xa0, 0x5c, 0x9f, 0x3a, 0xf8,
+    ],
+    [
+        0x3e, 0x46, 0x22, 0x2c, 0x09, 0x2b, 0x9b, 0xaa, 0x5a, 0x16, 0xdf, 0x1d, 0xc2, 0x86, 0x4c, 0x7c,
+        0xb5, 0xfb, 0x79, 0x60, 0x9d, 0x09, 0x6a, 0xc2, 0xa1, 0x0d, 0x64, 0xf8, 0x24, 0xd1, 0xc7, 0x3a,
+    ],
+    [
+        0x8c, 0xf2, 0xc9, 0xbd, 0x23, 0x69, 0xa3, 0xb9, 0xe0, 0xbb, 0xdd, 0x2c, 0xee, 0x63, 0x46, 0x05,
+        0x44, 0x0a, 0xdb, 0x2d, 0x70, 0xb1, 0x52, 0x63, 0xdb, 0xe7, 0xd0, 0xd1, 0x6e, 0xbf, 0xc0, 0x3d,
+    ],
+    [
+        0xfb, 0xbb, 0x94, 0x25, 0x87, 0xef, 0x25, 0x67, 0x38, 0xd2, 0xbb, 0xb0, 0x50, 0x64, 0x23, 0x88,
+        0x02, 0x06, 0xcf, 0xbf, 0xa2, 0xed, 0x4d, 0x34, 0x0d, 0x79, 0x6b, 0x0f, 0x7e, 0x29, 0x09, 0x80,
+    ],
+    [
+        0xbb, 0xc8, 0x4b, 0xb1, 0xec, 0xa9, 0xb9, 0x8b, 0x54, 0x67, 0x08, 0xca, 0xdc, 0xf8, 0x29, 0x98,
+        0x3d, 0xd0, 0x3d, 0xa1, 0x68, 0xf6, 0x5a, 0xea, 0xe2, 0x17, 0x24, 0x00, 0x30, 0x72, 0xc2, 0x1a,
+    ],
+    [
+        0x66, 0x9b, 0x03, 0xf8, 0xec, 0x71, 0x53, 0xd9, 0xf9, 0xdb, 0x77, 0x94, 0x49, 0xf5, 0x04, 0x48,
+        0x99, 0x42, 0x35, 0x9a, 0xab, 0x73, 0x4d, 0x93, 0x84, 0x17, 0x88, 0x93, 0x94, 0x39, 0x4b, 0x09,
+    ],
+    [
+        0xa3, 0xf6, 0x71, 0x4a, 0x23, 0xc8, 0xf9, 0xb9, 0x1f, 0xb3, 0xdd, 0x6e, 0x31, 0x1b, 0xd1, 0xcf,
+        0x5f, 0x8b, 0xcb, 0x36, 0x18, 0xde, 0xe8, 0xae, 0x5c, 0xd7, 0xab, 0x0e, 0xc4, 0x0c, 0x08, 0x76,
+    ],
+    [
+        0x0b, 0xd2, 0x11, 0x8f, 0xbd, 0x68, 0xfa, 0x6b, 0xb6, 0x73, 0x35, 0x96, 0xbb, 0x87, 0x9f, 0x57,
+        0x1b, 0xd6, 0x48, 0x82, 0x1f, 0x74, 0x37, 0x41, 0x9d, 0xcf, 0x49, 0xf5, 0x07, 0x8f, 0x1d, 0xb8,
+    ],
+    [
+        0x51, 0xc2, 0x0d, 0x66, 0x00, 0x80, 0x24, 0xc0, 0x4c, 0xf1, 0x14, 0x56, 0x4a, 0x99, 0x8e, 0x49,
+        0xef, 0x8f, 0x6e, 0x04, 0x4e, 0x2d, 0x13, 0xa0, 0x3a, 0x66, 0x52, 0x1d, 0x6e, 0x20, 0x05, 0x03,
+    ],
+    [
+        0xb2, 0x9e, 0x66, 0x5a, 0x5b, 0xe9, 0x28, 0xc8, 0x88, 0xca, 0xe7, 0x96, 0x38, 0xea, 0x9f, 0x6f,
+        0xeb, 0xb5, 0xe0, 0xda, 0x9c, 0x0a, 0xdb, 0x8e, 0xef, 0x89, 0x28, 0x95, 0xfd, 0xeb, 0x34, 0xf3,
+    ],
+    [
+        0xe3, 0x9d, 0x25, 0xeb, 0xb3, 0x23, 0x8d, 0xd9, 0xc2, 0x46, 0xe2, 0x2c, 0xce, 0xef, 0xfe, 0x7a,
+        0x6d, 0xef, 0x73, 0x90, 0x37, 0xe7, 0xdd, 0xb4, 0x18, 0x89, 0xd3, 0xed, 0x2b, 0xdb, 0x54, 0xcb,
+    ],
+    [
+        0xc3, 0x9d, 0x93, 0x28, 0xc5, 0xe0, 0xbe, 0xf0, 0x2e, 0x85, 0x9c, 0xe6, 0x3d, 0x15, 0xd3, 0xa5,
+        0x24, 0x0a, 0x4c, 0x52, 0xee, 0x34, 0xbb, 0x87, 0xac, 0x5b, 0xa2, 0x31, 0x91, 0x0b, 0xf5, 0xf0,
+    ],
+    [
+        0xfd, 0x15, 0x3e, 0x6b, 0xe1, 0x76, 0x31, 0x77, 0xc1, 0xd4, 0x3f, 0x43, 0x66, 0x1a, 0x35, 0x92,
+        0x14, 0xd2, 0x62, 0x56, 0xcf, 0x62, 0xb8, 0x11, 0xd8, 0x44, 0x23, 0x82, 0xdb, 0xca, 0x74, 0x70,
+    ],
+    [
+        0xe8, 0x12, 0x3d, 0x75, 0x5c, 0x5a, 0x32, 0x14, 0x0e, 0xa8, 0x76, 0x05, 0xc1, 0x8e, 0xf7, 0xf3,
+        0x42, 0xdc, 0x74, 0x61, 0xe1, 0xfd, 0x47, 0x0a, 0x15, 0x83, 0x56, 0xea, 0xed, 0x68, 0x50, 0x3d,
+    ],
+    [
+        0x16, 0x02, 0x2e, 0x1f, 0xc0, 0x87, 0x98, 0x26, 0x98, 0xd7, 0x86, 0x1e, 0xce, 0x25, 0x06, 0xbc,
+        0x8f, 0x21, 0x62, 0x6c, 0x03, 0xa3, 0x0c, 0x42, 0x42, 0x59, 0xd3, 0xf7, 0xfd, 0xe2, 0x86, 0x33,
+    ],
+    [
+        0x65, 0x55, 0x42, 0xf1, 0x4a, 0x71, 0x9c, 0xa6, 0x24, 0x25, 0x5c, 0x59, 0x09, 0x6d, 0x3a, 0xfb,
+        0x83, 0xcc, 0xef, 0x5b, 0xb2, 0x9c, 0xe0, 0x5d, 0x37, 0xd5, 0x40, 0xe9, 0x39, 0xa3, 0xd7, 0xd1,
+    ],
+    [
+        0x25, 0x9d, 0xeb, 0xd0, 0xf2, 0x28, 0x94, 0x01, 0xc7, 0xda, 0x9a, 0xeb, 0x00, 0x57, 0xab, 0xb1,
+        0xd4, 0xa8, 0xc7, 0xa2, 0xcf, 0xfe, 0x0a, 0xf3, 0xec, 0x42, 0x91, 0x04, 0x97, 0x93, 0x6a, 0x75,
+    ],
+    [
+        0xdd, 0x36, 0x3a, 0xe2, 0xd1, 0x36, 0x11, 0xd3, 0x7d, 0xac, 0x77, 0xe6, 0x53, 0x43, 0x67, 0x45,
+        0xbc, 0xda, 0x1f, 0x4e, 0x2d, 0x76, 0xb0, 0x4b, 0x27, 0x23, 0x93, 0x09, 0x47, 0xd9, 0x46, 0x02,
+    ],
+    [
+        0x5a, 0x62, 0xaa, 0xa4, 0x15, 0x41, 0x24, 0x2c, 0xf1, 0x2e, 0x48, 0x27, 0x43, 0xee, 0xdf, 0x40,
+        0x3c, 0x08, 0xe9, 0x20, 0x33, 0x0f, 0x67, 0x36, 0x82, 0x5b, 0xfe, 0x27, 0xf2, 0xda, 0x82, 0x37,
+    ],
+    [
+        0xe6, 0x9e, 0x67, 0x4a, 0xe7, 0x8b, 0x12, 0x09, 0x25, 0xd5, 0x3f, 0x10, 0xda, 0xcc, 0xf9, 0x90,
+        0xdd, 0xd8, 0x31, 0xc0, 0x25, 0x41, 0x5b, 0xdf, 0x41, 0xb4, 0xd5, 0x94, 0x15, 0x7d, 0x23, 0xcf,
+    ],
+    [
+        0x78, 0x2d, 0x35, 0xb1, 0xfd, 0xad, 0x7d, 0x54, 0xe7, 0xa1, 0xb3, 0x6a, 0x2a, 0xb1, 0x02, 0x1e,
+        0x87, 0x2c, 0x76, 0x92, 0xbb, 0x80, 0xfd, 0xd1, 0x2b, 0xfc, 0x32, 0x1e, 0x9e, 0x42, 0x04, 0x09,
+    ],
+];
+
+/// Append-only Merkle tree over the receipts of every closed trade in one
+/// mint.
+///
+/// Each mint gets its own tree, so trades in different mints never contend
+/// for the same writable account. Only the root and the right edge of the tree are stored, so the account
+/// stays the same size however many receipts it holds. Full receipts are
+/// emitted in `ReceiptAppended` events, from which anyone can rebuild the
+/// tree and prove a receipt's inclusion against `root`.
+#[account]
+#[derive(Default)]
+pub struct ReceiptTree {
+    /// Mint of the trades whose receipts the tree holds
+    pub mint: Pubkey,
+    /// Receipts appended so far, and the leaf index of the next one
+    pub count: u64,
+    /// Root over every receipt appended so far
+    pub root: [u8; 32],
+    /// Latest filled subtree at each height, the left siblings of later leaves
+    pub frontier: [[u8; 32]; RECEIPT_TREE_DEPTH],
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl ReceiptTree {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // mint
+        8 +  // count
+        32 + // root
+        32 * RECEIPT_TREE_DEPTH + // frontier
+        1;   // bump
+
+    /// Add a receipt as the next leaf, returning its index
+    pub fn append(&mut self, receipt: &Receipt) -> Result<u64> {
+        let index = self.count;
+        require!(index < 1 << RECEIPT_TREE_DEPTH, TradeEscrowError::ReceiptTreeFull);
+
+        let mut node = receipt.leaf();
+        let mut stored = false;
+        for (height, empty) in EMPTY_RECEIPT_SUBTREES[..RECEIPT_TREE_DEPTH].iter().enumerate() {
+            if (index >> height) & 1 == 1 {
+                node = hash_receipt_nodes(&self.frontier[height], &node);
+            } else {
+                // The first left child on the path is the subtree later leaves pair with
+                if !stored {
+                    self.frontier[height] = node;
+                    stored = true;
+                }
+                node = hash_receipt_nodes(&node, empty);
+            }
+        }
+
+        self.root = node;
+        self.count = index + 1;
+        Ok(index)
+    }
+}
+
+/// Seeds for the receipt tree PDA
+pub const RECEIPT_TREE_SEED: &[u8] = b"receipt_tree";
+
+/// Generate receipt tree PDA for a mint
+pub fn get_receipt_tree_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIPT_TREE_SEED, mint.as_ref()], program_id)
+}
\ No newline at end of file
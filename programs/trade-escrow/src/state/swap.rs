@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use crate::errors::TradeEscrowError;
+use crate::state::{Receipt, TradeOutcome, TradedItem};
+
+/// Maximum items either side can put into one swap
+pub const MAX_SWAP_ITEMS: usize = 4;
+
+/// Lifecycle of a swap
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SwapStatus {
+    /// Offered by the maker, waiting for the taker
+    #[default]
+    Proposed,
+    /// Both sides committed, waiting for delivery in both directions
+    Locked,
+}
+
+/// Item-for-item trade with optional cash on either side.
+///
+/// Swaps charge no protocol fee: cash legs are paid over in full. They still
+/// count against the TVL and per-user limits while the swap is open.
+#[account]
+#[derive(Default)]
+pub struct SwapEscrow {
+    /// Wallet proposing the swap
+    pub maker: Pubkey,
+    /// Wallet the swap is offered to
+    pub taker: Pubkey,
+    /// Maker-chosen identifier, unique per maker
+    pub swap_id: u64,
+    /// Steam asset IDs the maker sends
+    pub maker_items: Vec<u64>,
+    /// Steam asset IDs the taker sends
+    pub taker_items: Vec<u64>,
+    /// Mint of both cash legs
+    pub mint: Pubkey,
+    /// Cash the maker adds on top of their items
+    pub maker_cash: u64,
+    /// Cash the taker adds on top of their items
+    pub taker_cash: u64,
+    /// Offer can no longer be accepted after this time (Unix timestamp)
+    pub expiry: i64,
+    /// Seconds both sides have to deliver once accepted
+    pub delivery_window: i64,
+    /// Deadline for delivery in both directions, set on accept (Unix timestamp)
+    pub deadline: i64,
+    /// When the maker's cash leg was counted against the limits (Unix timestamp)
+    pub proposed_at: i64,
+    /// When the taker's cash leg was counted against the limits (Unix timestamp)
+    pub accepted_at: i64,
+    pub status: SwapStatus,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl SwapEscrow {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // maker
+        32 + // taker
+        8 +  // swap_id
+        4 + 8 * MAX_SWAP_ITEMS + // maker_items
+        4 + 8 * MAX_SWAP_ITEMS + // taker_items
+        32 + // mint
+        8 +  // maker_cash
+        8 +  // taker_cash
+        8 +  // expiry
+        8 +  // delivery_window
+        8 +  // deadline
+        8 +  // proposed_at
+        8 +  // accepted_at
+        1 +  // status
+        1;   // bump
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.deadline
+    }
+
+    /// Cash held for both legs while the swap is locked
+    pub fn total_cash(&self) -> Result<u64> {
+        Ok(self.maker_cash
+            .checked_add(self.taker_cash)
+            .ok_or(TradeEscrowError::ArithmeticOverflow)?)
+    }
+
+    /// Receipt of the swap at `swap` closing with `outcome` at `timestamp`
+    pub fn receipt(&self, swap: Pubkey, outcome: TradeOutcome, timestamp: i64) -> Result<Receipt> {
+        Ok(Receipt {
+            escrow: swap,
+            buyer: self.taker,
+            seller: self.maker,
+            item: TradedItem::Swap {
+                maker_items: self.maker_items.clone(),
+                taker_items: self.taker_items.clone(),
+            },
+            amount: self.total_cash()?,
+            outcome,
+            timestamp,
+        })
+    }
+
+    /// Receipt oracles sign once both sides have delivered
+    pub fn settlement_message(&self, swap: &Pubkey) -> String {
+        format!(
+            "swap:{}:{}:{}",
+            join_items(&self.maker_items),
+            join_items(&self.taker_items),
+            swap
+        )
+    }
+
+    /// Receipt oracles sign when either side failed to deliver
+    pub fn failure_message(&self, swap: &Pubkey) -> String {
+        format!("swap_failed:{}", swap)
+    }
+}
+
+fn join_items(items: &[u64]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Seeds for swap PDA
+pub const SWAP_SEED: &[u8] = b"swap";
+
+/// Seeds for the swap's cash vault
+pub const SWAP_VAULT_SEED: &[u8] = b"swap_vault";
+
+/// Generate swap PDA
+pub fn get_swap_pda(maker: &Pubkey, swap_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SWAP_SEED, maker.as_ref(), &swap_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Generate swap vault PDA
+pub fn get_swap_vault_pda(swap: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SWAP_VAULT_SEED, swap.as_ref()], program_id)
+}
\ No newline at end of file
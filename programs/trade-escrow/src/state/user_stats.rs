@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+
+/// Number of daily buckets in the rolling volume window
+pub const VOLUME_WINDOW_DAYS: usize = 30;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Number of buckets the rolling user limit window is tracked in
+pub const LIMIT_WINDOW_BUCKETS: usize = 24;
+
+/// Longest rolling user limit window the admin may set: one year
+pub const MAX_USER_LIMIT_WINDOW: i64 = 365 * SECONDS_PER_DAY;
+
+#[account]
+#[derive(Default)]
+pub struct UserStats {
+    /// Wallet these stats belong to
+    pub user: Pubkey,
+    /// Length of a limit bucket in seconds, derived from the limit window
+    pub limit_bucket_seconds: i64,
+    /// Bucket index (Unix time / bucket length) of the most recent limit bucket
+    pub limit_bucket: i64,
+    /// Amount locked per bucket, indexed by bucket modulo the number of buckets
+    pub limit_volume: [u64; LIMIT_WINDOW_BUCKETS],
+    /// Day index (Unix days) of the most recent volume bucket
+    pub volume_day: i64,
+    /// Volume this user settled as buyer per day, indexed by day modulo the window length
+    pub daily_volume: [u64; VOLUME_WINDOW_DAYS],
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl UserStats {
+    pub const LEN: usize =
+        8 +  // discriminator
+        32 + // user
+        8 +  // limit_bucket_seconds
+        8 +  // limit_bucket
+        8 * LIMIT_WINDOW_BUCKETS + // limit_volume
+        8 +  // volume_day
+        8 * VOLUME_WINDOW_DAYS + // daily_volume
+        1;   // bump
+
+    /// Claim a freshly created stats account for `user`
+    pub fn ensure_initialized(&mut self, user: Pubkey, bump: u8) {
+        if self.user == Pubkey::default() {
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+
+    /// Amount locked over the rolling `window_seconds` before `now`.
+    ///
+    /// A lock counts until the bucket it fell in has entirely left the
+    /// window, so for the whole window after it and at most one bucket longer.
+    pub fn current_window_volume(&self, now: i64, window_seconds: i64) -> u64 {
+        let Some(bucket_seconds) = limit_bucket_seconds(window_seconds) else {
+            return 0;
+        };
+        if bucket_seconds != self.limit_bucket_seconds {
+            return 0;
+        }
+        let current = now.div_euclid(bucket_seconds);
+        let buckets = LIMIT_WINDOW_BUCKETS as i64;
+        (self.limit_bucket - buckets + 1..=self.limit_bucket)
+            .filter(|bucket| current - bucket < buckets)
+            .map(|bucket| self.limit_volume[bucket.rem_euclid(buckets) as usize])
+            .fold(0u64, |total, volume| total.saturating_add(volume))
+    }
+
+    /// Move the limit buckets up to `now`, returning the bucket length.
+    ///
+    /// Buckets of another length were recorded under a different window and are dropped.
+    fn roll_limit_buckets(&mut self, now: i64, window_seconds: i64) -> Option<i64> {
+        let bucket_seconds = limit_bucket_seconds(window_seconds)?;
+        let current = now.div_euclid(bucket_seconds);
+        let buckets = LIMIT_WINDOW_BUCKETS as i64;
+        if bucket_seconds != self.limit_bucket_seconds {
+            self.limit_bucket_seconds = bucket_seconds;
+            self.limit_bucket = current;
+            self.limit_volume = [0; LIMIT_WINDOW_BUCKETS];
+        } else if current > self.limit_bucket {
+            let first_stale = (self.limit_bucket + 1).max(current - buckets + 1);
+            for bucket in first_stale..=current {
+                self.limit_volume[bucket.rem_euclid(buckets) as usize] = 0;
+            }
+            self.limit_bucket = current;
+        }
+        Some(bucket_seconds)
+    }
+
+    /// Add `amount` locked at `now` to its bucket
+    pub fn record_lock(&mut self, amount: u64, now: i64, window_seconds: i64) {
+        if let Some(bucket_seconds) = self.roll_limit_buckets(now, window_seconds) {
+            let buckets = LIMIT_WINDOW_BUCKETS as i64;
+            let bucket = now.div_euclid(bucket_seconds).rem_euclid(buckets) as usize;
+            self.limit_volume[bucket] = self.limit_volume[bucket].saturating_add(amount);
+        }
+    }
+
+    /// Take back `amount` recorded at `locked_at` that was returned without trading.
+    ///
+    /// Funding that has left the window no longer counts, so there is nothing to release.
+    pub fn release_lock(&mut self, amount: u64, locked_at: i64, now: i64, window_seconds: i64) {
+        let Some(bucket_seconds) = self.roll_limit_buckets(now, window_seconds) else {
+            return;
+        };
+        let buckets = LIMIT_WINDOW_BUCKETS as i64;
+        let bucket = locked_at.div_euclid(bucket_seconds);
+        if bucket <= self.limit_bucket && self.limit_bucket - bucket < buckets {
+            let volume = &mut self.limit_volume[bucket.rem_euclid(buckets) as usize];
+            *volume = volume.saturating_sub(amount);
+        }
+    }
+
+    /// Volume settled as buyer over the last 30 days, including today
+    pub fn volume_30d(&self, now: i64) -> u64 {
+        let today = now.div_euclid(SECONDS_PER_DAY);
+        let window = VOLUME_WINDOW_DAYS as i64;
+        (self.volume_day - window + 1..=self.volume_day)
+            .filter(|day| today - day < window)
+            .map(|day| self.daily_volume[day.rem_euclid(window) as usize])
+            .fold(0u64, |total, volume| total.saturating_add(volume))
+    }
+
+    /// Add settled `amount` to today's bucket, clearing buckets for skipped days
+    pub fn record_volume(&mut self, amount: u64, now: i64) {
+        let today = now.div_euclid(SECONDS_PER_DAY);
+        let window = VOLUME_WINDOW_DAYS as i64;
+        if today > self.volume_day {
+            let first_stale = (self.volume_day + 1).max(today - window + 1);
+            for day in first_stale..=today {
+                self.daily_volume[day.rem_euclid(window) as usize] = 0;
+            }
+            self.volume_day = today;
+        }
+        let bucket = &mut self.daily_volume[today.rem_euclid(window) as usize];
+        *bucket = bucket.saturating_add(amount);
+    }
+}
+
+/// Bucket length that lets the buckets before the current one cover `window_seconds`.
+///
+/// `None` when there is no window, or one too long to bucket.
+fn limit_bucket_seconds(window_seconds: i64) -> Option<i64> {
+    if window_seconds <= 0 {
+        return None;
+    }
+    let covering = LIMIT_WINDOW_BUCKETS as i64 - 1;
+    window_seconds
+        .checked_add(covering - 1)
+        .map(|padded| padded / covering)
+}
+
+/// Seeds for user stats PDA
+pub const USER_STATS_SEED: &[u8] = b"user_stats";
+
+/// Generate user stats PDA
+pub fn get_user_stats_pda(user: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USER_STATS_SEED, user.as_ref()], program_id)
+}
\ No newline at end of file
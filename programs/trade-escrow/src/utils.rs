@@ -1,33 +1,106 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{
-    ed25519_program,
-    instruction::Instruction,
-    sysvar::instructions::load_instruction_at,
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
 };
 use crate::errors::*;
 
-/// Verify Ed25519 signature
+/// Where the signature offsets start in Ed25519 program data: count, then padding
+const ED25519_OFFSETS_START: usize = 2;
+/// Size of one signature's offsets header in Ed25519 program data
+const ED25519_OFFSETS_SIZE: usize = 14;
+/// Instruction index meaning "the Ed25519 instruction itself"
+const ED25519_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Verify an Ed25519 signature against the transaction's verify instructions.
+///
+/// The runtime rejects the whole transaction if any Ed25519 program
+/// instruction fails, so `signature` is valid when an instruction before this
+/// one carries it together with `pubkey` and `message`.
 pub fn verify_signature(
+    instructions_sysvar: &AccountInfo,
     signature: &[u8; 64],
     message: &[u8],
     pubkey: &Pubkey,
 ) -> Result<bool> {
-    // Create the Ed25519 verification instruction
-    let instruction = ed25519_program::new_ed25519_instruction(
-        pubkey,
-        message,
-        signature,
-    );
+    let current = load_current_index_checked(instructions_sysvar)?;
+    for index in 0..current {
+        let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if instruction.program_id != ed25519_program::ID {
+            continue;
+        }
+        if ed25519_verifies(&instruction.data, signature, message, pubkey)? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
 
-    // In a real implementation, you would verify this via CPI or syscall
-    // For now, we'll do a basic check
-    if signature.iter().all(|&b| b == 0) {
-        return Ok(false);
+/// Whether Ed25519 program `data` verifies `signature` by `pubkey` over `message`.
+///
+/// Only offsets into the instruction's own data are accepted.
+fn ed25519_verifies(
+    data: &[u8],
+    signature: &[u8; 64],
+    message: &[u8],
+    pubkey: &Pubkey,
+) -> Result<bool> {
+    let count = *data.first().ok_or(TradeEscrowError::InvalidSignatureFormat)? as usize;
+    for i in 0..count {
+        let start = ED25519_OFFSETS_START + i * ED25519_OFFSETS_SIZE;
+        let offsets = data
+            .get(start..start + ED25519_OFFSETS_SIZE)
+            .ok_or(TradeEscrowError::InvalidSignatureFormat)?;
+        let field = |n: usize| u16::from_le_bytes([offsets[2 * n], offsets[2 * n + 1]]);
+        let (signature_offset, public_key_offset, message_offset, message_size) =
+            (field(0) as usize, field(2) as usize, field(4) as usize, field(5) as usize);
+        require!(
+            field(1) == ED25519_CURRENT_INSTRUCTION
+                && field(3) == ED25519_CURRENT_INSTRUCTION
+                && field(6) == ED25519_CURRENT_INSTRUCTION,
+            TradeEscrowError::InvalidSignatureFormat
+        );
+
+        let signed = (
+            data.get(signature_offset..signature_offset + 64),
+            data.get(public_key_offset..public_key_offset + 32),
+            data.get(message_offset..message_offset + message_size),
+        );
+        match signed {
+            (Some(signed_signature), Some(signed_pubkey), Some(signed_message)) => {
+                if signed_signature == signature
+                    && signed_pubkey == pubkey.as_ref()
+                    && signed_message == message
+                {
+                    return Ok(true);
+                }
+            }
+            _ => return err!(TradeEscrowError::InvalidSignatureFormat),
+        }
+    }
+
+    Ok(false)
+}
+
+/// Count how many distinct oracles signed `message`
+pub fn count_oracle_signatures(
+    instructions_sysvar: &AccountInfo,
+    oracle_pubkeys: &[Pubkey; 3],
+    signatures: &[[u8; 64]],
+    message: &[u8],
+) -> Result<u8> {
+    let mut signed = [false; 3];
+    for signature in signatures.iter() {
+        for (i, oracle_pubkey) in oracle_pubkeys.iter().enumerate() {
+            if !signed[i] && verify_signature(instructions_sysvar, signature, message, oracle_pubkey)? {
+                signed[i] = true;
+                break;
+            }
+        }
     }
 
-    // TODO: Implement proper Ed25519 verification
-    // This is a placeholder - in production, use proper cryptographic verification
-    Ok(true)
+    Ok(signed.iter().filter(|&&s| s).count() as u8)
 }
 
 /// Validate asset ID format
@@ -43,7 +116,7 @@ pub fn calculate_deadline(offset_seconds: i64) -> Result<i64> {
     
     // Minimum 1 minute, maximum 10 minutes
     require!(
-        offset_seconds >= 60 && offset_seconds <= 600,
+        (60..=600).contains(&offset_seconds),
         TradeEscrowError::InvalidDeadline
     );
     
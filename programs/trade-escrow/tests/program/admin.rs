@@ -0,0 +1,445 @@
+use proptest::prelude::*;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{
+    ConfigChange, FeePayer, LimitSettings, PriceTier, UserStats, VolumeTier, FEE_TIER_COUNT,
+    MAX_USER_LIMIT_WINDOW,
+};
+use trade_escrow::{ConfigUpdated, EmergencyPause, EscrowLocked, EscrowRefunded, EscrowSettled};
+use trade_escrow_client::instructions;
+
+use crate::fixture::{assert_error, replace_account, Market};
+
+#[test]
+fn initialize_records_config() {
+    let market = Market::new();
+    let config = market.config();
+    assert_eq!(config.admin, market.admin);
+    assert_eq!(config.guardian, market.guardian);
+    assert_eq!(config.fee_recipient, market.fee_recipient);
+    assert_eq!(config.oracle_pubkeys, market.oracles);
+    assert_eq!(config.fee_bps, 50);
+    assert_eq!(config.fee_payer, FeePayer::Buyer);
+    assert!(!config.paused);
+    assert_eq!(config.total_locked, 0);
+}
+
+#[test]
+fn only_guardian_pauses_and_unpauses() {
+    let mut market = Market::new();
+    let guardian = market.guardian;
+    let stranger = market.trader(0).wallet;
+
+    let ix = replace_account(instructions::pause(&guardian), &guardian, stranger);
+    assert_error(
+        market.send(&[ix], &[stranger]),
+        TradeEscrowError::UnauthorizedGuardian,
+    );
+
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+    assert!(market.config().paused);
+    let paused = market.svm.events::<EmergencyPause>();
+    assert_eq!(paused[0].triggered_by, guardian);
+    assert_eq!(paused[0].timestamp, market.svm.now());
+
+    let ix = replace_account(instructions::unpause(&guardian), &guardian, stranger);
+    assert_error(
+        market.send(&[ix], &[stranger]),
+        TradeEscrowError::UnauthorizedGuardian,
+    );
+
+    market
+        .send(&[instructions::unpause(&guardian)], &[guardian])
+        .unwrap();
+    assert!(!market.config().paused);
+    let updated = market.svm.events::<ConfigUpdated>();
+    assert_eq!(updated[0].updated_by, guardian);
+    assert_eq!(updated[0].change, ConfigChange::Unpause);
+}
+
+#[test]
+fn only_admin_updates_config() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let stranger = market.trader(0).wallet;
+
+    let updates = [
+        instructions::update_limits(&admin, 0, 0, 0, 0),
+        instructions::update_fees(&admin, 50, 0, FeePayer::Buyer),
+        instructions::update_fee_schedule(
+            &admin,
+            [PriceTier::default(); FEE_TIER_COUNT],
+            [VolumeTier::default(); FEE_TIER_COUNT],
+        ),
+        instructions::update_price_checks(&admin, admin, 0, 60, 100, 86_400),
+        instructions::update_min_fee(&admin, &market.mint, 0),
+    ];
+    for update in updates {
+        let ix = replace_account(update, &admin, stranger);
+        assert_error(
+            market.send(&[ix], &[stranger]),
+            TradeEscrowError::UnauthorizedAdmin,
+        );
+    }
+
+    market
+        .admin(instructions::update_limits(&admin, 4, 3, 2, 1))
+        .unwrap();
+    let config = market.config();
+    assert_eq!(
+        (
+            config.max_tvl,
+            config.max_trade_amount,
+            config.user_limit_amount,
+            config.user_limit_window
+        ),
+        (4, 3, 2, 1)
+    );
+    let updated = market.svm.events::<ConfigUpdated>();
+    assert_eq!(updated[0].updated_by, admin);
+    assert_eq!(
+        updated[0].change,
+        ConfigChange::Limits {
+            old: LimitSettings {
+                max_tvl: 0,
+                max_trade_amount: 0,
+                user_limit_amount: 0,
+                user_limit_window: 0,
+            },
+            new: LimitSettings {
+                max_tvl: 4,
+                max_trade_amount: 3,
+                user_limit_amount: 2,
+                user_limit_window: 1,
+            },
+        }
+    );
+}
+
+#[test]
+fn events_are_numbered_without_gaps() {
+    let mut market = Market::new();
+    let guardian = market.guardian;
+    let admin = market.admin;
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    assert_eq!(market.config().event_sequence, 0);
+
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+    assert_eq!(market.svm.events::<EmergencyPause>()[0].sequence, 1);
+    market
+        .send(&[instructions::unpause(&guardian)], &[guardian])
+        .unwrap();
+    assert_eq!(market.svm.events::<ConfigUpdated>()[0].sequence, 2);
+
+    let settled = market.lock(&buyer, &seller, 1, 1_000_000);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].sequence, 3);
+    let refunded = market.lock(&buyer, &seller, 2, 1_000_000);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].sequence, 4);
+    market.settle(&settled, &seller).unwrap();
+    assert_eq!(market.svm.events::<EscrowSettled>()[0].sequence, 5);
+
+    // Marketplace events are not numbered
+    let expiry = market.svm.now() + 3_600;
+    let ix = instructions::create_listing(
+        &seller.wallet,
+        &market.mint,
+        1,
+        3,
+        1_000_000,
+        expiry,
+        None,
+        None,
+    );
+    market.send(&[ix], &[seller.wallet]).unwrap();
+    assert_eq!(market.config().event_sequence, 5);
+
+    // Failed transactions do not use up a number
+    assert_error(
+        market.refund(&refunded, &buyer),
+        TradeEscrowError::CannotRefund,
+    );
+    market.svm.warp(301);
+    market.refund(&refunded, &buyer).unwrap();
+    assert_eq!(market.svm.events::<EscrowRefunded>()[0].sequence, 6);
+
+    market
+        .admin(instructions::update_min_fee(&admin, &market.mint, 1_000))
+        .unwrap();
+    assert_eq!(market.svm.events::<ConfigUpdated>()[0].sequence, 7);
+    assert_eq!(market.config().event_sequence, 7);
+}
+
+#[test]
+fn config_updates_validate_ranges() {
+    let mut market = Market::new();
+    let admin = market.admin;
+
+    assert_error(
+        market.admin(instructions::update_limits(&admin, 0, 0, 1, -1)),
+        TradeEscrowError::InvalidLimits,
+    );
+    assert_error(
+        market.admin(instructions::update_limits(
+            &admin,
+            0,
+            0,
+            1,
+            MAX_USER_LIMIT_WINDOW + 1,
+        )),
+        TradeEscrowError::InvalidLimits,
+    );
+    market
+        .admin(instructions::update_limits(
+            &admin,
+            0,
+            0,
+            1,
+            MAX_USER_LIMIT_WINDOW,
+        ))
+        .unwrap();
+    assert_error(
+        market.admin(instructions::update_fees(
+            &admin,
+            10_001,
+            0,
+            FeePayer::Buyer,
+        )),
+        TradeEscrowError::InvalidFeeBps,
+    );
+    assert_error(
+        market.admin(instructions::update_fees(
+            &admin,
+            50,
+            10_001,
+            FeePayer::Buyer,
+        )),
+        TradeEscrowError::InvalidFeeBps,
+    );
+    let mut price_tiers = [PriceTier::default(); FEE_TIER_COUNT];
+    price_tiers[0] = PriceTier {
+        min_amount: 1,
+        fee_bps: 10_001,
+    };
+    assert_error(
+        market.admin(instructions::update_fee_schedule(
+            &admin,
+            price_tiers,
+            [VolumeTier::default(); FEE_TIER_COUNT],
+        )),
+        TradeEscrowError::InvalidFeeBps,
+    );
+    assert_error(
+        market.admin(instructions::update_price_checks(
+            &admin, admin, 0, -1, 100, 86_400,
+        )),
+        TradeEscrowError::InvalidPriceFeed,
+    );
+    assert_error(
+        market.admin(instructions::update_price_checks(
+            &admin, admin, 0, 60, 10_001, 86_400,
+        )),
+        TradeEscrowError::InvalidPriceFeed,
+    );
+}
+
+#[test]
+fn lock_enforces_exposure_limits() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+
+    // The per-trade cap applies to the amount locked including the buyer's fee
+    market
+        .admin(instructions::update_limits(&admin, 0, 1_000_000, 0, 0))
+        .unwrap();
+    let args = market.lock_args(1, 1_000_000);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::TradeLimitExceeded,
+    );
+
+    market
+        .admin(instructions::update_limits(&admin, 1_500_000, 0, 0, 0))
+        .unwrap();
+    market.lock(&buyer, &seller, 1, 1_000_000);
+    let args = market.lock_args(2, 600_000);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::TvlLimitExceeded,
+    );
+    market.lock(&buyer, &seller, 2, 400_000);
+    assert_eq!(market.config().total_locked, 1_407_000);
+}
+
+#[test]
+fn lock_enforces_rolling_user_limit() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let buyer = market.trader(10_000_000);
+    let other = market.trader(10_000_000);
+    let seller = market.trader(0);
+    market
+        .admin(instructions::update_limits(&admin, 0, 0, 1_500_000, 3_600))
+        .unwrap();
+
+    market.lock(&buyer, &seller, 1, 1_000_000);
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let args = market.lock_args(2, 600_000);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::UserLimitExceeded,
+    );
+
+    // Other buyers have their own window
+    market.lock(&other, &seller, 2, 1_000_000);
+
+    // The window rolls rather than resetting, so the first lock counts for
+    // the whole hour after it and leaves within a bucket (1/23 hour) of that
+    market.svm.warp(3_599);
+    let args = market.lock_args(3, 1_000_000);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::UserLimitExceeded,
+    );
+    market.svm.warp(3_600 / 23 + 1);
+    market.lock(&buyer, &seller, 3, 1_000_000);
+}
+
+#[test]
+fn refund_releases_rolling_user_limit() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    market
+        .admin(instructions::update_limits(&admin, 0, 0, 1_500_000, 3_600))
+        .unwrap();
+
+    let escrow = market.lock(&buyer, &seller, 1, 1_000_000);
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let args = market.lock_args(2, 600_000);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::UserLimitExceeded,
+    );
+
+    // The refunded trade stops counting within the same window
+    market.svm.warp(301);
+    market.refund(&escrow, &buyer).unwrap();
+    assert_eq!(market.config().total_locked, 0);
+    market.lock(&buyer, &seller, 3, 1_000_000);
+}
+
+#[test]
+fn fee_schedule_applies_price_tiers_and_volume_discounts() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let mut price_tiers = [PriceTier::default(); FEE_TIER_COUNT];
+    price_tiers[0] = PriceTier {
+        min_amount: 1_000_000,
+        fee_bps: 20,
+    };
+    let mut volume_tiers = [VolumeTier::default(); FEE_TIER_COUNT];
+    volume_tiers[0] = VolumeTier {
+        min_volume: 1_000_000,
+        discount_bps: 5_000,
+    };
+    market
+        .admin(instructions::update_fee_schedule(
+            &admin,
+            price_tiers,
+            volume_tiers,
+        ))
+        .unwrap();
+    assert_eq!(market.config().price_tiers, price_tiers);
+    assert_eq!(market.config().volume_tiers, volume_tiers);
+    assert_eq!(
+        market.svm.events::<ConfigUpdated>()[0].change,
+        ConfigChange::FeeSchedule {
+            old_price_tiers: [PriceTier::default(); FEE_TIER_COUNT],
+            new_price_tiers: price_tiers,
+            old_volume_tiers: [VolumeTier::default(); FEE_TIER_COUNT],
+            new_volume_tiers: volume_tiers,
+        }
+    );
+
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    market.lock(&buyer, &seller, 1, 999_999);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].fee, 4_999);
+
+    let escrow = market.lock(&buyer, &seller, 2, 1_000_000);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].fee, 2_000);
+
+    // Settled volume earns the discount on later trades
+    market.settle(&escrow, &seller).unwrap();
+    market.lock(&buyer, &seller, 3, 1_000_000);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].fee, 1_000);
+
+    // The discount is the buyer's even when the seller bears the fee
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let mut args = market.lock_args(5, 1_000_000);
+    args.fee_payer_override = Some(FeePayer::Seller);
+    market.lock_with(&accounts, &args).unwrap();
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].fee, 1_000);
+
+    // ...until it falls out of the 30-day window
+    market.svm.warp(31 * 86_400);
+    market.lock(&buyer, &seller, 4, 1_000_000);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].fee, 2_000);
+}
+
+const USER_LIMIT: u64 = 1_000_000;
+
+proptest! {
+    #[test]
+    fn no_window_holds_more_than_the_limit(
+        window in 1..=100_000i64,
+        locks in proptest::collection::vec((0..=50_000i64, 1..=USER_LIMIT), 1..40),
+    ) {
+        let mut stats = UserStats::default();
+        let mut now = 1_700_000_000;
+        let mut accepted: Vec<(i64, u64)> = vec![];
+        for (gap, amount) in locks {
+            now += gap;
+            if stats.current_window_volume(now, window) + amount > USER_LIMIT {
+                continue;
+            }
+            stats.record_lock(amount, now, window);
+            accepted.push((now, amount));
+
+            let in_window: u64 = accepted
+                .iter()
+                .filter(|(locked_at, _)| now - locked_at < window)
+                .map(|(_, amount)| amount)
+                .sum();
+            prop_assert!(in_window <= USER_LIMIT);
+        }
+    }
+
+    #[test]
+    fn release_returns_the_lock(
+        window in 1..=100_000i64,
+        amount in 1..=USER_LIMIT,
+        held in 0..=100_000i64,
+    ) {
+        let mut stats = UserStats::default();
+        stats.record_lock(amount, 1_700_000_000, window);
+        stats.release_lock(amount, 1_700_000_000, 1_700_000_000 + held, window);
+        prop_assert_eq!(stats.current_window_volume(1_700_000_000 + held, window), 0);
+    }
+
+    #[test]
+    fn oversized_windows_do_not_overflow(window in i64::MAX - 100..=i64::MAX, now in any::<i64>()) {
+        let mut stats = UserStats::default();
+        stats.record_lock(USER_LIMIT, now, window);
+        stats.release_lock(USER_LIMIT, now, now, window);
+        prop_assert_eq!(stats.current_window_volume(now, window), 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::TokenAccount;
+use solana_sdk::signature::{Keypair, Signature};
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{Config, Escrow};
+use trade_escrow::EscrowSettled;
+use trade_escrow_aggregator::{
+    settle_instructions, Aggregator, AggregatorError, Chain, HttpOracle, OpenEscrow, OracleEndpoint,
+};
+use trade_escrow_client::{instructions, pda};
+use trade_escrow_oracle::{OracleSigner, ReceiptServer, ReceiptStore, TradeObservation};
+
+use crate::fixture::{assert_error, Market};
+
+/// Chain backed by the in-process runtime, submitting without fee payer
+struct SvmChain {
+    market: RefCell<Market>,
+}
+
+impl Chain for SvmChain {
+    fn config(&self) -> std::result::Result<Config, AggregatorError> {
+        Ok(self.market.borrow().config())
+    }
+
+    fn unix_timestamp(&self) -> std::result::Result<i64, AggregatorError> {
+        Ok(self.market.borrow().svm.now())
+    }
+
+    fn open_escrows(&self) -> std::result::Result<Vec<OpenEscrow>, AggregatorError> {
+        let market = self.market.borrow();
+        let mut open = vec![];
+        for (address, account) in market.svm.accounts_owned_by(&trade_escrow::ID) {
+            if !account.data.starts_with(&Escrow::DISCRIMINATOR) {
+                continue;
+            }
+            let escrow = Escrow::try_deserialize(&mut &account.data[..])
+                .map_err(|_| AggregatorError::Decode(address))?;
+            if escrow.settled {
+                continue;
+            }
+            let vault: TokenAccount = market.svm.get(&pda::escrow_vault(&address));
+            open.push(OpenEscrow {
+                address,
+                escrow,
+                mint: vault.mint,
+            });
+        }
+        Ok(open)
+    }
+
+    fn submit(
+        &self,
+        instructions: &[Instruction],
+    ) -> std::result::Result<Signature, AggregatorError> {
+        self.market
+            .borrow_mut()
+            .send(instructions, &[])
+            .map_err(|error| AggregatorError::Rpc(format!("{:?}", error)))?;
+        Ok(Signature::new_unique())
+    }
+}
+
+/// An oracle service on a local port
+fn start_oracle() -> (OracleSigner, ReceiptStore, String) {
+    let signer = OracleSigner::new(Keypair::new());
+    let store = ReceiptStore::new();
+    let server = ReceiptServer::bind("127.0.0.1:0", signer.pubkey(), store.clone()).unwrap();
+    let url = format!("http://{}", server.local_addr().unwrap());
+    server.spawn();
+    (signer, store, url)
+}
+
+#[test]
+fn aggregator_settles_locked_escrow_from_oracle_receipts() {
+    let mut market = Market::new();
+    let oracles = [start_oracle(), start_oracle()];
+    let admin = market.admin;
+    let oracle_keys = [
+        oracles[0].0.pubkey(),
+        oracles[1].0.pubkey(),
+        Pubkey::new_unique(),
+    ];
+    market
+        .admin(instructions::update_oracles(&admin, oracle_keys))
+        .unwrap();
+
+    let buyer = market.trader(1_005_000);
+    let seller = market.trader(0);
+    let mint = market.mint;
+    let seller_tokens = get_associated_token_address(&seller.wallet, &mint);
+    market
+        .svm
+        .create_token_account_at(seller_tokens, &seller.wallet, &mint, 0);
+    let escrow = market.lock(&buyer, &seller, 42, 1_000_000);
+
+    let endpoints: Vec<Box<dyn OracleEndpoint>> = oracles
+        .iter()
+        .map(|(_, _, url)| Box::new(HttpOracle::new(url)) as Box<dyn OracleEndpoint>)
+        .collect();
+    let chain = SvmChain {
+        market: RefCell::new(market),
+    };
+    let mut aggregator = Aggregator::new(chain, endpoints, 2);
+    let start = Instant::now();
+
+    // Nothing to submit until both oracles have attested delivery
+    let delivered = TradeObservation::ItemDelivered {
+        escrow,
+        asset_id: 42,
+        buyer: buyer.wallet,
+        criteria: None,
+    };
+    oracles[0].1.insert(oracles[0].0.sign(&delivered));
+    let report = aggregator.tick(start).unwrap();
+    assert!(report.submitted.is_empty());
+    assert_eq!(report.waiting, 1);
+
+    oracles[1].1.insert(oracles[1].0.sign(&delivered));
+
+    // Without their Ed25519 verify instructions the receipts do not count
+    let receipts = [oracles[0].0.sign(&delivered), oracles[1].0.sign(&delivered)];
+    let open = aggregator.chain().open_escrows().unwrap().remove(0);
+    let ixs = settle_instructions(&open, &receipts);
+    assert_eq!(ixs.len(), 3);
+    assert_error(
+        aggregator.chain().market.borrow_mut().send(&ixs[2..], &[]),
+        TradeEscrowError::InvalidOracleSignatures,
+    );
+
+    let report = aggregator.tick(start + Duration::from_secs(60)).unwrap();
+    assert_eq!(report.submitted.len(), 1);
+    assert!(report.errors.is_empty());
+
+    let market = aggregator.chain().market.borrow();
+    assert!(market.escrow(&escrow).settled);
+    assert_eq!(market.svm.events::<EscrowSettled>()[0].escrow_id, escrow);
+    assert_eq!(market.svm.balance(&pda::escrow_vault(&escrow)), 0);
+    assert_eq!(market.svm.balance(&seller_tokens), 1_000_000);
+    assert_eq!(market.fee_vault_balance(), 5_000);
+    drop(market);
+
+    // Settled escrows are no longer listed
+    let report = aggregator.tick(start + Duration::from_secs(120)).unwrap();
+    assert!(report.submitted.is_empty());
+    assert_eq!(report.waiting, 0);
+}
\ No newline at end of file
@@ -0,0 +1,432 @@
+use anchor_lang::prelude::*;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::Auction;
+use trade_escrow::{AuctionCancelled, AuctionCreated, AuctionFinalized, BidPlaced};
+use trade_escrow_client::instructions;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_anchor_error, assert_error, replace_account, Market, Trader};
+use crate::svm::TxError;
+
+/// 1 token reserve, 0.1 token increments, 100s of bidding, 60s anti-sniping window
+fn create_auction(market: &mut Market, seller: &Trader) -> Pubkey {
+    let end_time = market.svm.now() + 100;
+    let ix = instructions::create_auction(
+        &seller.wallet,
+        &market.mint,
+        1,
+        42,
+        1_000_000,
+        100_000,
+        end_time,
+        60,
+        300,
+        None,
+    );
+    market.send(&[ix], &[seller.wallet]).unwrap();
+    pda::auction(&seller.wallet, 1)
+}
+
+fn bid(
+    market: &mut Market,
+    auction_key: &Pubkey,
+    bidder: &Trader,
+    previous: Option<&Trader>,
+    amount: u64,
+) -> std::result::Result<(), TxError> {
+    let auction: Auction = market.svm.get(auction_key);
+    bid_on(market, auction_key, &auction, bidder, previous, amount)
+}
+
+fn bid_on(
+    market: &mut Market,
+    auction_key: &Pubkey,
+    auction: &Auction,
+    bidder: &Trader,
+    previous: Option<&Trader>,
+    amount: u64,
+) -> std::result::Result<(), TxError> {
+    let ix = instructions::place_bid(
+        auction_key,
+        auction,
+        &bidder.wallet,
+        &bidder.tokens,
+        previous.map(|previous| previous.tokens),
+        amount,
+    );
+    market.send(&[ix], &[bidder.wallet])
+}
+
+fn finalize(
+    market: &mut Market,
+    auction_key: &Pubkey,
+    winner: &Trader,
+) -> std::result::Result<Pubkey, TxError> {
+    let auction: Auction = market.svm.get(auction_key);
+    let nonce = market.svm.now() as u64;
+    let payer = market.admin;
+    let ix = instructions::finalize_auction(auction_key, &auction, &winner.wallet, &payer, nonce);
+    market.send(&[ix], &[payer])?;
+    Ok(pda::escrow(
+        &winner.wallet,
+        &auction.seller,
+        auction.asset_id,
+        nonce,
+    ))
+}
+
+#[test]
+fn highest_bid_becomes_trade_escrow() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let alice = market.trader(5_000_000);
+    let bob = market.trader(5_000_000);
+    let auction_key = create_auction(&mut market, &seller);
+    assert_eq!(
+        market.svm.events::<AuctionCreated>()[0].reserve_price,
+        1_000_000
+    );
+    let vault = pda::auction_vault(&auction_key);
+
+    bid(&mut market, &auction_key, &alice, None, 1_000_000).unwrap();
+    assert_eq!(market.svm.balance(&vault), 1_005_000);
+    assert_eq!(market.config().total_locked, 1_005_000);
+
+    // Outbidding refunds the previous high bidder in the same instruction
+    bid(&mut market, &auction_key, &bob, Some(&alice), 1_200_000).unwrap();
+    assert_eq!(market.svm.balance(&alice.tokens), 5_000_000);
+    assert_eq!(market.svm.balance(&vault), 1_206_000);
+    assert_eq!(market.config().total_locked, 1_206_000);
+    assert_eq!(market.svm.events::<BidPlaced>()[0].bidder, bob.wallet);
+
+    assert_error(
+        finalize(&mut market, &auction_key, &bob),
+        TradeEscrowError::AuctionNotEnded,
+    );
+    market.svm.warp(100);
+    assert_error(
+        bid(&mut market, &auction_key, &alice, Some(&bob), 1_300_000),
+        TradeEscrowError::AuctionEnded,
+    );
+    assert_error(
+        finalize(&mut market, &auction_key, &alice),
+        TradeEscrowError::NoWinningBid,
+    );
+
+    let closed: Auction = market.svm.get(&auction_key);
+    let seller_lamports = market.svm.lamports(&seller.wallet);
+    let auction_rent = market.svm.lamports(&auction_key);
+    let vault_rent = market.svm.lamports(&vault);
+    let escrow = finalize(&mut market, &auction_key, &bob).unwrap();
+    assert!(!market.svm.exists(&auction_key));
+    assert!(!market.svm.exists(&vault));
+    assert_eq!(
+        market.svm.lamports(&seller.wallet),
+        seller_lamports + auction_rent + vault_rent
+    );
+    let finalized = market.svm.events::<AuctionFinalized>();
+    assert_eq!(
+        (finalized[0].escrow_id, finalized[0].amount),
+        (escrow, 1_200_000)
+    );
+    let trade = market.escrow(&escrow);
+    assert_eq!(
+        (trade.buyer, trade.amount, trade.fee_amount),
+        (bob.wallet, 1_200_000, 6_000)
+    );
+    assert_eq!(trade.deadline, market.svm.now() + 300);
+    assert_eq!(market.svm.balance(&pda::escrow_vault(&escrow)), 1_206_000);
+    assert_eq!(market.config().total_locked, 1_206_000);
+
+    // The auction is closed on finalization, so later bids can't load it
+    assert_anchor_error(
+        bid_on(
+            &mut market,
+            &auction_key,
+            &closed,
+            &alice,
+            Some(&bob),
+            1_300_000,
+        ),
+        ErrorCode::AccountNotInitialized,
+    );
+
+    market.settle(&escrow, &seller).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 1_200_000);
+    assert_eq!(market.config().total_locked, 0);
+}
+
+#[test]
+fn outbid_bidders_get_their_reservation_back() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    market
+        .admin(instructions::update_limits(
+            &admin, 0, 0, 10_000_000, 86_400,
+        ))
+        .unwrap();
+    let seller = market.trader(0);
+    let alice = market.trader(5_000_000);
+    let bob = market.trader(5_000_000);
+    let auction_key = create_auction(&mut market, &seller);
+    let window_volume = |market: &Market, trader: &Trader| market.window_volume(&trader.wallet);
+
+    bid(&mut market, &auction_key, &alice, None, 1_000_000).unwrap();
+    bid(&mut market, &auction_key, &bob, Some(&alice), 1_100_000).unwrap();
+    assert_eq!(market.config().total_locked, 1_105_500);
+    assert_eq!(window_volume(&market, &alice), 0);
+    assert_eq!(window_volume(&market, &bob), 1_105_500);
+
+    // The outbid bidder's stats must be their own
+    let auction: Auction = market.svm.get(&auction_key);
+    let ix = instructions::place_bid(
+        &auction_key,
+        &auction,
+        &alice.wallet,
+        &alice.tokens,
+        Some(bob.tokens),
+        1_200_000,
+    );
+    let ix = replace_account(
+        ix,
+        &pda::user_stats(&bob.wallet),
+        pda::user_stats(&alice.wallet),
+    );
+    assert_error(
+        market.send(&[ix], &[alice.wallet]),
+        TradeEscrowError::InvalidPreviousBidderAccount,
+    );
+
+    bid(&mut market, &auction_key, &alice, Some(&bob), 1_200_000).unwrap();
+    assert_eq!(market.config().total_locked, 1_206_000);
+    assert_eq!(window_volume(&market, &alice), 1_206_000);
+    assert_eq!(window_volume(&market, &bob), 0);
+
+    // Raising your own bid swaps the old reservation for the new one
+    bid(&mut market, &auction_key, &alice, Some(&alice), 1_300_000).unwrap();
+    assert_eq!(market.config().total_locked, 1_306_500);
+    assert_eq!(window_volume(&market, &alice), 1_306_500);
+    assert_eq!(market.svm.balance(&alice.tokens), 5_000_000 - 1_306_500);
+    assert_eq!(market.svm.balance(&bob.tokens), 5_000_000);
+}
+
+#[test]
+fn late_bids_extend_the_auction() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let alice = market.trader(5_000_000);
+    let bob = market.trader(5_000_000);
+    let auction_key = create_auction(&mut market, &seller);
+    let start = market.svm.now();
+
+    market.svm.warp(90);
+    bid(&mut market, &auction_key, &alice, None, 1_000_000).unwrap();
+    assert_eq!(market.svm.events::<BidPlaced>()[0].end_time, start + 150);
+
+    market.svm.warp(20);
+    bid(&mut market, &auction_key, &bob, Some(&alice), 1_100_000).unwrap();
+    let auction: Auction = market.svm.get(&auction_key);
+    assert_eq!(auction.end_time, start + 170);
+}
+
+#[test]
+fn bids_are_validated() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let alice = market.trader(5_000_000);
+    let bob = market.trader(5_000_000);
+    let auction_key = create_auction(&mut market, &seller);
+
+    assert_error(
+        bid(&mut market, &auction_key, &alice, None, 999_999),
+        TradeEscrowError::BidTooLow,
+    );
+    assert_error(
+        bid(&mut market, &auction_key, &seller, None, 1_000_000),
+        TradeEscrowError::InvalidAuction,
+    );
+    bid(&mut market, &auction_key, &alice, None, 1_000_000).unwrap();
+
+    assert_error(
+        bid(&mut market, &auction_key, &bob, Some(&alice), 1_099_999),
+        TradeEscrowError::BidTooLow,
+    );
+    assert_error(
+        bid(&mut market, &auction_key, &bob, None, 1_100_000),
+        TradeEscrowError::InvalidPreviousBidderAccount,
+    );
+    assert_error(
+        bid(&mut market, &auction_key, &bob, Some(&bob), 1_100_000),
+        TradeEscrowError::InvalidPreviousBidderAccount,
+    );
+    // The bid plus the buyer's fee is more than Bob holds
+    assert_error(
+        bid(&mut market, &auction_key, &bob, Some(&alice), 5_000_000),
+        TradeEscrowError::InsufficientFunds,
+    );
+    assert_eq!(market.svm.balance(&bob.tokens), 5_000_000);
+
+    let guardian = market.guardian;
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+    assert_error(
+        bid(&mut market, &auction_key, &bob, Some(&alice), 1_100_000),
+        TradeEscrowError::ContractPaused,
+    );
+}
+
+#[test]
+fn create_auction_validates_terms() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let now = market.svm.now();
+    let mint = market.mint;
+
+    let invalid = [
+        (0, 1_000_000, 100_000, now + 100, 60),
+        (42, 0, 100_000, now + 100, 60),
+        (42, 1_000_000, 0, now + 100, 60),
+        (42, 1_000_000, 100_000, now, 60),
+        (42, 1_000_000, 100_000, now + 100, -1),
+    ];
+    for (asset_id, reserve, increment, end_time, extension) in invalid {
+        let ix = instructions::create_auction(
+            &seller.wallet,
+            &mint,
+            1,
+            asset_id,
+            reserve,
+            increment,
+            end_time,
+            extension,
+            300,
+            None,
+        );
+        assert_error(
+            market.send(&[ix], &[seller.wallet]),
+            TradeEscrowError::InvalidAuction,
+        );
+    }
+    let ix = instructions::create_auction(
+        &seller.wallet,
+        &mint,
+        1,
+        42,
+        1_000_000,
+        100_000,
+        now + 100,
+        60,
+        601,
+        None,
+    );
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidDeadline,
+    );
+
+    let guardian = market.guardian;
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+    let ix = instructions::create_auction(
+        &seller.wallet,
+        &mint,
+        1,
+        42,
+        1_000_000,
+        100_000,
+        now + 100,
+        60,
+        300,
+        None,
+    );
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::ContractPaused,
+    );
+}
+
+#[test]
+fn only_seller_cancels_auction_without_bids() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let stranger = market.trader(0);
+    let alice = market.trader(5_000_000);
+    let auction_key = create_auction(&mut market, &seller);
+    let auction: Auction = market.svm.get(&auction_key);
+
+    let ix = replace_account(
+        instructions::cancel_auction(&auction_key, &auction),
+        &seller.wallet,
+        stranger.wallet,
+    );
+    assert_error(
+        market.send(&[ix], &[stranger.wallet]),
+        TradeEscrowError::UnauthorizedSeller,
+    );
+
+    // The seller can't pull an auction out from under a bidder
+    bid(&mut market, &auction_key, &alice, None, 1_000_000).unwrap();
+    let ix = instructions::cancel_auction(&auction_key, &auction);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::AuctionHasBids,
+    );
+
+    let ix = instructions::create_auction(
+        &seller.wallet,
+        &market.mint,
+        2,
+        43,
+        1_000_000,
+        100_000,
+        market.svm.now() + 100,
+        60,
+        300,
+        None,
+    );
+    market.send(&[ix], &[seller.wallet]).unwrap();
+    let empty_key = pda::auction(&seller.wallet, 2);
+    let empty: Auction = market.svm.get(&empty_key);
+    market
+        .send(
+            &[instructions::cancel_auction(&empty_key, &empty)],
+            &[seller.wallet],
+        )
+        .unwrap();
+    assert_eq!(
+        market.svm.events::<AuctionCancelled>()[0].auction,
+        empty_key
+    );
+    assert!(!market.svm.exists(&empty_key));
+    assert!(!market.svm.exists(&pda::auction_vault(&empty_key)));
+}
+
+#[test]
+fn finalize_checks_seller_and_mint() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let alice = market.trader(5_000_000);
+    let auction_key = create_auction(&mut market, &seller);
+    bid(&mut market, &auction_key, &alice, None, 1_000_000).unwrap();
+    market.svm.warp(100);
+
+    let auction: Auction = market.svm.get(&auction_key);
+    let nonce = market.svm.now() as u64;
+    let payer = market.admin;
+    let ix = instructions::finalize_auction(&auction_key, &auction, &alice.wallet, &payer, nonce);
+    let ix = replace_account(ix, &seller.wallet, alice.wallet);
+    assert_error(
+        market.send(&[ix], &[payer]),
+        TradeEscrowError::UnauthorizedSeller,
+    );
+
+    let other_mint = market.svm.create_mint(6);
+    let ix = instructions::finalize_auction(&auction_key, &auction, &alice.wallet, &payer, nonce);
+    let ix = replace_account(ix, &market.mint, other_mint);
+    assert_error(
+        market.send(&[ix], &[payer]),
+        TradeEscrowError::InvalidAuction,
+    );
+}
\ No newline at end of file
@@ -0,0 +1,334 @@
+use anchor_lang::prelude::*;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{BuyOrder, FeePayer, ItemClass, WearBounds};
+use trade_escrow::{BuyOrderCancelled, BuyOrderCreated, BuyOrderFilled};
+use trade_escrow_client::instructions;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_error, replace_account, Market, Trader};
+use crate::svm::TxError;
+
+fn redline() -> ItemClass {
+    ItemClass {
+        appid: 730,
+        market_hash_name_hash: [1; 32],
+    }
+}
+
+/// Float below 0.2 on the program's wear scale
+fn field_tested() -> Option<WearBounds> {
+    Some(WearBounds {
+        min: 0,
+        max: 200_000_000,
+    })
+}
+
+fn create_buy_order(
+    market: &mut Market,
+    buyer: &Trader,
+    unit_price: u64,
+    quantity: u32,
+) -> std::result::Result<Pubkey, TxError> {
+    let expiry = market.svm.now() + 3_600;
+    let ix = instructions::create_buy_order(
+        &buyer.wallet,
+        &market.mint,
+        &buyer.tokens,
+        1,
+        redline(),
+        field_tested(),
+        unit_price,
+        quantity,
+        expiry,
+        None,
+    );
+    market.send(&[ix], &[buyer.wallet])?;
+    Ok(pda::buy_order(&buyer.wallet, 1))
+}
+
+fn fill(
+    market: &mut Market,
+    order_key: &Pubkey,
+    seller: &Trader,
+    asset_id: u64,
+    wear: Option<u32>,
+) -> std::result::Result<Pubkey, TxError> {
+    let order: BuyOrder = market.svm.get(order_key);
+    let nonce = market.svm.now() as u64;
+    let ix = instructions::fill_buy_order(
+        order_key,
+        &order,
+        &seller.wallet,
+        asset_id,
+        wear,
+        300,
+        nonce,
+    );
+    market.send(&[ix], &[seller.wallet])?;
+    Ok(pda::escrow(&order.buyer, &seller.wallet, asset_id, nonce))
+}
+
+#[test]
+fn sellers_fill_buy_order_one_item_at_a_time() {
+    let mut market = Market::new();
+    let buyer = market.trader(5_000_000);
+    let seller = market.trader(0);
+    let order_key = create_buy_order(&mut market, &buyer, 1_000_000, 2).unwrap();
+    let vault = pda::buy_order_vault(&order_key);
+    assert_eq!(market.svm.balance(&vault), 2_010_000);
+    assert_eq!(market.svm.events::<BuyOrderCreated>()[0].deposit, 2_010_000);
+    assert_eq!(market.config().total_locked, 2_010_000);
+
+    let first = fill(&mut market, &order_key, &seller, 100, Some(150_000_000)).unwrap();
+    assert_eq!(market.svm.balance(&vault), 1_005_000);
+    assert_eq!(market.svm.balance(&pda::escrow_vault(&first)), 1_005_000);
+    let escrow = market.escrow(&first);
+    assert_eq!(escrow.buyer, buyer.wallet);
+    assert_eq!(
+        (escrow.item_class, escrow.wear_bounds),
+        (Some(redline()), field_tested())
+    );
+    let filled = market.svm.events::<BuyOrderFilled>();
+    assert_eq!((filled[0].escrow_id, filled[0].remaining), (first, 1));
+
+    let second = fill(&mut market, &order_key, &seller, 101, Some(10_000_000)).unwrap();
+    assert_eq!(market.svm.balance(&vault), 0);
+    assert_eq!(market.svm.events::<BuyOrderFilled>()[0].remaining, 0);
+    assert_error(
+        fill(&mut market, &order_key, &seller, 102, Some(10_000_000)),
+        TradeEscrowError::BuyOrderFilled,
+    );
+
+    // Filling moves exposure from the bid to its escrows
+    assert_eq!(market.config().total_locked, 2_010_000);
+
+    market.settle(&first, &seller).unwrap();
+    market.settle(&second, &seller).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 2_000_000);
+    assert_eq!(market.fee_vault_balance(), 10_000);
+    assert_eq!(market.config().total_locked, 0);
+}
+
+#[test]
+fn fills_charge_the_fee_reserved_at_creation() {
+    let mut market = Market::new();
+    let buyer = market.trader(5_000_000);
+    let seller = market.trader(0);
+    let order_key = create_buy_order(&mut market, &buyer, 1_000_000, 2).unwrap();
+    let order: BuyOrder = market.svm.get(&order_key);
+    assert_eq!(
+        (order.fee_payer, order.unit_fee, order.unit_fee_bps),
+        (FeePayer::Buyer, 5_000, 50)
+    );
+
+    let admin = market.admin;
+    market
+        .admin(instructions::update_fees(&admin, 500, 0, FeePayer::Seller))
+        .unwrap();
+
+    let first = fill(&mut market, &order_key, &seller, 100, Some(1)).unwrap();
+    let second = fill(&mut market, &order_key, &seller, 101, Some(1)).unwrap();
+    let escrow = market.escrow(&second);
+    assert_eq!(
+        (escrow.fee_payer, escrow.fee_amount, escrow.fee_bps),
+        (FeePayer::Buyer, 5_000, 50)
+    );
+    assert_eq!(market.svm.balance(&pda::buy_order_vault(&order_key)), 0);
+
+    market.settle(&first, &seller).unwrap();
+    market.settle(&second, &seller).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 2_000_000);
+    assert_eq!(market.fee_vault_balance(), 10_000);
+}
+
+#[test]
+fn buy_order_deposit_counts_against_limits() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let buyer = market.trader(5_000_000);
+
+    // Each item fits the TVL and user limits, but a bid for two does not
+    let cases = [
+        (
+            (0, 1_000_000, 0, 0),
+            1,
+            TradeEscrowError::TradeLimitExceeded,
+        ),
+        ((2_000_000, 0, 0, 0), 2, TradeEscrowError::TvlLimitExceeded),
+        (
+            (0, 0, 2_000_000, 86_400),
+            2,
+            TradeEscrowError::UserLimitExceeded,
+        ),
+    ];
+    for ((max_tvl, max_trade_amount, user_limit_amount, window), quantity, error) in cases {
+        market
+            .admin(instructions::update_limits(
+                &admin,
+                max_tvl,
+                max_trade_amount,
+                user_limit_amount,
+                window,
+            ))
+            .unwrap();
+        assert_error(
+            create_buy_order(&mut market, &buyer, 1_000_000, quantity),
+            error,
+        );
+    }
+    assert_eq!(market.svm.balance(&buyer.tokens), 5_000_000);
+    assert_eq!(market.config().total_locked, 0);
+}
+
+#[test]
+fn create_buy_order_validates_terms() {
+    let mut market = Market::new();
+    let buyer = market.trader(5_000_000);
+    let now = market.svm.now();
+    let bad_bounds = Some(WearBounds { min: 2, max: 1 });
+
+    let cases = [
+        (0, 1, now + 60, field_tested()),
+        (1_000, 0, now + 60, field_tested()),
+        (1_000, 1, now, field_tested()),
+        (1_000, 1, now + 60, bad_bounds),
+    ];
+    for (unit_price, quantity, expiry, wear_bounds) in cases {
+        let ix = instructions::create_buy_order(
+            &buyer.wallet,
+            &market.mint,
+            &buyer.tokens,
+            1,
+            redline(),
+            wear_bounds,
+            unit_price,
+            quantity,
+            expiry,
+            None,
+        );
+        assert_error(
+            market.send(&[ix], &[buyer.wallet]),
+            TradeEscrowError::InvalidBuyOrder,
+        );
+    }
+
+    // Three items at 2_000_000 plus fees need more than the buyer holds
+    assert_error(
+        create_buy_order(&mut market, &buyer, 2_000_000, 3),
+        TradeEscrowError::InsufficientFunds,
+    );
+    assert_eq!(market.svm.balance(&buyer.tokens), 5_000_000);
+    assert_eq!(market.config().total_locked, 0);
+}
+
+#[test]
+fn fill_buy_order_checks_item_and_order() {
+    let mut market = Market::new();
+    let buyer = market.trader(5_000_000);
+    let seller = market.trader(0);
+    let order_key = create_buy_order(&mut market, &buyer, 1_000_000, 1).unwrap();
+    let order: BuyOrder = market.svm.get(&order_key);
+    let nonce = market.svm.now() as u64;
+
+    assert_error(
+        fill(&mut market, &order_key, &seller, 100, Some(250_000_000)),
+        TradeEscrowError::ItemDoesNotMatch,
+    );
+    assert_error(
+        fill(&mut market, &order_key, &seller, 100, None),
+        TradeEscrowError::ItemDoesNotMatch,
+    );
+    assert_error(
+        fill(&mut market, &order_key, &seller, 0, Some(1)),
+        TradeEscrowError::InvalidBuyOrder,
+    );
+    let ix =
+        instructions::fill_buy_order(&order_key, &order, &seller.wallet, 100, Some(1), 601, nonce);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidDeadline,
+    );
+
+    let other_mint = market.svm.create_mint(6);
+    let ix =
+        instructions::fill_buy_order(&order_key, &order, &seller.wallet, 100, Some(1), 300, nonce);
+    let ix = replace_account(ix, &market.mint, other_mint);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidBuyOrder,
+    );
+
+    market.svm.warp(3_601);
+    assert_error(
+        fill(&mut market, &order_key, &seller, 100, Some(1)),
+        TradeEscrowError::BuyOrderExpired,
+    );
+}
+
+#[test]
+fn cancel_returns_unfilled_balance() {
+    let mut market = Market::new();
+    let buyer = market.trader(5_000_000);
+    let seller = market.trader(0);
+    let stranger = market.trader(0);
+    let admin = market.admin;
+    market
+        .admin(instructions::update_limits(
+            &admin, 0, 0, 10_000_000, 86_400,
+        ))
+        .unwrap();
+    let order_key = create_buy_order(&mut market, &buyer, 1_000_000, 3).unwrap();
+    fill(&mut market, &order_key, &seller, 100, Some(1)).unwrap();
+    let order: BuyOrder = market.svm.get(&order_key);
+
+    let ix = instructions::cancel_buy_order(&order_key, &order, &stranger.tokens);
+    let ix = replace_account(ix, &buyer.wallet, stranger.wallet);
+    assert_error(
+        market.send(&[ix], &[stranger.wallet]),
+        TradeEscrowError::UnauthorizedBuyer,
+    );
+
+    market
+        .send(
+            &[instructions::cancel_buy_order(
+                &order_key,
+                &order,
+                &buyer.tokens,
+            )],
+            &[buyer.wallet],
+        )
+        .unwrap();
+    assert_eq!(market.svm.balance(&buyer.tokens), 5_000_000 - 1_005_000);
+    // Only the filled item's escrow still counts against the limits
+    assert_eq!(market.config().total_locked, 1_005_000);
+    assert_eq!(market.window_volume(&buyer.wallet), 1_005_000);
+    assert!(!market.svm.exists(&order_key));
+    assert!(!market.svm.exists(&pda::buy_order_vault(&order_key)));
+    let cancelled = market.svm.events::<BuyOrderCancelled>();
+    assert_eq!(
+        (cancelled[0].unfilled, cancelled[0].refunded),
+        (2, 2_010_000)
+    );
+}
+
+#[test]
+fn paused_market_rejects_buy_orders() {
+    let mut market = Market::new();
+    let buyer = market.trader(5_000_000);
+    let seller = market.trader(0);
+    let order_key = create_buy_order(&mut market, &buyer, 1_000_000, 1).unwrap();
+    let guardian = market.guardian;
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+
+    assert_error(
+        fill(&mut market, &order_key, &seller, 100, Some(1)),
+        TradeEscrowError::ContractPaused,
+    );
+    let other = market.trader(5_000_000);
+    assert_error(
+        create_buy_order(&mut market, &other, 1_000_000, 1),
+        TradeEscrowError::ContractPaused,
+    );
+}
\ No newline at end of file
@@ -0,0 +1,407 @@
+use anchor_lang::prelude::*;
+use proptest::prelude::*;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{CommodityEscrow, Config, ItemClass, RefundReason};
+use trade_escrow::{CommodityEscrowLocked, CommodityEscrowSettled, EscrowRefunded};
+use trade_escrow_client::instructions::{self, LockCommodityAccounts};
+use trade_escrow_client::messages;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_error, replace_account, Market, Trader};
+use crate::strategy::{fee_payer, locked_commodity};
+use crate::svm::TxError;
+
+pub fn revolution_case() -> ItemClass {
+    ItemClass {
+        appid: 730,
+        market_hash_name_hash: [2; 32],
+    }
+}
+
+fn lock_with(
+    market: &mut Market,
+    buyer: &Trader,
+    seller: &Trader,
+    quantity: u32,
+    unit_price: u64,
+    signer: &Pubkey,
+    deadline_offset: i64,
+) -> std::result::Result<Pubkey, TxError> {
+    let accounts = LockCommodityAccounts {
+        buyer: buyer.wallet,
+        seller: seller.wallet,
+        mint: market.mint,
+        buyer_token_account: buyer.tokens,
+    };
+    let nonce = market.svm.now() as u64;
+    let message = messages::commodity_ask_message(
+        &revolution_case(),
+        quantity,
+        unit_price,
+        &seller.wallet,
+        market.svm.now() + deadline_offset,
+        nonce,
+        None,
+    );
+    let (mut ixs, signatures) = market.signed(&message, &[*signer]);
+    ixs.push(instructions::lock_commodity(
+        &accounts,
+        revolution_case(),
+        quantity,
+        unit_price,
+        unit_price.saturating_mul(quantity as u64),
+        signatures[0],
+        deadline_offset,
+        None,
+        nonce,
+    ));
+    market.send(&ixs, &[buyer.wallet])?;
+    Ok(pda::commodity_escrow(&buyer.wallet, &seller.wallet, nonce))
+}
+
+/// 50 cases at 0.1 tokens each
+pub fn lock(market: &mut Market, buyer: &Trader, seller: &Trader) -> Pubkey {
+    lock_with(market, buyer, seller, 50, 100_000, &seller.wallet, 300).unwrap()
+}
+
+pub fn settle(
+    market: &mut Market,
+    escrow_key: &Pubkey,
+    buyer: &Trader,
+    seller: &Trader,
+    delivered: u32,
+    signers: &[Pubkey],
+) -> std::result::Result<(), TxError> {
+    let escrow: CommodityEscrow = market.svm.get(escrow_key);
+    let message = messages::commodity_settlement_message(escrow_key, &escrow, delivered);
+    let (mut ixs, signatures) = market.signed(&message, signers);
+    ixs.push(instructions::settle_commodity(
+        escrow_key,
+        &escrow,
+        &market.mint,
+        &seller.tokens,
+        &buyer.tokens,
+        delivered,
+        signatures,
+    ));
+    market.send(&ixs, &[])
+}
+
+pub fn refund(
+    market: &mut Market,
+    escrow_key: &Pubkey,
+    buyer: &Trader,
+) -> std::result::Result<(), TxError> {
+    let escrow: CommodityEscrow = market.svm.get(escrow_key);
+    let ix = instructions::refund_commodity(escrow_key, &escrow, &market.mint, &buyer.tokens);
+    market.send(&[ix], &[buyer.wallet])
+}
+
+#[test]
+fn partial_delivery_pays_pro_rata_and_refunds_rest() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    let escrow_key = lock(&mut market, &buyer, &seller);
+    let vault = pda::escrow_vault(&escrow_key);
+    assert_eq!(market.svm.balance(&vault), 5_025_000);
+    let locked = market.svm.events::<CommodityEscrowLocked>();
+    assert_eq!((locked[0].quantity, locked[0].fee), (50, 25_000));
+    assert_eq!((locked[0].mint, locked[0].sequence), (market.mint, 1));
+
+    assert_error(
+        settle(&mut market, &escrow_key, &buyer, &seller, 51, &oracles[..2]),
+        TradeEscrowError::InvalidQuantity,
+    );
+    assert_error(
+        settle(&mut market, &escrow_key, &buyer, &seller, 30, &oracles[..1]),
+        TradeEscrowError::InsufficientOracleSignatures,
+    );
+    assert_error(
+        settle(
+            &mut market,
+            &escrow_key,
+            &buyer,
+            &seller,
+            30,
+            &[buyer.wallet, seller.wallet],
+        ),
+        TradeEscrowError::InvalidOracleSignatures,
+    );
+
+    settle(&mut market, &escrow_key, &buyer, &seller, 30, &oracles[..2]).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 3_000_000);
+    assert_eq!(market.fee_vault_balance(), 15_000);
+    assert_eq!(market.svm.balance(&buyer.tokens), 10_000_000 - 3_015_000);
+    assert_eq!(market.svm.balance(&vault), 0);
+    assert_eq!(market.config().total_locked, 0);
+    let settled = market.svm.events::<CommodityEscrowSettled>();
+    assert_eq!(
+        (
+            settled[0].delivered,
+            settled[0].amount,
+            settled[0].protocol_fee,
+            settled[0].refunded
+        ),
+        (30, 3_000_000, 15_000, 2_010_000)
+    );
+    assert_eq!(settled[0].sequence, 2);
+
+    assert_error(
+        settle(&mut market, &escrow_key, &buyer, &seller, 30, &oracles[..2]),
+        TradeEscrowError::CannotSettle,
+    );
+    market.svm.warp(301);
+    assert_error(
+        refund(&mut market, &escrow_key, &buyer),
+        TradeEscrowError::CannotRefund,
+    );
+}
+
+#[test]
+fn partial_delivery_releases_undelivered_exposure() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let admin = market.admin;
+    market
+        .admin(instructions::update_limits(&admin, 0, 0, 6_000_000, 86_400))
+        .unwrap();
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    let escrow_key = lock(&mut market, &buyer, &seller);
+
+    settle(&mut market, &escrow_key, &buyer, &seller, 30, &oracles[..2]).unwrap();
+    assert_eq!(market.window_volume(&buyer.wallet), 3_015_000);
+
+    // Only the delivered 3_015_000 still counts, leaving 2_985_000 of the limit
+    market.svm.warp(1);
+    assert_error(
+        lock_with(
+            &mut market,
+            &buyer,
+            &seller,
+            30,
+            100_000,
+            &seller.wallet,
+            300,
+        ),
+        TradeEscrowError::UserLimitExceeded,
+    );
+    lock_with(
+        &mut market,
+        &buyer,
+        &seller,
+        29,
+        100_000,
+        &seller.wallet,
+        300,
+    )
+    .unwrap();
+    assert_eq!(market.config().total_locked, 2_914_500);
+}
+
+#[test]
+fn expired_commodity_escrow_refunds_buyer() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let admin = market.admin;
+    market
+        .admin(instructions::update_limits(
+            &admin, 0, 0, 10_000_000, 86_400,
+        ))
+        .unwrap();
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    let stranger = market.trader(0);
+    let escrow_key = lock(&mut market, &buyer, &seller);
+
+    assert_error(
+        refund(&mut market, &escrow_key, &buyer),
+        TradeEscrowError::CannotRefund,
+    );
+    market.svm.warp(301);
+    assert_error(
+        settle(&mut market, &escrow_key, &buyer, &seller, 50, &oracles[..2]),
+        TradeEscrowError::CannotSettle,
+    );
+
+    let escrow: CommodityEscrow = market.svm.get(&escrow_key);
+    let ix = instructions::refund_commodity(&escrow_key, &escrow, &market.mint, &stranger.tokens);
+    let ix = replace_account(ix, &buyer.wallet, stranger.wallet);
+    assert_error(
+        market.send(&[ix], &[stranger.wallet]),
+        TradeEscrowError::UnauthorizedRefund,
+    );
+
+    refund(&mut market, &escrow_key, &buyer).unwrap();
+    assert_eq!(market.svm.balance(&buyer.tokens), 10_000_000);
+    assert_eq!(market.window_volume(&buyer.wallet), 0);
+    assert_eq!(market.config().total_locked, 0);
+    let refunded = market.svm.events::<EscrowRefunded>();
+    assert_eq!(refunded[0].amount, 5_025_000);
+    assert_eq!(refunded[0].reason, RefundReason::DeadlineExpired);
+    assert_eq!(refunded[0].sequence, 3);
+    assert_error(
+        refund(&mut market, &escrow_key, &buyer),
+        TradeEscrowError::CannotRefund,
+    );
+}
+
+#[test]
+fn lock_commodity_validates_ask() {
+    let mut market = Market::new();
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+
+    assert_error(
+        lock_with(
+            &mut market,
+            &buyer,
+            &seller,
+            0,
+            100_000,
+            &seller.wallet,
+            300,
+        ),
+        TradeEscrowError::InvalidQuantity,
+    );
+    assert_error(
+        lock_with(
+            &mut market,
+            &buyer,
+            &seller,
+            2,
+            u64::MAX,
+            &seller.wallet,
+            300,
+        ),
+        TradeEscrowError::ArithmeticOverflow,
+    );
+    assert_error(
+        lock_with(
+            &mut market,
+            &buyer,
+            &seller,
+            50,
+            100_000,
+            &buyer.wallet,
+            300,
+        ),
+        TradeEscrowError::InvalidAskSignature,
+    );
+    assert_error(
+        lock_with(
+            &mut market,
+            &buyer,
+            &seller,
+            50,
+            100_000,
+            &seller.wallet,
+            601,
+        ),
+        TradeEscrowError::InvalidDeadline,
+    );
+
+    let accounts = LockCommodityAccounts {
+        buyer: buyer.wallet,
+        seller: seller.wallet,
+        mint: market.mint,
+        buyer_token_account: buyer.tokens,
+    };
+    let nonce = market.svm.now() as u64;
+    let message = messages::commodity_ask_message(
+        &revolution_case(),
+        50,
+        100_000,
+        &seller.wallet,
+        market.svm.now() + 300,
+        nonce,
+        None,
+    );
+    let (mut ixs, signatures) = market.signed(&message, &[seller.wallet]);
+    ixs.push(instructions::lock_commodity(
+        &accounts,
+        revolution_case(),
+        50,
+        100_000,
+        4_999_999,
+        signatures[0],
+        300,
+        None,
+        nonce,
+    ));
+    assert_error(
+        market.send(&ixs, &[buyer.wallet]),
+        TradeEscrowError::PriceExceedsMaximum,
+    );
+    // 100 cases cost the whole balance before the fee
+    assert_error(
+        lock_with(
+            &mut market,
+            &buyer,
+            &seller,
+            100,
+            100_000,
+            &seller.wallet,
+            300,
+        ),
+        TradeEscrowError::InsufficientFunds,
+    );
+
+    let guardian = market.guardian;
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+    assert_error(
+        lock_with(
+            &mut market,
+            &buyer,
+            &seller,
+            50,
+            100_000,
+            &seller.wallet,
+            300,
+        ),
+        TradeEscrowError::ContractPaused,
+    );
+}
+
+proptest! {
+    #[test]
+    fn partial_settlement_empties_vault(
+        fee_bps in 0u16..=1_000,
+        unit_price in 0u64..1_000_000_000,
+        quantity in 1u32..=1_000,
+        delivered_share in 0u32..=1_000,
+        payer in fee_payer(),
+    ) {
+        let config = Config {
+            fee_bps,
+            ..Default::default()
+        };
+        let escrow = locked_commodity(&config, unit_price, quantity, payer).unwrap();
+        let delivered = delivered_share.min(quantity);
+
+        let (seller_amount, protocol_fee, refund) = escrow.settlement_amounts(delivered).unwrap();
+        prop_assert_eq!(seller_amount + protocol_fee + refund, escrow.locked_total().unwrap());
+    }
+
+    #[test]
+    fn full_delivery_matches_single_escrow(
+        fee_bps in 0u16..=1_000,
+        unit_price in 0u64..1_000_000_000,
+        quantity in 1u32..=1_000,
+        payer in fee_payer(),
+    ) {
+        let config = Config {
+            fee_bps,
+            ..Default::default()
+        };
+        let escrow = locked_commodity(&config, unit_price, quantity, payer).unwrap();
+
+        let (_, protocol_fee, refund) = escrow.settlement_amounts(quantity).unwrap();
+        prop_assert_eq!(protocol_fee, escrow.fee_amount);
+        prop_assert_eq!(refund, 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,43 @@
+/// Variants no instruction can currently return
+const UNREACHABLE: &[&str] = &[];
+
+const SUITE: &[&str] = &[
+    include_str!("admin.rs"),
+    include_str!("auction.rs"),
+    include_str!("buy_order.rs"),
+    include_str!("commodity.rs"),
+    include_str!("escrow.rs"),
+    include_str!("fees.rs"),
+    include_str!("listing.rs"),
+    include_str!("pricing.rs"),
+    include_str!("receipts.rs"),
+    include_str!("swap.rs"),
+];
+
+#[test]
+fn every_error_variant_is_exercised() {
+    let errors = include_str!("../../src/errors.rs");
+    let variants: Vec<&str> = errors
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_suffix(','))
+        .filter(|name| name.chars().all(char::is_alphanumeric))
+        .collect();
+    assert!(variants.len() > 50, "parsed {} variants", variants.len());
+
+    let missing: Vec<&str> = variants
+        .into_iter()
+        .filter(|variant| !UNREACHABLE.contains(variant))
+        .filter(|variant| !SUITE.iter().any(|source| expects(source, variant)))
+        .collect();
+    assert!(missing.is_empty(), "no test expects {:?}", missing);
+}
+
+/// Whether `source` names the variant, and not just one it prefixes
+fn expects(source: &str, variant: &str) -> bool {
+    let pattern = format!("TradeEscrowError::{}", variant);
+    source.match_indices(&pattern).any(|(start, _)| {
+        let next = source[start + pattern.len()..].chars().next();
+        !matches!(next, Some(next) if next.is_alphanumeric())
+    })
+}
\ No newline at end of file
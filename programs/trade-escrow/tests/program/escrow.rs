@@ -0,0 +1,392 @@
+use anchor_lang::prelude::Pubkey;
+use proptest::prelude::*;
+use solana_sdk::transaction::TransactionError;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{Config, FeePayer};
+use trade_escrow::{EscrowLocked, EscrowSettled};
+use trade_escrow_client::instructions;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_error, Market};
+use crate::strategy::{base_fee, deposit, fee_payer, locked_escrow};
+use crate::svm::TxError;
+
+#[test]
+fn lock_then_settle_pays_seller_and_fee_vault() {
+    let mut market = Market::new();
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+
+    let escrow = market.lock(&buyer, &seller, 42, 1_000_000);
+    let vault = pda::escrow_vault(&escrow);
+    // 0.5% default fee, paid by the buyer on top of the price
+    assert_eq!(market.svm.balance(&vault), 1_005_000);
+    assert_eq!(market.svm.balance(&buyer.tokens), 8_995_000);
+    assert_eq!(market.config().total_locked, 1_005_000);
+    let locked = market.svm.events::<EscrowLocked>();
+    assert_eq!(locked.len(), 1);
+    assert_eq!(locked[0].escrow_id, escrow);
+    assert_eq!(locked[0].mint, market.mint);
+    assert_eq!(locked[0].fee, 5_000);
+    assert_eq!(locked[0].deadline, market.svm.now() + 300);
+
+    market.settle(&escrow, &seller).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 1_000_000);
+    assert_eq!(market.fee_vault_balance(), 5_000);
+    assert_eq!(market.fee_vault().total_collected, 5_000);
+    assert_eq!(market.svm.balance(&vault), 0);
+    assert_eq!(market.config().total_locked, 0);
+    assert!(market.escrow(&escrow).settled);
+}
+
+#[test]
+fn expired_escrow_refunds_buyer_once() {
+    let mut market = Market::new();
+    let buyer = market.trader(2_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 7, 1_000_000);
+
+    assert_error(
+        market.refund(&escrow, &buyer),
+        TradeEscrowError::CannotRefund,
+    );
+
+    market.svm.warp(300);
+    // The deadline itself is still within the delivery window
+    assert_error(
+        market.refund(&escrow, &buyer),
+        TradeEscrowError::CannotRefund,
+    );
+    market.svm.warp(1);
+    assert_error(
+        market.settle(&escrow, &seller),
+        TradeEscrowError::CannotSettle,
+    );
+
+    market.refund(&escrow, &buyer).unwrap();
+    assert_eq!(market.svm.balance(&buyer.tokens), 2_000_000);
+    assert_eq!(market.svm.balance(&pda::escrow_vault(&escrow)), 0);
+    assert_eq!(market.config().total_locked, 0);
+
+    assert_error(
+        market.refund(&escrow, &buyer),
+        TradeEscrowError::CannotRefund,
+    );
+    assert_eq!(market.svm.balance(&buyer.tokens), 2_000_000);
+}
+
+#[test]
+fn lock_rejects_invalid_asks() {
+    let mut market = Market::new();
+    let buyer = market.trader(1_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+
+    let mut args = market.lock_args(1, 500_000);
+    args.deadline_offset = 0;
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::InvalidDeadline,
+    );
+    args.deadline_offset = 601;
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::InvalidDeadline,
+    );
+
+    // An ask verified for anyone but the seller is not the seller's ask
+    let args = market.lock_args(1, 500_000);
+    assert_error(
+        market.lock_signed_by(&accounts, &args, &buyer.wallet),
+        TradeEscrowError::InvalidAskSignature,
+    );
+
+    let mut args = market.lock_args(1, 500_000);
+    args.price_max = 499_999;
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::PriceExceedsMaximum,
+    );
+
+    let mut referred = accounts.clone();
+    referred.referrer = Some(buyer.wallet);
+    let args = market.lock_args(1, 500_000);
+    assert_error(
+        market.lock_with(&referred, &args),
+        TradeEscrowError::InvalidReferrer,
+    );
+
+    // 1_000_000 plus the buyer's fee is more than the buyer holds
+    let args = market.lock_args(1, 1_000_000);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::InsufficientFunds,
+    );
+
+    // The price plus the buyer's fee does not fit in a u64
+    let args = market.lock_args(1, u64::MAX);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::ArithmeticOverflow,
+    );
+
+    // Nothing moved and nothing was counted
+    assert_eq!(market.svm.balance(&buyer.tokens), 1_000_000);
+    assert_eq!(market.config().total_locked, 0);
+}
+
+#[test]
+fn settle_requires_two_oracle_signatures() {
+    let mut market = Market::new();
+    let buyer = market.trader(2_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 3, 1_000_000);
+
+    let oracles = market.oracles;
+    let ixs = market.settle_ixs(&escrow, &seller, None, &oracles[..1]);
+    assert_error(
+        market.send(&ixs, &[]),
+        TradeEscrowError::InsufficientOracleSignatures,
+    );
+
+    // Verified signatures by anyone but the oracles do not count
+    let strangers = [buyer.wallet, seller.wallet, market.admin];
+    let ixs = market.settle_ixs(&escrow, &seller, None, &strangers);
+    assert_error(
+        market.send(&ixs, &[]),
+        TradeEscrowError::InvalidOracleSignatures,
+    );
+
+    // Nor do oracle signatures the transaction does not verify
+    let ixs = market.settle_ixs(&escrow, &seller, None, &oracles[..2]);
+    assert_error(
+        market.send(&ixs[2..], &[]),
+        TradeEscrowError::InvalidOracleSignatures,
+    );
+    let mut ixs = market.settle_ixs(&escrow, &seller, None, &oracles[..2]);
+    ixs[0].data[100] ^= 1;
+    assert_eq!(
+        market.send(&ixs, &[]),
+        Err(TxError(TransactionError::InvalidAccountIndex))
+    );
+
+    // Verify instructions must carry the signed data themselves
+    let mut ixs = market.settle_ixs(&escrow, &seller, None, &oracles[..2]);
+    for index_field in [4, 8, 14] {
+        ixs[0].data[index_field..index_field + 2].copy_from_slice(&0u16.to_le_bytes());
+    }
+    assert_error(
+        market.send(&ixs, &[]),
+        TradeEscrowError::InvalidSignatureFormat,
+    );
+    assert!(!market.escrow(&escrow).settled);
+}
+
+#[test]
+fn private_ask_locks_only_for_named_buyer() {
+    let mut market = Market::new();
+    let buyer = market.trader(1_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+
+    let mut args = market.lock_args(1, 500_000);
+    args.allowed_buyer = Some(seller.wallet);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::BuyerNotAllowed,
+    );
+
+    args.allowed_buyer = Some(buyer.wallet);
+    let escrow = market.lock_with(&accounts, &args).unwrap();
+    assert_eq!(market.escrow(&escrow).buyer, buyer.wallet);
+}
+
+#[test]
+fn settle_splits_fee_with_referrer() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    market
+        .admin(instructions::update_fees(
+            &admin,
+            100,
+            2_500,
+            FeePayer::Buyer,
+        ))
+        .unwrap();
+    let buyer = market.trader(2_000_000);
+    let seller = market.trader(0);
+    let referrer = market.trader(0);
+
+    // Buyers cannot refer themselves
+    let mut accounts = market.lock_accounts(&buyer, &seller);
+    accounts.referrer = Some(buyer.wallet);
+    assert_error(
+        market.lock_with(&accounts, &market.lock_args(9, 1_000_000)),
+        TradeEscrowError::InvalidReferrer,
+    );
+
+    accounts.referrer = Some(referrer.wallet);
+    let escrow = market
+        .lock_with(&accounts, &market.lock_args(9, 1_000_000))
+        .unwrap();
+    assert_eq!(market.escrow(&escrow).referrer, Some(referrer.wallet));
+
+    // The referrer's share is fixed at lock, whatever the admin sets later
+    market
+        .admin(instructions::update_fees(
+            &admin,
+            100,
+            10_000,
+            FeePayer::Buyer,
+        ))
+        .unwrap();
+
+    let oracles = market.oracles;
+    let ixs = market.settle_ixs(&escrow, &seller, None, &oracles[..2]);
+    assert_error(
+        market.send(&ixs, &[]),
+        TradeEscrowError::InvalidReferrerAccount,
+    );
+    let ixs = market.settle_ixs(&escrow, &seller, Some(seller.tokens), &oracles[..2]);
+    assert_error(
+        market.send(&ixs, &[]),
+        TradeEscrowError::InvalidReferrerAccount,
+    );
+
+    let ixs = market.settle_ixs(&escrow, &seller, Some(referrer.tokens), &oracles[..2]);
+    market.send(&ixs, &[]).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 1_000_000);
+    assert_eq!(market.svm.balance(&referrer.tokens), 2_500);
+    assert_eq!(market.fee_vault_balance(), 7_500);
+    let settled = &market.svm.events::<EscrowSettled>()[0];
+    assert_eq!(settled.referrer, Some(referrer.wallet));
+    assert_eq!((settled.protocol_fee, settled.referral_fee), (7_500, 2_500));
+}
+
+#[test]
+fn seller_paid_fee_comes_out_of_proceeds() {
+    let mut market = Market::new();
+    let buyer = market.trader(1_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let mut args = market.lock_args(5, 1_000_000);
+    args.fee_payer_override = Some(FeePayer::Seller);
+
+    let escrow = market.lock_with(&accounts, &args).unwrap();
+    assert_eq!(market.svm.balance(&buyer.tokens), 0);
+    assert_eq!(market.escrow(&escrow).fee_payer, FeePayer::Seller);
+
+    market.settle(&escrow, &seller).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 995_000);
+    assert_eq!(market.fee_vault_balance(), 5_000);
+}
+
+#[test]
+fn every_fee_payer_leaves_the_vault_empty() {
+    // 0.5% of this amount is an odd fee, so a split charges the seller the extra unit
+    let amount = 1_000_200;
+    for (fee_payer, buyer_fee, seller_fee) in [
+        (FeePayer::Buyer, 5_001, 0),
+        (FeePayer::Seller, 0, 5_001),
+        (FeePayer::Split, 2_500, 2_501),
+    ] {
+        let mut market = Market::new();
+        let buyer = market.trader(2 * (amount + buyer_fee));
+        let seller = market.trader(0);
+        let accounts = market.lock_accounts(&buyer, &seller);
+        let mut escrows = Vec::new();
+        for asset_id in [1, 2] {
+            let mut args = market.lock_args(asset_id, amount);
+            args.fee_payer_override = Some(fee_payer);
+            escrows.push(market.lock_with(&accounts, &args).unwrap());
+        }
+        let (settled, refunded) = (escrows[0], escrows[1]);
+        assert_eq!(market.svm.balance(&buyer.tokens), 0);
+        assert_eq!(
+            market.svm.balance(&pda::escrow_vault(&settled)),
+            amount + buyer_fee
+        );
+
+        market.settle(&settled, &seller).unwrap();
+        assert_eq!(market.svm.balance(&seller.tokens), amount - seller_fee);
+        assert_eq!(market.fee_vault_balance(), 5_001);
+        assert_eq!(market.svm.balance(&pda::escrow_vault(&settled)), 0);
+
+        market.svm.warp(301);
+        market.refund(&refunded, &buyer).unwrap();
+        assert_eq!(market.svm.balance(&buyer.tokens), amount + buyer_fee);
+        assert_eq!(market.svm.balance(&pda::escrow_vault(&refunded)), 0);
+        assert_eq!(market.config().total_locked, 0);
+    }
+}
+
+#[test]
+fn pause_blocks_trading_but_not_refunds() {
+    let mut market = Market::new();
+    let buyer = market.trader(3_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 11, 1_000_000);
+    let guardian = market.guardian;
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let args = market.lock_args(12, 1_000_000);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::ContractPaused,
+    );
+    assert_error(
+        market.settle(&escrow, &seller),
+        TradeEscrowError::ContractPaused,
+    );
+
+    market.svm.warp(301);
+    market.refund(&escrow, &buyer).unwrap();
+    assert_eq!(market.svm.balance(&buyer.tokens), 3_000_000);
+}
+
+proptest! {
+    #[test]
+    fn fee_shares_add_up(fee in any::<u64>(), payer in fee_payer()) {
+        let (buyer_fee, seller_fee) = payer.split(fee);
+        prop_assert_eq!(buyer_fee + seller_fee, fee);
+    }
+
+    #[test]
+    fn vault_is_empty_after_settle(
+        amount in 1..=u64::MAX / 2,
+        fee_bps in 0..=10000u16,
+        referral_share_bps in 0..=10000u16,
+        payer in fee_payer(),
+        has_referrer in any::<bool>(),
+    ) {
+        let config = Config {
+            fee_bps,
+            referral_share_bps,
+            ..Default::default()
+        };
+        let referrer = has_referrer.then(Pubkey::new_unique);
+        let escrow = locked_escrow(&config, amount, base_fee(&config, amount), payer, referrer);
+        let vault = deposit(&escrow);
+
+        let (protocol_fee, referral_fee) = escrow.split_fee().unwrap();
+        prop_assert_eq!(vault - escrow.seller_amount().unwrap() - protocol_fee - referral_fee, 0);
+    }
+
+    #[test]
+    fn vault_is_empty_after_refund(
+        amount in 1..=u64::MAX / 2,
+        fee_bps in 0..=10000u16,
+        payer in fee_payer(),
+    ) {
+        let config = Config {
+            fee_bps,
+            ..Default::default()
+        };
+        let escrow = locked_escrow(&config, amount, base_fee(&config, amount), payer, None);
+
+        prop_assert_eq!(deposit(&escrow) - escrow.locked_total().unwrap(), 0);
+    }
+}
\ No newline at end of file
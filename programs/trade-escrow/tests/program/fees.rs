@@ -0,0 +1,277 @@
+use anchor_lang::prelude::*;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{ConfigChange, FeePayer, PriceTier, VolumeTier, FEE_TIER_COUNT};
+use trade_escrow::{ConfigUpdated, EscrowLocked, FeesWithdrawn};
+use trade_escrow_client::instructions;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_anchor_error, assert_error, replace_account, Market};
+
+#[test]
+fn fee_vault_requires_admin() {
+    let mut market = Market::new();
+    let stranger = market.trader(0).wallet;
+    let mint = market.svm.create_mint(9);
+
+    let admin = market.admin;
+    let ix = replace_account(
+        instructions::initialize_fee_vault(&admin, &mint),
+        &admin,
+        stranger,
+    );
+    assert_error(
+        market.send(&[ix], &[stranger]),
+        TradeEscrowError::UnauthorizedAdmin,
+    );
+    assert!(!market.svm.exists(&pda::fee_vault(&mint)));
+    assert!(!market.svm.exists(&pda::receipt_tree(&mint)));
+
+    market
+        .admin(instructions::initialize_fee_vault(&admin, &mint))
+        .unwrap();
+    assert!(market.svm.exists(&pda::fee_vault(&mint)));
+    assert!(market.svm.exists(&pda::receipt_tree(&mint)));
+}
+
+#[test]
+fn withdraw_fees_sweeps_vault() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let fee_recipient = market.fee_recipient;
+    let recipient_tokens = market
+        .svm
+        .create_token_account(&fee_recipient, &market.mint, 0);
+    let stranger = market.trader(0);
+
+    assert_error(
+        market.send(
+            &[instructions::withdraw_fees(
+                &admin,
+                &market.mint,
+                &recipient_tokens,
+            )],
+            &[admin],
+        ),
+        TradeEscrowError::NoFeesToWithdraw,
+    );
+
+    let buyer = market.trader(2_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 1, 1_000_000);
+    market.settle(&escrow, &seller).unwrap();
+
+    market
+        .send(&[instructions::get_accrued_fees(&market.mint)], &[])
+        .unwrap();
+    assert_eq!(market.svm.return_data::<u64>(), Some(5_000));
+
+    // Only the admin may choose where fees go
+    assert_error(
+        market.send(
+            &[instructions::withdraw_fees(
+                &stranger.wallet,
+                &market.mint,
+                &stranger.tokens,
+            )],
+            &[stranger.wallet],
+        ),
+        TradeEscrowError::UnauthorizedFeeWithdrawal,
+    );
+
+    // ...but anyone may sweep to the configured recipient
+    market
+        .send(
+            &[instructions::withdraw_fees(
+                &stranger.wallet,
+                &market.mint,
+                &recipient_tokens,
+            )],
+            &[stranger.wallet],
+        )
+        .unwrap();
+    assert_eq!(market.svm.balance(&recipient_tokens), 5_000);
+    assert_eq!(market.fee_vault_balance(), 0);
+    assert_eq!(market.fee_vault().total_withdrawn, 5_000);
+    let withdrawn = market.svm.events::<FeesWithdrawn>();
+    assert_eq!(withdrawn[0].amount, 5_000);
+    assert_eq!(withdrawn[0].recipient, recipient_tokens);
+    assert_eq!(withdrawn[0].withdrawn_by, stranger.wallet);
+
+    assert_error(
+        market.send(
+            &[instructions::withdraw_fees(
+                &admin,
+                &market.mint,
+                &recipient_tokens,
+            )],
+            &[admin],
+        ),
+        TradeEscrowError::NoFeesToWithdraw,
+    );
+}
+
+#[test]
+fn withdraw_fees_leaves_donated_tokens() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let fee_recipient = market.fee_recipient;
+    let recipient_tokens = market
+        .svm
+        .create_token_account(&fee_recipient, &market.mint, 0);
+
+    let buyer = market.trader(2_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 1, 1_000_000);
+    market.settle(&escrow, &seller).unwrap();
+
+    // Tokens sent straight to the vault are not fees
+    let vault_tokens = pda::fee_vault_tokens(&market.mint);
+    market.svm.create_token_account_at(
+        vault_tokens,
+        &pda::fee_vault(&market.mint),
+        &market.mint,
+        5_000 + 70_000,
+    );
+
+    market
+        .send(&[instructions::get_accrued_fees(&market.mint)], &[])
+        .unwrap();
+    assert_eq!(market.svm.return_data::<u64>(), Some(5_000));
+
+    market
+        .admin(instructions::withdraw_fees(
+            &admin,
+            &market.mint,
+            &recipient_tokens,
+        ))
+        .unwrap();
+    assert_eq!(market.svm.balance(&recipient_tokens), 5_000);
+    assert_eq!(market.fee_vault_balance(), 70_000);
+    assert_eq!(market.fee_vault().total_collected, 5_000);
+    assert_eq!(market.fee_vault().total_withdrawn, 5_000);
+
+    assert_error(
+        market.send(
+            &[instructions::withdraw_fees(
+                &admin,
+                &market.mint,
+                &recipient_tokens,
+            )],
+            &[admin],
+        ),
+        TradeEscrowError::NoFeesToWithdraw,
+    );
+}
+
+#[test]
+fn accrued_fees_need_an_initialized_vault() {
+    let mut market = Market::new();
+    let mint = market.svm.create_mint(6);
+    assert_anchor_error(
+        market.send(&[instructions::get_accrued_fees(&mint)], &[]),
+        ErrorCode::AccountNotInitialized,
+    );
+}
+
+#[test]
+fn minimum_fee_applies_per_mint() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    market
+        .admin(instructions::update_min_fee(&admin, &market.mint, 20_000))
+        .unwrap();
+    assert_eq!(market.fee_vault().min_fee, 20_000);
+    assert_eq!(
+        market.svm.events::<ConfigUpdated>()[0].change,
+        ConfigChange::MinFee {
+            mint: market.mint,
+            old: 0,
+            new: 20_000,
+        }
+    );
+
+    let buyer = market.trader(2_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 1, 1_000_000);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].fee, 20_000);
+    assert_eq!(market.escrow(&escrow).fee_bps, 200);
+
+    // Never more than the trade itself
+    market.lock(&buyer, &seller, 2, 10_000);
+    assert_eq!(market.svm.events::<EscrowLocked>()[0].fee, 10_000);
+}
+
+#[test]
+fn fee_changes_do_not_affect_open_escrows() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    let buyer = market.trader(3_000_000);
+    let seller = market.trader(0);
+    let settled = market.lock(&buyer, &seller, 1, 1_000_000);
+    let refunded = market.lock(&buyer, &seller, 2, 1_000_000);
+
+    market
+        .admin(instructions::update_fees(&admin, 500, 0, FeePayer::Seller))
+        .unwrap();
+
+    market.settle(&settled, &seller).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 1_000_000);
+    assert_eq!(market.fee_vault_balance(), 5_000);
+    assert_eq!(market.svm.balance(&pda::escrow_vault(&settled)), 0);
+
+    market.svm.warp(301);
+    market.refund(&refunded, &buyer).unwrap();
+    assert_eq!(market.svm.balance(&buyer.tokens), 3_000_000 - 1_005_000);
+    assert_eq!(market.svm.balance(&pda::escrow_vault(&refunded)), 0);
+    assert_eq!(market.config().total_locked, 0);
+}
+
+#[test]
+fn fee_schedule_changes_do_not_affect_open_escrows() {
+    for (fee_payer, buyer_fee, seller_fee) in [
+        (FeePayer::Buyer, 5_000, 0),
+        (FeePayer::Seller, 0, 5_000),
+        (FeePayer::Split, 2_500, 2_500),
+    ] {
+        let mut market = Market::new();
+        let admin = market.admin;
+        let buyer = market.trader(2 * (1_000_000 + buyer_fee));
+        let seller = market.trader(0);
+        let accounts = market.lock_accounts(&buyer, &seller);
+        let mut escrows = Vec::new();
+        for asset_id in [1, 2] {
+            let mut args = market.lock_args(asset_id, 1_000_000);
+            args.fee_payer_override = Some(fee_payer);
+            escrows.push(market.lock_with(&accounts, &args).unwrap());
+        }
+        let (settled, refunded) = (escrows[0], escrows[1]);
+
+        // Raise both the flat fee and the tier covering these trades
+        let mut price_tiers = [PriceTier::default(); FEE_TIER_COUNT];
+        price_tiers[0] = PriceTier {
+            min_amount: 1,
+            fee_bps: 1_000,
+        };
+        market
+            .admin(instructions::update_fees(&admin, 500, 0, FeePayer::Split))
+            .unwrap();
+        market
+            .admin(instructions::update_fee_schedule(
+                &admin,
+                price_tiers,
+                [VolumeTier::default(); FEE_TIER_COUNT],
+            ))
+            .unwrap();
+
+        market.settle(&settled, &seller).unwrap();
+        assert_eq!(market.svm.balance(&seller.tokens), 1_000_000 - seller_fee);
+        assert_eq!(market.fee_vault_balance(), 5_000);
+        assert_eq!(market.svm.balance(&pda::escrow_vault(&settled)), 0);
+
+        market.svm.warp(301);
+        market.refund(&refunded, &buyer).unwrap();
+        assert_eq!(market.svm.balance(&buyer.tokens), 1_000_000 + buyer_fee);
+        assert_eq!(market.svm.balance(&pda::escrow_vault(&refunded)), 0);
+        assert_eq!(market.config().total_locked, 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,280 @@
+use anchor_lang::error::ERROR_CODE_OFFSET;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{Instruction, InstructionError};
+use solana_sdk::transaction::TransactionError;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{Config, Escrow, FeeVault, ItemClass, ReceiptTree, UserStats};
+use trade_escrow_client::instructions::{self, LockAccounts, LockArgs};
+use trade_escrow_client::messages::{self, AskPrice};
+use trade_escrow_client::pda;
+
+use crate::svm::{Svm, TxError};
+
+/// A participant with SOL for rent and a funded token account
+#[derive(Clone, Copy)]
+pub struct Trader {
+    pub wallet: Pubkey,
+    pub tokens: Pubkey,
+}
+
+/// Initialized program with one payment mint and its fee vault
+pub struct Market {
+    pub svm: Svm,
+    pub admin: Pubkey,
+    pub guardian: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub oracles: [Pubkey; 3],
+    pub mint: Pubkey,
+}
+
+impl Market {
+    pub fn new() -> Self {
+        let mut svm = Svm::new();
+        let admin = svm.wallet(10_000_000_000);
+        let guardian = svm.wallet(1_000_000_000);
+        let fee_recipient = Pubkey::new_unique();
+        let oracles = [svm.keypair(), svm.keypair(), svm.keypair()];
+
+        let mint = svm.create_mint(6);
+        let mut market = Self {
+            svm,
+            admin,
+            guardian,
+            fee_recipient,
+            oracles,
+            mint,
+        };
+        market
+            .send(
+                &[instructions::initialize(
+                    &admin,
+                    &guardian,
+                    &fee_recipient,
+                    oracles,
+                )],
+                &[admin],
+            )
+            .unwrap();
+        market
+            .send(
+                &[instructions::initialize_fee_vault(&admin, &mint)],
+                &[admin],
+            )
+            .unwrap();
+        market
+    }
+
+    pub fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<(), TxError> {
+        self.svm.send(instructions, signers)
+    }
+
+    /// Send as the admin
+    pub fn admin(&mut self, instruction: Instruction) -> std::result::Result<(), TxError> {
+        let admin = self.admin;
+        self.send(&[instruction], &[admin])
+    }
+
+    pub fn trader(&mut self, tokens: u64) -> Trader {
+        let wallet = self.svm.wallet(10_000_000_000);
+        let tokens = self.svm.create_token_account(&wallet, &self.mint, tokens);
+        Trader { wallet, tokens }
+    }
+
+    pub fn config(&self) -> Config {
+        self.svm.get(&pda::config())
+    }
+
+    pub fn receipt_tree(&self) -> ReceiptTree {
+        self.svm.get(&pda::receipt_tree(&self.mint))
+    }
+
+    pub fn fee_vault(&self) -> FeeVault {
+        self.svm.get(&pda::fee_vault(&self.mint))
+    }
+
+    pub fn fee_vault_balance(&self) -> u64 {
+        self.svm.balance(&pda::fee_vault_tokens(&self.mint))
+    }
+
+    /// Amount counted against `wallet`'s rolling user limit now
+    pub fn window_volume(&self, wallet: &Pubkey) -> u64 {
+        let stats: UserStats = self.svm.get(&pda::user_stats(wallet));
+        stats.current_window_volume(self.svm.now(), self.config().user_limit_window)
+    }
+
+    /// Ed25519 verify instructions for `message` signed by each of `signers`,
+    /// and the signatures in the same order
+    pub fn signed(&self, message: &str, signers: &[Pubkey]) -> (Vec<Instruction>, Vec<[u8; 64]>) {
+        signers
+            .iter()
+            .map(|signer| {
+                let signature = self.svm.sign_message(signer, message.as_bytes());
+                let verify =
+                    messages::ed25519_verify_instruction(signer, &signature, message.as_bytes());
+                (verify, signature)
+            })
+            .unzip()
+    }
+
+    pub fn lock_accounts(&self, buyer: &Trader, seller: &Trader) -> LockAccounts {
+        LockAccounts {
+            buyer: buyer.wallet,
+            seller: seller.wallet,
+            mint: self.mint,
+            buyer_token_account: buyer.tokens,
+            ..Default::default()
+        }
+    }
+
+    pub fn lock_args(&self, asset_id: u64, amount: u64) -> LockArgs {
+        LockArgs {
+            asset_id,
+            item_class: ItemClass::default(),
+            price: AskPrice::Tokens(amount),
+            price_max: amount,
+            // Signed by the seller when sent
+            ask_signature: [0; 64],
+            deadline_offset: 300,
+            fee_payer_override: None,
+            allowed_buyer: None,
+            price_override: false,
+        }
+    }
+
+    /// Lock with the given accounts and arguments, returning the escrow address
+    pub fn lock_with(
+        &mut self,
+        accounts: &LockAccounts,
+        args: &LockArgs,
+    ) -> std::result::Result<Pubkey, TxError> {
+        self.lock_signed_by(accounts, args, &accounts.seller)
+    }
+
+    /// Lock with the ask signed by `signer` instead of the seller
+    pub fn lock_signed_by(
+        &mut self,
+        accounts: &LockAccounts,
+        args: &LockArgs,
+        signer: &Pubkey,
+    ) -> std::result::Result<Pubkey, TxError> {
+        let nonce = self.svm.now() as u64;
+        let escrow = pda::escrow(&accounts.buyer, &accounts.seller, args.asset_id, nonce);
+        let message = messages::ask_message(
+            args.asset_id,
+            &args.item_class,
+            &accounts.seller,
+            &args.price,
+            self.svm.now() + args.deadline_offset,
+            nonce,
+            args.fee_payer_override,
+            args.allowed_buyer,
+        );
+        let (mut ixs, signatures) = self.signed(&message, &[*signer]);
+        let args = LockArgs {
+            ask_signature: signatures[0],
+            ..args.clone()
+        };
+        ixs.push(instructions::lock(accounts, &args, nonce));
+        self.send(&ixs, &[accounts.buyer])?;
+        Ok(escrow)
+    }
+
+    /// Lock a plain token-priced escrow
+    pub fn lock(&mut self, buyer: &Trader, seller: &Trader, asset_id: u64, amount: u64) -> Pubkey {
+        let accounts = self.lock_accounts(buyer, seller);
+        let args = self.lock_args(asset_id, amount);
+        self.lock_with(&accounts, &args).unwrap()
+    }
+
+    pub fn escrow(&self, escrow: &Pubkey) -> Escrow {
+        self.svm.get(escrow)
+    }
+
+    /// `settle` attested by `signers`, after their verify instructions
+    pub fn settle_ixs(
+        &self,
+        escrow_key: &Pubkey,
+        seller: &Trader,
+        referrer_token_account: Option<Pubkey>,
+        signers: &[Pubkey],
+    ) -> Vec<Instruction> {
+        let escrow = self.escrow(escrow_key);
+        let message = messages::settlement_message(escrow_key, &escrow);
+        let (mut ixs, signatures) = self.signed(&message, signers);
+        ixs.push(instructions::settle(
+            escrow_key,
+            &escrow,
+            &self.mint,
+            &seller.tokens,
+            referrer_token_account,
+            signatures,
+        ));
+        ixs
+    }
+
+    /// Settle with two oracle signatures; anyone may submit
+    pub fn settle(
+        &mut self,
+        escrow_key: &Pubkey,
+        seller: &Trader,
+    ) -> std::result::Result<(), TxError> {
+        let ixs = self.settle_ixs(escrow_key, seller, None, &self.oracles[..2]);
+        self.send(&ixs, &[])
+    }
+
+    pub fn refund(
+        &mut self,
+        escrow_key: &Pubkey,
+        buyer: &Trader,
+    ) -> std::result::Result<(), TxError> {
+        let escrow = self.escrow(escrow_key);
+        let instruction = instructions::refund(escrow_key, &escrow, &self.mint, &buyer.tokens);
+        self.send(&[instruction], &[buyer.wallet])
+    }
+}
+
+/// Swap one account of an instruction for another, keeping its privileges
+pub fn replace_account(mut instruction: Instruction, from: &Pubkey, to: Pubkey) -> Instruction {
+    for meta in instruction.accounts.iter_mut() {
+        if meta.pubkey == *from {
+            meta.pubkey = to;
+        }
+    }
+    instruction
+}
+
+/// Assert a transaction failed with a program error
+#[track_caller]
+pub fn assert_error(
+    result: std::result::Result<impl std::fmt::Debug, TxError>,
+    error: TradeEscrowError,
+) {
+    let expected = InstructionError::Custom(ERROR_CODE_OFFSET + error as u32);
+    match result {
+        Err(TxError(TransactionError::InstructionError(_, actual))) => {
+            assert_eq!(actual, expected, "expected {:?}", error)
+        }
+        Err(TxError(actual)) => panic!("expected {:?}, got {:?}", error, actual),
+        Ok(value) => panic!("expected {:?}, got Ok({:?})", error, value),
+    }
+}
+
+/// Assert a transaction failed with one of Anchor's own errors
+#[track_caller]
+pub fn assert_anchor_error(
+    result: std::result::Result<impl std::fmt::Debug, TxError>,
+    error: ErrorCode,
+) {
+    let expected = InstructionError::Custom(error as u32);
+    match result {
+        Err(TxError(TransactionError::InstructionError(_, actual))) => {
+            assert_eq!(actual, expected, "expected {:?}", error)
+        }
+        Err(TxError(actual)) => panic!("expected {:?}, got {:?}", error, actual),
+        Ok(value) => panic!("expected {:?}, got Ok({:?})", error, value),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,372 @@
+//! Random instruction sequences checked against vault accounting invariants.
+//!
+//! Each case replays a sequence of locks, settles, refunds, pauses, oracle
+//! and fee updates with random amounts and clock jumps, and after every step
+//! checks that:
+//! - each open escrow's vault holds exactly what it owes, and closed vaults are empty
+//! - `total_locked` equals the sum of open obligations
+//! - no escrow settles or refunds more than once
+//! - tokens are conserved across traders, vaults and the fee vault
+//!
+//! Token movements are predicted from the fee payer and referral share in
+//! force when each escrow locked, not read back from the escrow account, so
+//! a settlement that charges the wrong side or uses later fee settings shows
+//! up as a balance mismatch.
+//!
+//! Run longer with `PROPTEST_CASES=10000 cargo test --test program fuzz`.
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use proptest::prelude::*;
+use proptest::sample::Index;
+use trade_escrow::state::FeePayer;
+use trade_escrow_client::instructions;
+use trade_escrow_client::pda;
+
+use crate::fixture::{replace_account, Market, Trader};
+use crate::strategy::fee_payer;
+
+const TRADERS: usize = 3;
+const STARTING_BALANCE: u64 = 5_000_000;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Initialize,
+    Lock {
+        buyer: Index,
+        seller: Index,
+        amount: u64,
+        deadline_offset: i64,
+        fee_payer: Option<FeePayer>,
+        referred: bool,
+    },
+    Settle {
+        escrow: Index,
+        signatures: usize,
+    },
+    Refund {
+        escrow: Index,
+        by_buyer: bool,
+    },
+    Pause,
+    Unpause,
+    UpdateOracles,
+    UpdateFees {
+        fee_bps: u16,
+        referral_share_bps: u16,
+        fee_payer: FeePayer,
+    },
+    Warp(i64),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        1 => Just(Op::Initialize),
+        6 => (
+            any::<Index>(),
+            any::<Index>(),
+            prop_oneof![1..=2 * STARTING_BALANCE, any::<u64>()],
+            -10..=610i64,
+            proptest::option::of(fee_payer()),
+            any::<bool>(),
+        )
+            .prop_map(|(buyer, seller, amount, deadline_offset, fee_payer, referred)| Op::Lock {
+                buyer,
+                seller,
+                amount,
+                deadline_offset,
+                fee_payer,
+                referred,
+            }),
+        4 => (any::<Index>(), 0..=3usize).prop_map(|(escrow, signatures)| Op::Settle { escrow, signatures }),
+        3 => (any::<Index>(), any::<bool>()).prop_map(|(escrow, by_buyer)| Op::Refund { escrow, by_buyer }),
+        1 => Just(Op::Pause),
+        1 => Just(Op::Unpause),
+        1 => Just(Op::UpdateOracles),
+        2 => (0..=1_000u16, 0..=10_000u16, fee_payer()).prop_map(|(fee_bps, referral_share_bps, fee_payer)| {
+            Op::UpdateFees {
+                fee_bps,
+                referral_share_bps,
+                fee_payer,
+            }
+        }),
+        3 => (0..=700i64).prop_map(Op::Warp),
+    ]
+}
+
+struct Tracked {
+    key: Pubkey,
+    buyer: Trader,
+    seller: Trader,
+    open: bool,
+    /// What the vault holds while open: the amount plus the buyer's fee share
+    locked: u64,
+    /// The amount less the seller's fee share
+    seller_amount: u64,
+    protocol_fee: u64,
+    referral_fee: u64,
+}
+
+/// Market plus the balances every token account should hold
+struct Model {
+    market: Market,
+    traders: Vec<Trader>,
+    referrer: Trader,
+    stranger: Trader,
+    escrows: Vec<Tracked>,
+    expected: HashMap<Pubkey, u64>,
+    supply: u64,
+    next_asset: u64,
+}
+
+impl Model {
+    fn new() -> Self {
+        let mut market = Market::new();
+        let traders: Vec<Trader> = (0..TRADERS)
+            .map(|_| market.trader(STARTING_BALANCE))
+            .collect();
+        let referrer = market.trader(0);
+        let stranger = market.trader(0);
+
+        let mut expected = HashMap::new();
+        for trader in traders.iter().chain([&referrer, &stranger]) {
+            expected.insert(trader.tokens, market.svm.balance(&trader.tokens));
+        }
+        expected.insert(pda::fee_vault_tokens(&market.mint), 0);
+
+        Self {
+            market,
+            traders,
+            referrer,
+            stranger,
+            escrows: Vec::new(),
+            expected,
+            supply: STARTING_BALANCE * TRADERS as u64,
+            next_asset: 1,
+        }
+    }
+
+    fn credit(&mut self, account: Pubkey, amount: u64) {
+        *self.expected.entry(account).or_default() += amount;
+    }
+
+    fn debit(&mut self, account: Pubkey, amount: u64) {
+        *self.expected.entry(account).or_default() -= amount;
+    }
+
+    fn apply(&mut self, op: &Op) -> std::result::Result<(), TestCaseError> {
+        let admin = self.market.admin;
+        let guardian = self.market.guardian;
+        match op {
+            Op::Initialize => {
+                let config = self
+                    .market
+                    .svm
+                    .account(&pda::config())
+                    .unwrap()
+                    .data
+                    .clone();
+                let ix = instructions::initialize(&admin, &guardian, &admin, self.market.oracles);
+                prop_assert!(
+                    self.market.send(&[ix], &[admin]).is_err(),
+                    "config re-initialized"
+                );
+                prop_assert_eq!(
+                    &self.market.svm.account(&pda::config()).unwrap().data,
+                    &config
+                );
+            }
+            Op::Lock {
+                buyer,
+                seller,
+                amount,
+                deadline_offset,
+                fee_payer,
+                referred,
+            } => {
+                let buyer = self.traders[buyer.index(TRADERS)];
+                let seller = self.traders[seller.index(TRADERS)];
+                let mut accounts = self.market.lock_accounts(&buyer, &seller);
+                if *referred {
+                    accounts.referrer = Some(self.referrer.wallet);
+                }
+                let mut args = self.market.lock_args(self.next_asset, *amount);
+                args.deadline_offset = *deadline_offset;
+                args.fee_payer_override = *fee_payer;
+                self.next_asset += 1;
+                let config = self.market.config();
+
+                if let Ok(key) = self.market.lock_with(&accounts, &args) {
+                    let escrow = self.market.escrow(&key);
+                    prop_assert_eq!(escrow.amount, *amount);
+                    let fee = escrow.fee_amount;
+                    let (buyer_fee, seller_fee) = match fee_payer.unwrap_or(config.fee_payer) {
+                        FeePayer::Buyer => (fee, 0),
+                        FeePayer::Seller => (0, fee),
+                        FeePayer::Split => (fee / 2, fee - fee / 2),
+                    };
+                    let referral_fee = if *referred {
+                        (fee as u128 * config.referral_share_bps as u128 / 10_000) as u64
+                    } else {
+                        0
+                    };
+                    let locked = amount + buyer_fee;
+                    prop_assert_eq!(escrow.locked_total().unwrap(), locked);
+
+                    self.debit(buyer.tokens, locked);
+                    self.credit(pda::escrow_vault(&key), locked);
+                    self.escrows.push(Tracked {
+                        key,
+                        buyer,
+                        seller,
+                        open: true,
+                        locked,
+                        seller_amount: amount - seller_fee,
+                        protocol_fee: fee - referral_fee,
+                        referral_fee,
+                    });
+                }
+            }
+            Op::Settle { escrow, signatures } => {
+                if self.escrows.is_empty() {
+                    return Ok(());
+                }
+                let index = escrow.index(self.escrows.len());
+                let (key, seller) = (self.escrows[index].key, self.escrows[index].seller);
+                let referrer_tokens = self
+                    .market
+                    .escrow(&key)
+                    .referrer
+                    .map(|_| self.referrer.tokens);
+                let oracles = self.market.oracles;
+                let ixs =
+                    self.market
+                        .settle_ixs(&key, &seller, referrer_tokens, &oracles[..*signatures]);
+
+                if self.market.send(&ixs, &[]).is_ok() {
+                    prop_assert!(self.escrows[index].open, "escrow {} paid out twice", key);
+                    self.escrows[index].open = false;
+                    let tracked = &self.escrows[index];
+                    let (locked, seller_amount, protocol_fee, referral_fee) = (
+                        tracked.locked,
+                        tracked.seller_amount,
+                        tracked.protocol_fee,
+                        tracked.referral_fee,
+                    );
+                    self.debit(pda::escrow_vault(&key), locked);
+                    self.credit(seller.tokens, seller_amount);
+                    self.credit(pda::fee_vault_tokens(&self.market.mint), protocol_fee);
+                    self.credit(self.referrer.tokens, referral_fee);
+                }
+            }
+            Op::Refund { escrow, by_buyer } => {
+                if self.escrows.is_empty() {
+                    return Ok(());
+                }
+                let index = escrow.index(self.escrows.len());
+                let (key, buyer) = (self.escrows[index].key, self.escrows[index].buyer);
+                let escrow = self.market.escrow(&key);
+                let result = if *by_buyer {
+                    self.market.refund(&key, &buyer)
+                } else {
+                    let ix = instructions::refund(
+                        &key,
+                        &escrow,
+                        &self.market.mint,
+                        &self.stranger.tokens,
+                    );
+                    let ix = replace_account(ix, &buyer.wallet, self.stranger.wallet);
+                    self.market.send(&[ix], &[self.stranger.wallet])
+                };
+
+                if result.is_ok() {
+                    prop_assert!(*by_buyer, "stranger refunded {}", key);
+                    prop_assert!(self.escrows[index].open, "escrow {} paid out twice", key);
+                    self.escrows[index].open = false;
+                    let locked = self.escrows[index].locked;
+                    self.debit(pda::escrow_vault(&key), locked);
+                    self.credit(buyer.tokens, locked);
+                }
+            }
+            Op::Pause => {
+                self.market
+                    .send(&[instructions::pause(&guardian)], &[guardian])
+                    .unwrap();
+            }
+            Op::Unpause => {
+                self.market
+                    .send(&[instructions::unpause(&guardian)], &[guardian])
+                    .unwrap();
+            }
+            Op::UpdateOracles => {
+                let oracles = [
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                ];
+                self.market
+                    .admin(instructions::update_oracles(&admin, oracles))
+                    .unwrap();
+            }
+            Op::UpdateFees {
+                fee_bps,
+                referral_share_bps,
+                fee_payer,
+            } => {
+                self.market
+                    .admin(instructions::update_fees(
+                        &admin,
+                        *fee_bps,
+                        *referral_share_bps,
+                        *fee_payer,
+                    ))
+                    .unwrap();
+            }
+            Op::Warp(seconds) => self.market.svm.warp(*seconds),
+        }
+        Ok(())
+    }
+
+    fn check(&self) -> std::result::Result<(), TestCaseError> {
+        let svm = &self.market.svm;
+        let mut owed = 0;
+        for tracked in &self.escrows {
+            let escrow = self.market.escrow(&tracked.key);
+            let vault = svm.balance(&pda::escrow_vault(&tracked.key));
+            if tracked.open {
+                prop_assert!(!escrow.settled);
+                prop_assert_eq!(vault, tracked.locked, "vault of {} is off", tracked.key);
+                owed += tracked.locked;
+            } else {
+                prop_assert!(escrow.settled);
+                prop_assert_eq!(vault, 0, "vault of {} not emptied", tracked.key);
+            }
+        }
+        prop_assert_eq!(self.market.config().total_locked, owed);
+
+        for (account, expected) in &self.expected {
+            prop_assert_eq!(svm.balance(account), *expected, "balance of {}", account);
+        }
+        let total: u64 = self
+            .expected
+            .keys()
+            .map(|account| svm.balance(account))
+            .sum();
+        prop_assert_eq!(total, self.supply, "tokens created or destroyed");
+        Ok(())
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn random_sequences_keep_vaults_balanced(ops in proptest::collection::vec(op(), 1..48)) {
+        let mut model = Model::new();
+        for op in &ops {
+            model.apply(op)?;
+            model.check()?;
+        }
+    }
+}
\ No newline at end of file
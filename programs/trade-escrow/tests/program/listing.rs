@@ -0,0 +1,382 @@
+use anchor_lang::prelude::*;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{DutchCurve, DutchPricing, Listing, ListingStatus};
+use trade_escrow::{
+    ListingCancelled, ListingClosed, ListingCreated, ListingFilled, ListingPriceUpdated,
+};
+use trade_escrow_client::instructions;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_error, replace_account, Market, Trader};
+use crate::svm::TxError;
+
+fn create_listing(
+    market: &mut Market,
+    seller: &Trader,
+    price: u64,
+    allowed_buyer: Option<Pubkey>,
+) -> std::result::Result<Pubkey, TxError> {
+    let expiry = market.svm.now() + 3_600;
+    let ix = instructions::create_listing(
+        &seller.wallet,
+        &market.mint,
+        1,
+        42,
+        price,
+        expiry,
+        allowed_buyer,
+        None,
+    );
+    market.send(&[ix], &[seller.wallet])?;
+    Ok(pda::listing(&seller.wallet, 1))
+}
+
+fn buy(
+    market: &mut Market,
+    listing_key: &Pubkey,
+    buyer: &Trader,
+    price_max: u64,
+) -> std::result::Result<Pubkey, TxError> {
+    let listing: Listing = market.svm.get(listing_key);
+    let nonce = market.svm.now() as u64;
+    let ix = instructions::buy_listing(
+        listing_key,
+        &listing,
+        &buyer.wallet,
+        &buyer.tokens,
+        None,
+        price_max,
+        300,
+        nonce,
+    );
+    market.send(&[ix], &[buyer.wallet])?;
+    Ok(pda::escrow(
+        &buyer.wallet,
+        &listing.seller,
+        listing.asset_id,
+        nonce,
+    ))
+}
+
+#[test]
+fn buy_listing_opens_escrow_and_fills_listing() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let buyer = market.trader(2_000_000);
+    let listing_key = create_listing(&mut market, &seller, 1_000_000, None).unwrap();
+    let created = market.svm.events::<ListingCreated>();
+    assert_eq!(created[0].listing, listing_key);
+    assert_eq!(created[0].price, 1_000_000);
+
+    let escrow = buy(&mut market, &listing_key, &buyer, 1_000_000).unwrap();
+    let listing: Listing = market.svm.get(&listing_key);
+    assert_eq!(listing.status, ListingStatus::Filled);
+    let filled = market.svm.events::<ListingFilled>();
+    assert_eq!(filled[0].escrow_id, escrow);
+    assert_eq!(filled[0].price, 1_000_000);
+    assert_eq!(market.escrow(&escrow).asset_id, 42);
+    assert_eq!(market.svm.balance(&pda::escrow_vault(&escrow)), 1_005_000);
+
+    let other = market.trader(2_000_000);
+    assert_error(
+        buy(&mut market, &listing_key, &other, 1_000_000),
+        TradeEscrowError::ListingNotOpen,
+    );
+    let ix = instructions::cancel_listing(&listing_key, &listing);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::ListingNotOpen,
+    );
+
+    market.settle(&escrow, &seller).unwrap();
+    assert_eq!(market.svm.balance(&seller.tokens), 1_000_000);
+
+    // Only the seller reclaims the filled listing's rent
+    let ix = replace_account(
+        instructions::close_listing(&listing_key, &listing),
+        &seller.wallet,
+        other.wallet,
+    );
+    assert_error(
+        market.send(&[ix], &[other.wallet]),
+        TradeEscrowError::UnauthorizedSeller,
+    );
+    let rent = market.svm.lamports(&listing_key);
+    let before = market.svm.lamports(&seller.wallet);
+    market
+        .send(
+            &[instructions::close_listing(&listing_key, &listing)],
+            &[seller.wallet],
+        )
+        .unwrap();
+    assert!(!market.svm.exists(&listing_key));
+    assert_eq!(market.svm.lamports(&seller.wallet), before + rent);
+    assert_eq!(market.svm.events::<ListingClosed>()[0].listing, listing_key);
+}
+
+#[test]
+fn create_listing_validates_terms() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let now = market.svm.now();
+
+    let ix =
+        instructions::create_listing(&seller.wallet, &market.mint, 1, 42, 0, now + 60, None, None);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidListing,
+    );
+    let ix = instructions::create_listing(
+        &seller.wallet,
+        &market.mint,
+        1,
+        0,
+        100,
+        now + 60,
+        None,
+        None,
+    );
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidListing,
+    );
+    let ix =
+        instructions::create_listing(&seller.wallet, &market.mint, 1, 42, 100, now, None, None);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidListing,
+    );
+
+    let guardian = market.guardian;
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+    assert_error(
+        create_listing(&mut market, &seller, 100, None),
+        TradeEscrowError::ContractPaused,
+    );
+}
+
+#[test]
+fn seller_updates_and_cancels_listing() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let stranger = market.trader(0);
+    let buyer = market.trader(2_000_000);
+    let listing_key = create_listing(&mut market, &seller, 1_000_000, None).unwrap();
+    let listing: Listing = market.svm.get(&listing_key);
+
+    let ix = replace_account(
+        instructions::update_listing_price(&listing_key, &listing, 900_000),
+        &seller.wallet,
+        stranger.wallet,
+    );
+    assert_error(
+        market.send(&[ix], &[stranger.wallet]),
+        TradeEscrowError::UnauthorizedSeller,
+    );
+    let ix = instructions::update_listing_price(&listing_key, &listing, 0);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidListing,
+    );
+
+    let ix = instructions::update_listing_price(&listing_key, &listing, 1_200_000);
+    market.send(&[ix], &[seller.wallet]).unwrap();
+    let updated = market.svm.events::<ListingPriceUpdated>();
+    assert_eq!(
+        (updated[0].old_price, updated[0].new_price),
+        (1_000_000, 1_200_000)
+    );
+
+    // A buyer's price_max protects against an update racing the purchase
+    assert_error(
+        buy(&mut market, &listing_key, &buyer, 1_000_000),
+        TradeEscrowError::PriceExceedsMaximum,
+    );
+
+    // An open listing is cancelled, not closed
+    assert_error(
+        market.send(
+            &[instructions::close_listing(&listing_key, &listing)],
+            &[seller.wallet],
+        ),
+        TradeEscrowError::ListingStillOpen,
+    );
+
+    let ix = replace_account(
+        instructions::cancel_listing(&listing_key, &listing),
+        &seller.wallet,
+        stranger.wallet,
+    );
+    assert_error(
+        market.send(&[ix], &[stranger.wallet]),
+        TradeEscrowError::UnauthorizedSeller,
+    );
+    market
+        .send(
+            &[instructions::cancel_listing(&listing_key, &listing)],
+            &[seller.wallet],
+        )
+        .unwrap();
+    assert_eq!(
+        market.svm.events::<ListingCancelled>()[0].listing,
+        listing_key
+    );
+    assert!(!market.svm.exists(&listing_key));
+}
+
+#[test]
+fn buy_listing_enforces_listing_terms() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let buyer = market.trader(2_000_000);
+    let friend = market.trader(2_000_000);
+    let listing_key = create_listing(&mut market, &seller, 1_000_000, Some(friend.wallet)).unwrap();
+    let listing: Listing = market.svm.get(&listing_key);
+    let nonce = market.svm.now() as u64;
+
+    assert_error(
+        buy(&mut market, &listing_key, &buyer, 1_000_000),
+        TradeEscrowError::BuyerNotAllowed,
+    );
+
+    let ix = instructions::buy_listing(
+        &listing_key,
+        &listing,
+        &friend.wallet,
+        &friend.tokens,
+        None,
+        1_000_000,
+        0,
+        nonce,
+    );
+    assert_error(
+        market.send(&[ix], &[friend.wallet]),
+        TradeEscrowError::InvalidDeadline,
+    );
+
+    let ix = instructions::buy_listing(
+        &listing_key,
+        &listing,
+        &friend.wallet,
+        &friend.tokens,
+        Some(friend.wallet),
+        1_000_000,
+        300,
+        nonce,
+    );
+    assert_error(
+        market.send(&[ix], &[friend.wallet]),
+        TradeEscrowError::InvalidReferrer,
+    );
+
+    let other_mint = market.svm.create_mint(6);
+    let ix = instructions::buy_listing(
+        &listing_key,
+        &listing,
+        &friend.wallet,
+        &friend.tokens,
+        None,
+        1_000_000,
+        300,
+        nonce,
+    );
+    let ix = replace_account(ix, &market.mint, other_mint);
+    assert_error(
+        market.send(&[ix], &[friend.wallet]),
+        TradeEscrowError::InvalidListing,
+    );
+
+    market.svm.warp(3_601);
+    assert_error(
+        buy(&mut market, &listing_key, &friend, 1_000_000),
+        TradeEscrowError::ListingExpired,
+    );
+}
+
+#[test]
+fn buy_listing_requires_funds() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let buyer = market.trader(1_000_000);
+    let listing_key = create_listing(&mut market, &seller, 1_000_000, None).unwrap();
+
+    // The buyer pays the fee on top of the price
+    assert_error(
+        buy(&mut market, &listing_key, &buyer, 1_000_000),
+        TradeEscrowError::InsufficientFunds,
+    );
+    let listing: Listing = market.svm.get(&listing_key);
+    assert_eq!(listing.status, ListingStatus::Open);
+}
+
+#[test]
+fn dutch_listing_escrows_current_price() {
+    let mut market = Market::new();
+    let seller = market.trader(0);
+    let buyer = market.trader(2_000_000);
+    let now = market.svm.now();
+    let pricing = DutchPricing {
+        start_price: 1_000_000,
+        floor_price: 500_000,
+        start_time: now,
+        end_time: now + 1_000,
+        curve: DutchCurve::Linear,
+    };
+
+    let invalid = [
+        DutchPricing {
+            floor_price: 2_000_000,
+            ..pricing
+        },
+        // A span too long for an i64 would overflow when pricing
+        DutchPricing {
+            start_time: i64::MIN,
+            ..pricing
+        },
+    ];
+    for invalid in invalid {
+        let ix = instructions::create_dutch_listing(
+            &seller.wallet,
+            &market.mint,
+            1,
+            42,
+            invalid,
+            now + 3_600,
+            None,
+            None,
+        );
+        assert_error(
+            market.send(&[ix], &[seller.wallet]),
+            TradeEscrowError::InvalidListing,
+        );
+    }
+
+    let ix = instructions::create_dutch_listing(
+        &seller.wallet,
+        &market.mint,
+        1,
+        42,
+        pricing,
+        now + 3_600,
+        None,
+        None,
+    );
+    market.send(&[ix], &[seller.wallet]).unwrap();
+    let listing_key = pda::listing(&seller.wallet, 1);
+    let listing: Listing = market.svm.get(&listing_key);
+
+    // Dutch prices follow the curve, not the seller
+    let ix = instructions::update_listing_price(&listing_key, &listing, 900_000);
+    assert_error(
+        market.send(&[ix], &[seller.wallet]),
+        TradeEscrowError::InvalidListing,
+    );
+
+    market.svm.warp(500);
+    let escrow = buy(&mut market, &listing_key, &buyer, 800_000).unwrap();
+    assert_eq!(market.escrow(&escrow).amount, 750_000);
+    assert_eq!(market.svm.events::<ListingFilled>()[0].price, 750_000);
+    assert_eq!(market.svm.balance(&buyer.tokens), 2_000_000 - 753_750);
+}
\ No newline at end of file
@@ -0,0 +1,20 @@
+//! Tests of every instruction against a local bank, plus property tests of the
+//! fee and settlement arithmetic they rely on.
+
+mod admin;
+mod aggregator;
+mod auction;
+mod buy_order;
+mod commodity;
+mod coverage;
+mod escrow;
+mod fees;
+mod fixture;
+mod fuzz;
+mod listing;
+mod overflow;
+mod pricing;
+mod receipts;
+mod strategy;
+mod svm;
+mod swap;
\ No newline at end of file
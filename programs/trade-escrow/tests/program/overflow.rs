@@ -0,0 +1,321 @@
+//! Extreme amounts sent to every instruction that moves tokens.
+//!
+//! Fees, minimum fees and prices range over the whole `u64` domain. Each
+//! instruction must either succeed or fail with a program error; an overflow
+//! panic aborts the case. Tokens must be conserved across traders, vaults and
+//! the fee vault whichever way each instruction goes.
+
+use proptest::prelude::*;
+use trade_escrow::state::{
+    Auction, Config, FeeVault, ItemClass, PriceTier, UserStats, VolumeTier, FEE_TIER_COUNT,
+};
+use trade_escrow_client::instructions::{self, LockCommodityAccounts};
+use trade_escrow_client::messages;
+use trade_escrow_client::pda;
+
+use crate::fixture::Market;
+use crate::strategy::{edge_amount, fee_payer, locked_commodity, locked_escrow};
+
+const BALANCE: u64 = u64::MAX / 4;
+
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        0..=10_000_000u64,
+        BALANCE - 1_000..=BALANCE + 1_000,
+        u64::MAX - 1_000..=u64::MAX,
+        any::<u64>(),
+    ]
+}
+
+fn price_tiers() -> impl Strategy<Value = [PriceTier; FEE_TIER_COUNT]> {
+    proptest::array::uniform4((edge_amount(), 0..=10000u16)).prop_map(|tiers| {
+        tiers.map(|(min_amount, fee_bps)| PriceTier {
+            min_amount,
+            fee_bps,
+        })
+    })
+}
+
+fn volume_tiers() -> impl Strategy<Value = [VolumeTier; FEE_TIER_COUNT]> {
+    proptest::array::uniform4((edge_amount(), 0..=10000u16)).prop_map(|tiers| {
+        tiers.map(|(min_volume, discount_bps)| VolumeTier {
+            min_volume,
+            discount_bps,
+        })
+    })
+}
+
+fn case() -> ItemClass {
+    ItemClass {
+        appid: 730,
+        market_hash_name_hash: [3; 32],
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn extreme_amounts_fail_cleanly(
+        fee_bps in 0..=10_000u16,
+        referral_share_bps in 0..=10_000u16,
+        fee_payer in fee_payer(),
+        min_fee in amount(),
+        price in amount(),
+        second_price in amount(),
+        quantity in any::<u32>(),
+    ) {
+        let mut market = Market::new();
+        let admin = market.admin;
+        let mint = market.mint;
+        market
+            .admin(instructions::update_fees(&admin, fee_bps, referral_share_bps, fee_payer))
+            .unwrap();
+        market
+            .admin(instructions::update_min_fee(&admin, &mint, min_fee))
+            .unwrap();
+        let buyer = market.trader(BALANCE);
+        let seller = market.trader(0);
+        let bidder = market.trader(BALANCE);
+        let maker = market.trader(BALANCE);
+        let now = market.svm.now();
+        let nonce = now as u64;
+
+        // Plain escrow, settled straight away when it locks
+        let accounts = market.lock_accounts(&buyer, &seller);
+        if let Ok(escrow) = market.lock_with(&accounts, &market.lock_args(1, price)) {
+            market.settle(&escrow, &seller).unwrap();
+        }
+
+        // Commodity escrow, half delivered
+        let accounts = LockCommodityAccounts {
+            buyer: buyer.wallet,
+            seller: seller.wallet,
+            mint,
+            buyer_token_account: buyer.tokens,
+        };
+        let ask = messages::commodity_ask_message(
+            &case(),
+            quantity,
+            second_price,
+            &seller.wallet,
+            now + 300,
+            nonce,
+            None,
+        );
+        let (mut ixs, signatures) = market.signed(&ask, &[seller.wallet]);
+        ixs.push(instructions::lock_commodity(
+            &accounts,
+            case(),
+            quantity,
+            second_price,
+            u64::MAX,
+            signatures[0],
+            300,
+            None,
+            nonce,
+        ));
+        let commodity = pda::commodity_escrow(&buyer.wallet, &seller.wallet, nonce);
+        if market.send(&ixs, &[buyer.wallet]).is_ok() {
+            let escrow = market.svm.get(&commodity);
+            let delivered = quantity / 2;
+            let message = messages::commodity_settlement_message(&commodity, &escrow, delivered);
+            let (mut ixs, signatures) = market.signed(&message, &market.oracles[..2]);
+            ixs.push(instructions::settle_commodity(
+                &commodity,
+                &escrow,
+                &mint,
+                &seller.tokens,
+                &buyer.tokens,
+                delivered,
+                signatures,
+            ));
+            market.send(&ixs, &[]).unwrap();
+        }
+
+        // Buy order for as many items as asked
+        let ix = instructions::create_buy_order(
+            &buyer.wallet,
+            &mint,
+            &buyer.tokens,
+            1,
+            case(),
+            None,
+            second_price,
+            quantity,
+            now + 3_600,
+            None,
+        );
+        let _ = market.send(&[ix], &[buyer.wallet]);
+
+        // Swap with cash on both legs
+        let ix = instructions::propose_swap(
+            &maker.wallet,
+            &seller.wallet,
+            &mint,
+            &maker.tokens,
+            1,
+            vec![11],
+            vec![22],
+            price,
+            second_price,
+            now + 3_600,
+            300,
+        );
+        let _ = market.send(&[ix], &[maker.wallet]);
+
+        // Auction won at the bid, which becomes a trade escrow
+        let ix = instructions::create_auction(
+            &seller.wallet,
+            &mint,
+            1,
+            42,
+            1,
+            1,
+            now + 100,
+            60,
+            300,
+            None,
+        );
+        market.send(&[ix], &[seller.wallet]).unwrap();
+        let auction_key = pda::auction(&seller.wallet, 1);
+        let auction: Auction = market.svm.get(&auction_key);
+        let ix = instructions::place_bid(
+            &auction_key,
+            &auction,
+            &bidder.wallet,
+            &bidder.tokens,
+            None,
+            price,
+        );
+        let bid = market.send(&[ix], &[bidder.wallet]);
+        market.svm.warp(200);
+        let auction: Auction = market.svm.get(&auction_key);
+        let nonce = market.svm.now() as u64;
+        let ix = instructions::finalize_auction(&auction_key, &auction, &bidder.wallet, &admin, nonce);
+        let finalized = market.send(&[ix], &[admin]);
+        prop_assert!(bid.is_err() || finalized.is_ok(), "winning bid stuck in the auction");
+
+        let mut accounts = vec![
+            buyer.tokens,
+            seller.tokens,
+            bidder.tokens,
+            maker.tokens,
+            pda::fee_vault_tokens(&mint),
+            pda::escrow_vault(&commodity),
+            pda::buy_order_vault(&pda::buy_order(&buyer.wallet, 1)),
+            pda::swap_vault(&pda::swap(&maker.wallet, 1)),
+            pda::auction_vault(&auction_key),
+        ];
+        if finalized.is_ok() {
+            let escrow = pda::escrow(&bidder.wallet, &seller.wallet, 42, nonce);
+            accounts.push(pda::escrow_vault(&escrow));
+        }
+        let total: u64 = accounts
+            .iter()
+            .filter(|account| market.svm.exists(account))
+            .map(|account| market.svm.balance(account))
+            .sum();
+        prop_assert_eq!(total, 3 * BALANCE, "tokens created or destroyed");
+    }
+}
+
+proptest! {
+    #[test]
+    fn fee_never_exceeds_amount(
+        amount in edge_amount(),
+        fee_bps in 0..=10000u16,
+        price_tiers in price_tiers(),
+        volume_tiers in volume_tiers(),
+        min_fee in edge_amount(),
+        volume in edge_amount(),
+    ) {
+        let config = Config {
+            fee_bps,
+            price_tiers,
+            volume_tiers,
+            ..Default::default()
+        };
+        let fee_vault = FeeVault {
+            min_fee,
+            ..Default::default()
+        };
+        let mut user_stats = UserStats::default();
+        user_stats.record_volume(volume, 0);
+
+        let fee = config.calculate_fee(amount, &fee_vault, &user_stats, 0).unwrap();
+        prop_assert!(fee <= amount);
+    }
+
+    #[test]
+    fn referral_split_adds_up(fee in any::<u64>(), referral_share_bps in 0..=10000u16) {
+        let config = Config {
+            referral_share_bps,
+            ..Default::default()
+        };
+        let (protocol_fee, referral_fee) = config.split_fee(fee).unwrap();
+        prop_assert_eq!(protocol_fee as u128 + referral_fee as u128, fee as u128);
+    }
+
+    #[test]
+    fn escrow_pays_out_what_it_locked(
+        amount in edge_amount(),
+        fee_bps in 0..=10000u16,
+        min_fee in edge_amount(),
+        payer in fee_payer(),
+        referral_share_bps in 0..=10000u16,
+    ) {
+        let config = Config {
+            fee_bps,
+            referral_share_bps,
+            ..Default::default()
+        };
+        let fee_vault = FeeVault {
+            min_fee,
+            ..Default::default()
+        };
+        let fee = config
+            .calculate_fee(amount, &fee_vault, &UserStats::default(), 0)
+            .unwrap();
+        let escrow = locked_escrow(&config, amount, fee, payer, None);
+
+        // Overflow surfaces as an error, never as a wrapped or panicking sum
+        let (buyer_fee, _) = payer.split(fee);
+        let Ok(locked) = escrow.locked_total() else {
+            prop_assert!(amount.checked_add(buyer_fee).is_none());
+            return Ok(());
+        };
+
+        let (protocol_fee, referral_fee) = config.split_fee(fee).unwrap();
+        let paid_out = escrow.seller_amount().unwrap() as u128
+            + protocol_fee as u128
+            + referral_fee as u128;
+        prop_assert_eq!(paid_out, locked as u128);
+    }
+
+    #[test]
+    fn commodity_escrow_pays_out_what_it_locked(
+        fee_bps in 0..=10000u16,
+        unit_price in edge_amount(),
+        quantity in 1..=u32::MAX,
+        delivered_share in any::<u32>(),
+        payer in fee_payer(),
+    ) {
+        let config = Config {
+            fee_bps,
+            ..Default::default()
+        };
+        let Some(escrow) = locked_commodity(&config, unit_price, quantity, payer) else {
+            prop_assert!(unit_price.checked_mul(quantity as u64).is_none());
+            return Ok(());
+        };
+        let Ok(locked) = escrow.locked_total() else {
+            return Ok(());
+        };
+
+        let delivered = (delivered_share as u64 % (quantity as u64 + 1)) as u32;
+        let (seller_amount, protocol_fee, refund) = escrow.settlement_amounts(delivered).unwrap();
+        let paid_out = seller_amount as u128 + protocol_fee as u128 + refund as u128;
+        prop_assert_eq!(paid_out, locked as u128);
+    }
+}
\ No newline at end of file
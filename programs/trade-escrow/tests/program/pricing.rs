@@ -0,0 +1,413 @@
+use anchor_lang::prelude::*;
+use solana_sdk::account::Account;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{ItemClass, PriceFeed, PriceReference};
+use trade_escrow::{
+    EscrowLocked, PriceDeviationFlagged, PriceFeedPublished, ReferencePricePublished,
+};
+use trade_escrow_client::instructions;
+use trade_escrow_client::messages::AskPrice;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_anchor_error, assert_error, replace_account, Market};
+
+fn karambit() -> ItemClass {
+    ItemClass {
+        appid: 730,
+        market_hash_name_hash: [3; 32],
+    }
+}
+
+/// Hand price publishing to its own key with a 10% reference band, good for an hour
+fn with_publisher(market: &mut Market) -> Pubkey {
+    let admin = market.admin;
+    let publisher = market.trader(0).wallet;
+    market
+        .admin(instructions::update_price_checks(
+            &admin, publisher, 1_000, 60, 100, 3_600,
+        ))
+        .unwrap();
+    publisher
+}
+
+#[test]
+fn reference_price_guards_fat_fingered_asks() {
+    let mut market = Market::new();
+    let publisher = with_publisher(&mut market);
+    let mint = market.mint;
+    let stranger = market.trader(0).wallet;
+
+    let ix = replace_account(
+        instructions::publish_reference_price(&publisher, &mint, karambit(), 2_000_000_000),
+        &publisher,
+        stranger,
+    );
+    assert_error(
+        market.send(&[ix], &[stranger]),
+        TradeEscrowError::UnauthorizedPricePublisher,
+    );
+    let ix = instructions::publish_reference_price(&publisher, &mint, karambit(), 0);
+    assert_error(
+        market.send(&[ix], &[publisher]),
+        TradeEscrowError::InvalidReferencePrice,
+    );
+
+    let ix = instructions::publish_reference_price(&publisher, &mint, karambit(), 2_000_000_000);
+    market.send(&[ix], &[publisher]).unwrap();
+    let reference = pda::price_reference(&mint, &karambit());
+    let published = market.svm.events::<ReferencePricePublished>();
+    assert_eq!(
+        (published[0].price_reference, published[0].price),
+        (reference, 2_000_000_000)
+    );
+
+    let buyer = market.trader(3_000_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+
+    // $20 for a $2,000 knife
+    let mut args = market.lock_args(1, 20_000_000);
+    args.item_class = karambit();
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::PriceOutsideReferenceBand,
+    );
+
+    // Within the band passes silently
+    let mut inside = market.lock_args(2, 1_850_000_000);
+    inside.item_class = karambit();
+    market.lock_with(&accounts, &inside).unwrap();
+    assert!(market.svm.events::<PriceDeviationFlagged>().is_empty());
+
+    // An explicit override goes through but is flagged
+    args.price_override = true;
+    let escrow = market.lock_with(&accounts, &args).unwrap();
+    let flagged = market.svm.events::<PriceDeviationFlagged>();
+    assert_eq!(flagged[0].escrow_id, escrow);
+    assert_eq!(
+        (
+            flagged[0].reference_price,
+            flagged[0].amount,
+            flagged[0].band_bps
+        ),
+        (2_000_000_000, 20_000_000, 1_000)
+    );
+}
+
+#[test]
+fn reference_price_is_bound_to_the_signed_item_class() {
+    let mut market = Market::new();
+    let publisher = with_publisher(&mut market);
+    let mint = market.mint;
+    let other_mint = market.svm.create_mint(6);
+    let sticker = ItemClass {
+        appid: 730,
+        market_hash_name_hash: [4; 32],
+    };
+    let ix = instructions::publish_reference_price(&publisher, &mint, karambit(), 2_000_000_000);
+    market.send(&[ix], &[publisher]).unwrap();
+    for (mint, class) in [(mint, sticker), (other_mint, karambit())] {
+        let ix = instructions::publish_reference_price(&publisher, &mint, class, 20_000_000);
+        market.send(&[ix], &[publisher]).unwrap();
+    }
+
+    // A $20 karambit ask, checked against a reference the buyer picked
+    let buyer = market.trader(3_000_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let mut args = market.lock_args(1, 20_000_000);
+    args.item_class = karambit();
+    let reference = pda::price_reference(&mint, &karambit());
+    let substitutes = [
+        pda::price_reference(&mint, &sticker),
+        pda::price_reference(&other_mint, &karambit()),
+        // Omitted: an unpublished class's empty account
+        pda::price_reference(&mint, &ItemClass::default()),
+    ];
+    for substitute in substitutes {
+        let nonce = market.svm.now() as u64;
+        let ix = replace_account(
+            instructions::lock(&accounts, &args, nonce),
+            &reference,
+            substitute,
+        );
+        assert_anchor_error(
+            market.send(&[ix], &[buyer.wallet]),
+            ErrorCode::ConstraintSeeds,
+        );
+    }
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::PriceOutsideReferenceBand,
+    );
+
+    // Classes without a published reference trade unchecked
+    let unpriced = market.lock_args(2, 20_000_000);
+    market.lock_with(&accounts, &unpriced).unwrap();
+}
+
+#[test]
+fn stale_reference_price_blocks_locks() {
+    let mut market = Market::new();
+    let publisher = with_publisher(&mut market);
+    let mint = market.mint;
+    let ix = instructions::publish_reference_price(&publisher, &mint, karambit(), 2_000_000_000);
+    market.send(&[ix], &[publisher]).unwrap();
+
+    let buyer = market.trader(3_000_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let mut args = market.lock_args(1, 20_000_000);
+    args.item_class = karambit();
+    args.price_override = true;
+
+    // Even an overridden ask needs a current reference to be flagged against
+    market.svm.warp(3_601);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::StaleReferencePrice,
+    );
+
+    let ix = instructions::publish_reference_price(&publisher, &mint, karambit(), 2_000_000_000);
+    market.send(&[ix], &[publisher]).unwrap();
+    market.lock_with(&accounts, &args).unwrap();
+    assert_eq!(market.svm.events::<PriceDeviationFlagged>().len(), 1);
+}
+
+#[test]
+fn usd_ask_converts_at_feed_rate() {
+    let mut market = Market::new();
+    let publisher = with_publisher(&mut market);
+    let mint = market.mint;
+    let stranger = market.trader(0).wallet;
+
+    let ix = replace_account(
+        instructions::publish_price_feed(&publisher, &mint, 2_000_000, 1_000),
+        &publisher,
+        stranger,
+    );
+    assert_error(
+        market.send(&[ix], &[stranger]),
+        TradeEscrowError::UnauthorizedPricePublisher,
+    );
+    let ix = instructions::publish_price_feed(&publisher, &mint, 0, 0);
+    assert_error(
+        market.send(&[ix], &[publisher]),
+        TradeEscrowError::InvalidPriceFeed,
+    );
+
+    // One token is worth $2
+    let ix = instructions::publish_price_feed(&publisher, &mint, 2_000_000, 1_000);
+    market.send(&[ix], &[publisher]).unwrap();
+    assert_eq!(
+        market.svm.events::<PriceFeedPublished>()[0].price,
+        2_000_000
+    );
+    let feed: PriceFeed = market.svm.get(&pda::price_feed(&mint));
+    assert_eq!(feed.publish_time, market.svm.now());
+
+    let buyer = market.trader(100_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let mut args = market.lock_args(1, 0);
+    args.price = AskPrice::Usd(40_000_000);
+    args.price_max = 20_000_000;
+
+    let escrow = market.lock_with(&accounts, &args).unwrap();
+    let trade = market.escrow(&escrow);
+    assert_eq!(
+        (trade.amount, trade.usd_rate),
+        (20_000_000, Some(2_000_000))
+    );
+    assert_eq!(
+        market.svm.events::<EscrowLocked>()[0].usd_rate,
+        Some(2_000_000)
+    );
+
+    args.asset_id = 2;
+    args.price_max = 19_999_999;
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::PriceExceedsMaximum,
+    );
+
+    // Worthless tokens would need more than u64::MAX of them
+    let ix = instructions::publish_price_feed(&publisher, &mint, 1_000, 0);
+    market.send(&[ix], &[publisher]).unwrap();
+    args.price = AskPrice::Usd(u64::MAX);
+    args.price_max = u64::MAX;
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::InvalidPriceFeed,
+    );
+}
+
+#[test]
+fn usd_ask_rejects_unusable_feeds() {
+    let mut market = Market::new();
+    let publisher = with_publisher(&mut market);
+    let mint = market.mint;
+    let buyer = market.trader(100_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+    let mut args = market.lock_args(1, 0);
+    args.price = AskPrice::Usd(40_000_000);
+    args.price_max = u64::MAX;
+
+    // No feed passed at all
+    let nonce = market.svm.now() as u64;
+    let ix = instructions::lock(&accounts, &args, nonce);
+    let ix = replace_account(ix, &pda::price_feed(&mint), trade_escrow::ID);
+    assert_error(
+        market.send(&[ix], &[buyer.wallet]),
+        TradeEscrowError::InvalidPriceFeed,
+    );
+
+    // Confidence interval wider than 1% of the price
+    let ix = instructions::publish_price_feed(&publisher, &mint, 2_000_000, 20_001);
+    market.send(&[ix], &[publisher]).unwrap();
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::PriceFeedUncertain,
+    );
+
+    let ix = instructions::publish_price_feed(&publisher, &mint, 2_000_000, 20_000);
+    market.send(&[ix], &[publisher]).unwrap();
+    market.svm.warp(61);
+    assert_error(
+        market.lock_with(&accounts, &args),
+        TradeEscrowError::StalePriceFeed,
+    );
+
+    // A feed for another mint can't price this one
+    let other_mint = market.svm.create_mint(6);
+    let ix = instructions::publish_price_feed(&publisher, &other_mint, 1_000_000, 0);
+    market.send(&[ix], &[publisher]).unwrap();
+    let nonce = market.svm.now() as u64;
+    let ix = instructions::lock(&accounts, &args, nonce);
+    let ix = replace_account(ix, &pda::price_feed(&mint), pda::price_feed(&other_mint));
+    assert_error(
+        market.send(&[ix], &[buyer.wallet]),
+        TradeEscrowError::InvalidPriceFeed,
+    );
+}
+
+#[test]
+fn lock_checks_the_reference_account_in_place() {
+    let mut market = Market::new();
+    with_publisher(&mut market);
+    let mint = market.mint;
+
+    // A reference written straight into the account, as a publisher would leave it
+    let mut data = Vec::new();
+    PriceReference {
+        item_class: karambit(),
+        mint,
+        price: 2_000_000_000,
+        updated_at: market.svm.now(),
+        ..Default::default()
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    market.svm.set_account(
+        pda::price_reference(&mint, &karambit()),
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: trade_escrow::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let buyer = market.trader(3_000_000_000);
+    let seller = market.trader(0);
+    let accounts = market.lock_accounts(&buyer, &seller);
+
+    // 10% band around $2,000: $1,799 is out, $1,800 is in
+    let mut outside = market.lock_args(1, 1_799_000_000);
+    outside.item_class = karambit();
+    assert_error(
+        market.lock_with(&accounts, &outside),
+        TradeEscrowError::PriceOutsideReferenceBand,
+    );
+    let mut inside = market.lock_args(2, 1_800_000_000);
+    inside.item_class = karambit();
+    let escrow = market.lock_with(&accounts, &inside).unwrap();
+    assert_eq!(market.escrow(&escrow).amount, 1_800_000_000);
+    assert!(market.svm.events::<PriceDeviationFlagged>().is_empty());
+}
+
+fn feed(price: u64, confidence: u64, publish_time: i64) -> PriceFeed {
+    PriceFeed {
+        price,
+        confidence,
+        publish_time,
+        ..Default::default()
+    }
+}
+
+fn reference(price: u64) -> PriceReference {
+    PriceReference {
+        item_class: karambit(),
+        price,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn converts_usd_to_token_units() {
+    // USDC at $1.00, 6 decimals: $25.50 -> 25_500_000 units
+    assert_eq!(
+        feed(1_000_000, 0, 0).usd_to_tokens(25_500_000, 6),
+        Some(25_500_000)
+    );
+    // SOL at $150.00, 9 decimals: $300 -> 2 SOL
+    assert_eq!(
+        feed(150_000_000, 0, 0).usd_to_tokens(300_000_000, 9),
+        Some(2_000_000_000)
+    );
+    assert_eq!(feed(0, 0, 0).usd_to_tokens(1, 6), None);
+    assert_eq!(feed(1, 0, 0).usd_to_tokens(u64::MAX, 9), None);
+}
+
+#[test]
+fn rejects_stale_and_uncertain_prices() {
+    let price_feed = feed(150_000_000, 1_500_000, 1_000);
+    assert!(!price_feed.is_stale(1_060, 60));
+    assert!(price_feed.is_stale(1_061, 60));
+    assert!(price_feed.is_confident(100));
+    assert!(!price_feed.is_confident(99));
+}
+
+#[test]
+fn fat_fingered_ask_is_outside_band() {
+    // $2,000 knife listed at $20 with a 50% band
+    let reference = reference(2_000_000_000);
+    assert!(reference.deviates(20_000_000, 5_000));
+    assert!(reference.deviates(200_000_000_000, 5_000));
+}
+
+#[test]
+fn prices_within_band_pass() {
+    let reference = reference(2_000_000_000);
+    assert!(!reference.deviates(1_000_000_000, 5_000));
+    assert!(!reference.deviates(3_000_000_000, 5_000));
+    assert!(reference.deviates(999_999_999, 5_000));
+}
+
+#[test]
+fn reference_ages_out() {
+    let reference = PriceReference {
+        updated_at: 1_000,
+        ..reference(2_000_000_000)
+    };
+    assert!(!reference.is_stale(4_600, 3_600));
+    assert!(reference.is_stale(4_601, 3_600));
+}
+
+#[test]
+fn zero_band_disables_check() {
+    let reference = reference(2_000_000_000);
+    assert!(!reference.deviates(1, 0));
+}
\ No newline at end of file
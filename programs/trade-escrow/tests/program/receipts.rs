@@ -0,0 +1,270 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::AccountSerialize;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{
+    hash_receipt_nodes, Receipt, TradeOutcome, TradedItem, EMPTY_RECEIPT_LEAF,
+    EMPTY_RECEIPT_SUBTREES, RECEIPT_TREE_DEPTH,
+};
+use trade_escrow::ReceiptAppended;
+use trade_escrow_client::receipts::{prove_receipt, receipt_root, verify_receipt};
+use trade_escrow_client::{instructions, pda};
+
+use crate::fixture::{assert_anchor_error, assert_error, replace_account, Market};
+use crate::{commodity, swap};
+
+/// Receipt appended by the last transaction, checked to be at `index`
+fn appended_receipt(market: &Market, index: u64) -> Receipt {
+    let appended = market.svm.events::<ReceiptAppended>();
+    assert_eq!(appended.len(), 1);
+    assert_eq!(appended[0].mint, market.mint);
+    assert_eq!(appended[0].index, index);
+    assert_eq!(appended[0].root, market.receipt_tree().root);
+    appended[0].receipt.clone()
+}
+
+/// Every receipt proves against the program's root, and nowhere else
+#[track_caller]
+fn assert_provable(market: &Market, receipts: &[Receipt]) {
+    let tree = market.receipt_tree();
+    assert_eq!(tree.count, receipts.len() as u64);
+    assert_eq!(tree.root, receipt_root(receipts));
+    for (index, receipt) in receipts.iter().enumerate() {
+        let proof = prove_receipt(receipts, index as u64).unwrap();
+        assert!(verify_receipt(receipt, &proof, &tree.root));
+        let other = &receipts[(index + 1) % receipts.len()];
+        assert!(!verify_receipt(other, &proof, &tree.root));
+    }
+}
+
+#[test]
+fn empty_subtree_table_matches_node_hash() {
+    assert_eq!(EMPTY_RECEIPT_SUBTREES[0], EMPTY_RECEIPT_LEAF);
+    for height in 1..=RECEIPT_TREE_DEPTH {
+        let below = &EMPTY_RECEIPT_SUBTREES[height - 1];
+        assert_eq!(
+            EMPTY_RECEIPT_SUBTREES[height],
+            hash_receipt_nodes(below, below)
+        );
+    }
+}
+
+#[test]
+fn settle_and_refund_leave_provable_receipts() {
+    let mut market = Market::new();
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    assert_eq!(market.receipt_tree().count, 0);
+    assert_eq!(market.receipt_tree().root, receipt_root(&[]));
+
+    let settled = market.lock(&buyer, &seller, 1, 1_000_000);
+    let refunded = market.lock(&buyer, &seller, 2, 400_000);
+    market.settle(&settled, &seller).unwrap();
+    let appended = market.svm.events::<ReceiptAppended>();
+    assert_eq!(appended[0].index, 0);
+    assert_eq!(
+        appended[0].receipt,
+        Receipt {
+            escrow: settled,
+            buyer: buyer.wallet,
+            seller: seller.wallet,
+            item: TradedItem::Asset(1),
+            amount: 1_000_000,
+            outcome: TradeOutcome::Settled,
+            timestamp: market.svm.now(),
+        }
+    );
+    let mut receipts = vec![appended[0].receipt.clone()];
+
+    market.svm.warp(301);
+    market.refund(&refunded, &buyer).unwrap();
+    let appended = market.svm.events::<ReceiptAppended>();
+    assert_eq!(appended[0].index, 1);
+    assert_eq!(appended[0].receipt.escrow, refunded);
+    assert_eq!(appended[0].receipt.amount, 400_000);
+    assert_eq!(appended[0].receipt.outcome, TradeOutcome::Refunded);
+    receipts.push(appended[0].receipt.clone());
+
+    // The receipts in the events rebuild the root the program keeps
+    let tree = market.receipt_tree();
+    assert_eq!(tree.count, 2);
+    assert_eq!(tree.root, appended[0].root);
+    assert_eq!(tree.root, receipt_root(&receipts));
+    for (index, receipt) in receipts.iter().enumerate() {
+        let proof = prove_receipt(&receipts, index as u64).unwrap();
+        assert!(verify_receipt(receipt, &proof, &tree.root));
+    }
+
+    // A receipt cannot be altered, nor proven at another position
+    let proof = prove_receipt(&receipts, 0).unwrap();
+    let mut forged = receipts[0].clone();
+    forged.outcome = TradeOutcome::Refunded;
+    assert!(!verify_receipt(&forged, &proof, &tree.root));
+    assert!(!verify_receipt(&receipts[1], &proof, &tree.root));
+    assert!(prove_receipt(&receipts, 2).is_none());
+}
+
+#[test]
+fn commodity_outcomes_leave_provable_receipts() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let buyer = market.trader(20_000_000);
+    let seller = market.trader(0);
+    let delivered = commodity::lock(&mut market, &buyer, &seller);
+    market.svm.warp(1);
+    let expired = commodity::lock(&mut market, &buyer, &seller);
+
+    // 30 of the 50 cases arrived: the receipt covers those paid for
+    commodity::settle(&mut market, &delivered, &buyer, &seller, 30, &oracles[..2]).unwrap();
+    let settled = appended_receipt(&market, 0);
+    assert_eq!(
+        settled,
+        Receipt {
+            escrow: delivered,
+            buyer: buyer.wallet,
+            seller: seller.wallet,
+            item: TradedItem::Commodity {
+                class: commodity::revolution_case(),
+                quantity: 30,
+            },
+            amount: 3_000_000,
+            outcome: TradeOutcome::Settled,
+            timestamp: market.svm.now(),
+        }
+    );
+
+    market.svm.warp(301);
+    commodity::refund(&mut market, &expired, &buyer).unwrap();
+    let refunded = appended_receipt(&market, 1);
+    assert_eq!(refunded.escrow, expired);
+    assert_eq!(
+        refunded.item,
+        TradedItem::Commodity {
+            class: commodity::revolution_case(),
+            quantity: 50,
+        }
+    );
+    assert_eq!(refunded.amount, 5_000_000);
+    assert_eq!(refunded.outcome, TradeOutcome::Refunded);
+
+    assert_provable(&market, &[settled, refunded]);
+}
+
+#[test]
+fn swap_outcomes_leave_provable_receipts() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let taker = market.trader(0);
+    let mut receipts = vec![];
+    for (index, outcome) in [TradeOutcome::Settled, TradeOutcome::Refunded]
+        .into_iter()
+        .enumerate()
+    {
+        let maker = market.trader(40_000_000);
+        let swap_key = swap::propose(&mut market, &maker, &taker);
+        swap::accept(&mut market, &swap_key, &taker).unwrap();
+        match outcome {
+            TradeOutcome::Settled => {
+                swap::settle(&mut market, &swap_key, &maker, &taker, &oracles[..2])
+            }
+            TradeOutcome::Refunded => {
+                swap::refund(&mut market, &swap_key, &maker, &taker, &oracles[..2])
+            }
+        }
+        .unwrap();
+
+        // The taker is recorded as the buyer of the maker's items and cash
+        let receipt = appended_receipt(&market, index as u64);
+        assert_eq!(
+            receipt,
+            Receipt {
+                escrow: swap_key,
+                buyer: taker.wallet,
+                seller: maker.wallet,
+                item: TradedItem::Swap {
+                    maker_items: vec![11],
+                    taker_items: vec![22],
+                },
+                amount: 40_000_000,
+                outcome,
+                timestamp: market.svm.now(),
+            }
+        );
+        receipts.push(receipt);
+    }
+
+    assert_provable(&market, &receipts);
+}
+
+#[test]
+fn full_receipt_tree_rejects_receipts() {
+    let mut market = Market::new();
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 1, 1_000_000);
+
+    let key = pda::receipt_tree(&market.mint);
+    let mut tree = market.receipt_tree();
+    tree.count = 1 << RECEIPT_TREE_DEPTH;
+    let mut account = market.svm.account(&key).unwrap();
+    let mut data = Vec::new();
+    tree.try_serialize(&mut data).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    market.svm.set_account(key, account);
+
+    assert_error(
+        market.settle(&escrow, &seller),
+        TradeEscrowError::ReceiptTreeFull,
+    );
+}
+
+#[test]
+fn each_mint_keeps_its_own_receipt_tree() {
+    let mut market = Market::new();
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 1, 1_000_000);
+    market.settle(&escrow, &seller).unwrap();
+    let first_mint = market.mint;
+    let first = vec![appended_receipt(&market, 0)];
+    assert_eq!(market.receipt_tree().mint, first_mint);
+
+    // Trades in a second mint start a tree of their own at index 0
+    let admin = market.admin;
+    let second_mint = market.svm.create_mint(6);
+    market
+        .admin(instructions::initialize_fee_vault(&admin, &second_mint))
+        .unwrap();
+    market.mint = second_mint;
+    assert_eq!(market.receipt_tree().mint, second_mint);
+    assert_eq!(market.receipt_tree().root, receipt_root(&[]));
+
+    let buyer = market.trader(10_000_000);
+    let seller = market.trader(0);
+    let escrow = market.lock(&buyer, &seller, 1, 2_000_000);
+    market.settle(&escrow, &seller).unwrap();
+    let second = vec![appended_receipt(&market, 0)];
+    assert_eq!(market.receipt_tree().count, 1);
+    assert_eq!(market.receipt_tree().root, receipt_root(&second));
+
+    // The first mint's tree is untouched by the second mint's trades
+    market.mint = first_mint;
+    assert_eq!(market.receipt_tree().count, 1);
+    assert_eq!(market.receipt_tree().root, receipt_root(&first));
+
+    // A second-mint trade cannot append to the first mint's tree
+    market.mint = second_mint;
+    let oracles = market.oracles;
+    let escrow = market.lock(&buyer, &seller, 2, 1_000_000);
+    let ixs = market
+        .settle_ixs(&escrow, &seller, None, &oracles[..2])
+        .into_iter()
+        .map(|ix| {
+            replace_account(
+                ix,
+                &pda::receipt_tree(&second_mint),
+                pda::receipt_tree(&first_mint),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_anchor_error(market.send(&ixs, &[]), ErrorCode::ConstraintSeeds);
+}
\ No newline at end of file
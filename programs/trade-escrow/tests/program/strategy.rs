@@ -0,0 +1,71 @@
+//! Proptest strategies and escrow factories shared by the property tests.
+
+use anchor_lang::prelude::*;
+use proptest::prelude::*;
+use trade_escrow::state::{
+    effective_fee_bps, CommodityEscrow, Config, Escrow, FeePayer, FeeVault, UserStats,
+};
+
+pub fn fee_payer() -> impl Strategy<Value = FeePayer> {
+    prop_oneof![
+        Just(FeePayer::Buyer),
+        Just(FeePayer::Seller),
+        Just(FeePayer::Split),
+    ]
+}
+
+/// Amounts biased towards the edges of the `u64` range
+pub fn edge_amount() -> impl Strategy<Value = u64> {
+    prop_oneof![0..=1_000u64, u64::MAX - 1_000..=u64::MAX, any::<u64>()]
+}
+
+/// Fee `lock` charges on `amount` with no minimum fee or volume discount
+pub fn base_fee(config: &Config, amount: u64) -> u64 {
+    config
+        .calculate_fee(amount, &FeeVault::default(), &UserStats::default(), 0)
+        .unwrap()
+}
+
+/// Escrow as `lock` records it when charging `fee`
+pub fn locked_escrow(
+    config: &Config,
+    amount: u64,
+    fee: u64,
+    fee_payer: FeePayer,
+    referrer: Option<Pubkey>,
+) -> Escrow {
+    Escrow {
+        amount,
+        referrer,
+        fee_payer,
+        fee_amount: fee,
+        fee_bps: effective_fee_bps(amount, fee),
+        referral_share_bps: config.referral_share_bps,
+        ..Default::default()
+    }
+}
+
+/// Commodity escrow as `lock_commodity` records it; `None` if the price overflows
+pub fn locked_commodity(
+    config: &Config,
+    unit_price: u64,
+    quantity: u32,
+    fee_payer: FeePayer,
+) -> Option<CommodityEscrow> {
+    let mut escrow = CommodityEscrow {
+        unit_price,
+        quantity,
+        fee_payer,
+        ..Default::default()
+    };
+    let amount = escrow.amount().ok()?;
+    escrow.fee_amount = base_fee(config, amount);
+    escrow.fee_bps = effective_fee_bps(amount, escrow.fee_amount);
+    Some(escrow)
+}
+
+/// Amount `lock` transfers into the vault
+pub fn deposit(escrow: &Escrow) -> u64 {
+    let (buyer_fee, _) = escrow.fee_payer.split(escrow.fee_amount);
+    escrow.amount + buyer_fee
+}
\ No newline at end of file
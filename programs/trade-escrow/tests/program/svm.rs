@@ -0,0 +1,415 @@
+//! The program running on a `solana-program-test` bank.
+//!
+//! Transactions go through the real runtime: signatures, account locks,
+//! writable and signer privileges, rent and the SPL token program all behave
+//! as on a validator. Ed25519 verify instructions are checked before the
+//! transaction runs, as a validator's signature verification stage does.
+//! The program runs natively unless `SBF_OUT_DIR` points at a
+//! `cargo build-sbf` output holding `trade_escrow.so`, in which case the BPF
+//! build runs under the usual compute limits.
+//!
+//! The native runtime prints `sol_log_data` instead of logging it, so events
+//! are captured by wrapping its syscall stubs; under BPF they are read back
+//! from the `Program data:` log lines.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::{Discriminator, Event};
+use base64::Engine;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::feature_set::FeatureSet;
+use solana_sdk::message::Message;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Once;
+use tokio::runtime::Runtime;
+
+/// Failure of a transaction, as reported by the bank
+#[derive(Debug, PartialEq, Eq)]
+pub struct TxError(pub TransactionError);
+
+thread_local! {
+    /// Fields passed to `sol_log_data` by natively running programs
+    static LOGGED_DATA: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The native runtime's stubs, keeping the data the program logs
+struct EventStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for EventStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        LOGGED_DATA.with(|data| {
+            data.borrow_mut()
+                .extend(fields.iter().map(|field| field.to_vec()))
+        });
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+/// Anchor's entrypoint ties the account slice to the accounts' own lifetime
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    trade_escrow::entry(program_id, accounts, data)
+}
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "trade_escrow",
+        trade_escrow::ID,
+        processor!(process_instruction),
+    )
+}
+
+pub struct Svm {
+    context: ProgramTestContext,
+    runtime: Runtime,
+    clock: Clock,
+    keypairs: HashMap<Pubkey, Keypair>,
+    /// Every address the suite has touched, for `accounts_owned_by`
+    known: BTreeSet<Pubkey>,
+    processed: HashSet<Signature>,
+    events: Vec<Vec<u8>>,
+    return_data: Option<Vec<u8>>,
+}
+
+impl Default for Svm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Svm {
+    pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // The first bank installs the runtime's stubs; wrap them before any
+        // other test can run the program
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            // Every bank logs each instruction at debug level otherwise
+            if std::env::var_os("RUST_LOG").is_none() {
+                std::env::set_var("RUST_LOG", "error");
+            }
+            drop(runtime.block_on(program_test().start_with_context()));
+            let stubs = set_syscall_stubs(Box::new(NoStubs));
+            set_syscall_stubs(Box::new(EventStubs(stubs)));
+        });
+
+        let context = runtime.block_on(program_test().start_with_context());
+        let mut clock: Clock = runtime
+            .block_on(context.banks_client.clone().get_sysvar())
+            .unwrap();
+        clock.unix_timestamp = 1_700_000_000;
+        context.set_sysvar(&clock);
+
+        Self {
+            context,
+            runtime,
+            clock,
+            keypairs: HashMap::new(),
+            known: BTreeSet::new(),
+            processed: HashSet::new(),
+            events: vec![],
+            return_data: None,
+        }
+    }
+
+    pub fn now(&self) -> i64 {
+        self.clock.unix_timestamp
+    }
+
+    /// Move the cluster clock forward, onto a new slot
+    pub fn warp(&mut self, seconds: i64) {
+        self.next_slot();
+        self.clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&self.clock);
+    }
+
+    fn next_slot(&mut self) {
+        self.clock.slot += 1;
+        self.context.warp_to_slot(self.clock.slot).unwrap();
+        self.context.set_sysvar(&self.clock);
+    }
+
+    /// A new wallet the suite can sign for, funded with SOL for rent
+    pub fn wallet(&mut self, lamports: u64) -> Pubkey {
+        let keypair = Keypair::new();
+        let wallet = keypair.pubkey();
+        self.keypairs.insert(wallet, keypair);
+        self.airdrop(&wallet, lamports);
+        wallet
+    }
+
+    /// A new keypair the suite can sign messages with, holding no SOL
+    pub fn keypair(&mut self) -> Pubkey {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        self.keypairs.insert(pubkey, keypair);
+        pubkey
+    }
+
+    /// Ed25519 signature by a wallet or keypair of the suite over `message`
+    pub fn sign_message(&self, signer: &Pubkey, message: &[u8]) -> [u8; 64] {
+        let signature = self.keypairs[signer].sign_message(message);
+        signature.as_ref().try_into().unwrap()
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<Account> {
+        let mut client = self.context.banks_client.clone();
+        self.runtime
+            .block_on(client.get_account_with_commitment(*key, CommitmentLevel::Processed))
+            .unwrap()
+    }
+
+    /// Every account owned by `owner` among those the suite has touched
+    pub fn accounts_owned_by(&self, owner: &Pubkey) -> Vec<(Pubkey, Account)> {
+        self.known
+            .iter()
+            .filter_map(|key| Some((*key, self.account(key)?)))
+            .filter(|(_, account)| account.owner == *owner)
+            .collect()
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.known.insert(key);
+        self.context
+            .set_account(&key, &AccountSharedData::from(account));
+    }
+
+    /// Fund an account with SOL
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        let mut account = self.account(key).unwrap_or_default();
+        account.lamports += lamports;
+        self.set_account(*key, account);
+    }
+
+    /// Decode a program account, checking its discriminator
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.account(key).expect("account exists");
+        T::try_deserialize(&mut &account.data[..]).unwrap()
+    }
+
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.account(key)
+            .is_some_and(|account| account.lamports > 0)
+    }
+
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: Some(Pubkey::new_unique()).into(),
+            supply: u64::MAX / 2,
+            decimals,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        }
+        .pack_into_slice(&mut data);
+        self.set_token_owned(mint, data);
+        mint
+    }
+
+    pub fn create_token_account(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let account = Pubkey::new_unique();
+        self.create_token_account_at(account, owner, mint, amount);
+        account
+    }
+
+    /// Create a token account at a fixed address, such as an associated token account
+    pub fn create_token_account_at(
+        &mut self,
+        account: Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        self.set_token_owned(account, data);
+    }
+
+    fn set_token_owned(&mut self, key: Pubkey, data: Vec<u8>) {
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.set_account(
+            key,
+            Account {
+                lamports,
+                data,
+                owner: spl_token::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    /// SOL balance of an account; 0 if it does not exist
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.account(key).map_or(0, |account| account.lamports)
+    }
+
+    /// Token balance of an SPL token account; 0 if it does not exist
+    pub fn balance(&self, key: &Pubkey) -> u64 {
+        match self.account(key) {
+            Some(account) if account.owner == spl_token::ID => {
+                spl_token::state::Account::unpack(&account.data)
+                    .unwrap()
+                    .amount
+            }
+            _ => 0,
+        }
+    }
+
+    /// Events emitted by the last transaction that succeeded
+    pub fn events<T: Event + Discriminator>(&self) -> Vec<T> {
+        self.events
+            .iter()
+            .filter(|data| data.starts_with(&T::DISCRIMINATOR))
+            .map(|data| T::try_from_slice(&data[8..]).unwrap())
+            .collect()
+    }
+
+    /// Value returned with `set_return_data` by the last transaction that succeeded
+    pub fn return_data<T: AnchorDeserialize>(&self) -> Option<T> {
+        // The bank strips trailing zero bytes
+        let mut data = self.return_data.clone()?;
+        data.resize(1024, 0);
+        T::deserialize(&mut &data[..]).ok()
+    }
+
+    /// Execute instructions atomically, with `signers` having signed
+    pub fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<(), TxError> {
+        for meta in instructions.iter().flat_map(|ix| ix.accounts.iter()) {
+            self.known.insert(meta.pubkey);
+            if meta.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(TxError(TransactionError::SignatureFailure));
+            }
+        }
+
+        let mut client = self.context.banks_client.clone();
+        let mut transaction = self.sign(instructions, signers);
+        // Identical transactions need a fresh blockhash to be processed again
+        if self.processed.contains(&transaction.signatures[0]) {
+            self.next_slot();
+            transaction = self.sign(instructions, signers);
+        }
+        self.processed.insert(transaction.signatures[0]);
+        transaction
+            .verify_precompiles(&FeatureSet::all_enabled())
+            .map_err(TxError)?;
+
+        LOGGED_DATA.with(|data| data.borrow_mut().clear());
+        let outcome = self
+            .runtime
+            .block_on(client.process_transaction_with_metadata(transaction))
+            .unwrap();
+        let logged = LOGGED_DATA.with(|data| std::mem::take(&mut *data.borrow_mut()));
+        outcome.result.map_err(TxError)?;
+
+        let metadata = outcome.metadata.unwrap();
+        self.events = logged;
+        self.events.extend(
+            metadata
+                .log_messages
+                .iter()
+                .filter_map(|line| line.strip_prefix("Program data: "))
+                .flat_map(|fields| fields.split(' '))
+                .map(|field| {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(field)
+                        .unwrap()
+                }),
+        );
+        self.return_data = metadata.return_data.map(|data| data.data);
+        Ok(())
+    }
+
+    fn sign(&self, instructions: &[Instruction], signers: &[Pubkey]) -> Transaction {
+        let mut client = self.context.banks_client.clone();
+        let blockhash = self
+            .runtime
+            .block_on(client.get_latest_blockhash())
+            .unwrap();
+        let payer = &self.context.payer;
+        let message = Message::new(instructions, Some(&payer.pubkey()));
+        let keypairs: Vec<&Keypair> = message.account_keys
+            [..message.header.num_required_signatures as usize]
+            .iter()
+            .map(|key| self.keypairs.get(key).unwrap_or(payer))
+            .collect();
+        debug_assert!(keypairs[1..]
+            .iter()
+            .all(|keypair| signers.contains(&keypair.pubkey())));
+        Transaction::new(&keypairs, message, blockhash)
+    }
+}
+
+/// Placeholder while the runtime's stubs are swapped out
+struct NoStubs;
+
+impl SyscallStubs for NoStubs {}
\ No newline at end of file
@@ -0,0 +1,448 @@
+use anchor_lang::prelude::*;
+use trade_escrow::errors::TradeEscrowError;
+use trade_escrow::state::{RefundReason, SwapEscrow};
+use trade_escrow::{SwapAccepted, SwapCancelled, SwapProposed, SwapRefunded, SwapSettled};
+use trade_escrow_client::instructions;
+use trade_escrow_client::messages;
+use trade_escrow_client::pda;
+
+use crate::fixture::{assert_anchor_error, assert_error, replace_account, Market, Trader};
+use crate::svm::TxError;
+
+/// "My knife + 40 for your gloves", open for an hour with a 300s delivery window
+pub fn propose(market: &mut Market, maker: &Trader, taker: &Trader) -> Pubkey {
+    let expiry = market.svm.now() + 3_600;
+    let ix = instructions::propose_swap(
+        &maker.wallet,
+        &taker.wallet,
+        &market.mint,
+        &maker.tokens,
+        1,
+        vec![11],
+        vec![22],
+        40_000_000,
+        0,
+        expiry,
+        300,
+    );
+    market.send(&[ix], &[maker.wallet]).unwrap();
+    pda::swap(&maker.wallet, 1)
+}
+
+pub fn accept(
+    market: &mut Market,
+    swap_key: &Pubkey,
+    taker: &Trader,
+) -> std::result::Result<(), TxError> {
+    let swap: SwapEscrow = market.svm.get(swap_key);
+    let ix = instructions::accept_swap(swap_key, &swap, &taker.tokens);
+    market.send(&[ix], &[taker.wallet])
+}
+
+pub fn settle(
+    market: &mut Market,
+    swap_key: &Pubkey,
+    maker: &Trader,
+    taker: &Trader,
+    signers: &[Pubkey],
+) -> std::result::Result<(), TxError> {
+    let swap: SwapEscrow = market.svm.get(swap_key);
+    let message = messages::swap_settlement_message(swap_key, &swap);
+    let (mut ixs, signatures) = market.signed(&message, signers);
+    ixs.push(instructions::settle_swap(
+        swap_key,
+        &swap,
+        &maker.tokens,
+        &taker.tokens,
+        signatures,
+    ));
+    market.send(&ixs, &[])
+}
+
+pub fn refund(
+    market: &mut Market,
+    swap_key: &Pubkey,
+    maker: &Trader,
+    taker: &Trader,
+    signers: &[Pubkey],
+) -> std::result::Result<(), TxError> {
+    let swap: SwapEscrow = market.svm.get(swap_key);
+    let message = messages::swap_failure_message(swap_key, &swap);
+    let (mut ixs, signatures) = market.signed(&message, signers);
+    ixs.push(instructions::refund_swap(
+        swap_key,
+        &swap,
+        &maker.tokens,
+        &taker.tokens,
+        signatures,
+    ));
+    market.send(&ixs, &[])
+}
+
+#[test]
+fn swap_settles_cash_leg_to_taker() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let maker = market.trader(50_000_000);
+    let taker = market.trader(0);
+    let swap_key = propose(&mut market, &maker, &taker);
+    let vault = pda::swap_vault(&swap_key);
+    assert_eq!(market.svm.balance(&vault), 40_000_000);
+    assert_eq!(market.config().total_locked, 40_000_000);
+    assert_eq!(market.svm.events::<SwapProposed>()[0].maker_items, vec![11]);
+
+    assert_error(
+        settle(&mut market, &swap_key, &maker, &taker, &oracles[..2]),
+        TradeEscrowError::InvalidSwapState,
+    );
+    accept(&mut market, &swap_key, &taker).unwrap();
+    assert_eq!(
+        market.svm.events::<SwapAccepted>()[0].deadline,
+        market.svm.now() + 300
+    );
+
+    assert_error(
+        settle(&mut market, &swap_key, &maker, &taker, &oracles[..1]),
+        TradeEscrowError::InsufficientOracleSignatures,
+    );
+    assert_error(
+        settle(
+            &mut market,
+            &swap_key,
+            &maker,
+            &taker,
+            &[maker.wallet, taker.wallet],
+        ),
+        TradeEscrowError::InvalidOracleSignatures,
+    );
+    let swap: SwapEscrow = market.svm.get(&swap_key);
+    let maker_lamports = market.svm.lamports(&maker.wallet);
+    let rent = market.svm.lamports(&swap_key) + market.svm.lamports(&vault);
+    settle(&mut market, &swap_key, &maker, &taker, &oracles[..2]).unwrap();
+    assert_eq!(market.svm.balance(&taker.tokens), 40_000_000);
+    assert_eq!(market.config().total_locked, 0);
+    assert_eq!(market.svm.events::<SwapSettled>()[0].oracle_count, 2);
+
+    // The swap and its vault are closed, their rent going back to the maker
+    assert!(!market.svm.exists(&swap_key));
+    assert!(!market.svm.exists(&vault));
+    assert_eq!(market.svm.lamports(&maker.wallet), maker_lamports + rent);
+    let ix = instructions::settle_swap(&swap_key, &swap, &maker.tokens, &taker.tokens, vec![]);
+    assert_anchor_error(market.send(&[ix], &[]), ErrorCode::AccountNotInitialized);
+}
+
+#[test]
+fn failed_swap_refunds_both_cash_legs() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let maker = market.trader(50_000_000);
+    let taker = market.trader(10_000_000);
+    let expiry = market.svm.now() + 3_600;
+    let ix = instructions::propose_swap(
+        &maker.wallet,
+        &taker.wallet,
+        &market.mint,
+        &maker.tokens,
+        1,
+        vec![11, 12],
+        vec![22],
+        40_000_000,
+        5_000_000,
+        expiry,
+        300,
+    );
+    market.send(&[ix], &[maker.wallet]).unwrap();
+    let swap_key = pda::swap(&maker.wallet, 1);
+    accept(&mut market, &swap_key, &taker).unwrap();
+    assert_eq!(market.svm.balance(&pda::swap_vault(&swap_key)), 45_000_000);
+
+    assert_error(
+        refund(&mut market, &swap_key, &maker, &taker, &oracles[..1]),
+        TradeEscrowError::CannotRefund,
+    );
+    // A failure receipt refunds before the deadline
+    let vault = pda::swap_vault(&swap_key);
+    let maker_lamports = market.svm.lamports(&maker.wallet);
+    let rent = market.svm.lamports(&swap_key) + market.svm.lamports(&vault);
+    refund(&mut market, &swap_key, &maker, &taker, &oracles[..2]).unwrap();
+    assert_eq!(market.svm.balance(&maker.tokens), 50_000_000);
+    assert_eq!(market.svm.balance(&taker.tokens), 10_000_000);
+    assert_eq!(market.config().total_locked, 0);
+    assert!(!market.svm.exists(&swap_key));
+    assert!(!market.svm.exists(&vault));
+    assert_eq!(market.svm.lamports(&maker.wallet), maker_lamports + rent);
+    assert_eq!(
+        market.svm.events::<SwapRefunded>()[0].reason,
+        RefundReason::DeliveryFailed
+    );
+}
+
+#[test]
+fn expired_swap_refunds_without_receipt() {
+    let mut market = Market::new();
+    let oracles = market.oracles;
+    let maker = market.trader(50_000_000);
+    let taker = market.trader(0);
+    let swap_key = propose(&mut market, &maker, &taker);
+    accept(&mut market, &swap_key, &taker).unwrap();
+
+    market.svm.warp(301);
+    assert_error(
+        settle(&mut market, &swap_key, &maker, &taker, &oracles[..2]),
+        TradeEscrowError::CannotSettle,
+    );
+    let swap: SwapEscrow = market.svm.get(&swap_key);
+    refund(&mut market, &swap_key, &maker, &taker, &[]).unwrap();
+    assert_eq!(market.svm.balance(&maker.tokens), 50_000_000);
+    assert_eq!(
+        market.svm.events::<SwapRefunded>()[0].reason,
+        RefundReason::DeadlineExpired
+    );
+    let ix = instructions::refund_swap(&swap_key, &swap, &maker.tokens, &taker.tokens, vec![]);
+    assert_anchor_error(market.send(&[ix], &[]), ErrorCode::AccountNotInitialized);
+}
+
+#[test]
+fn propose_swap_validates_terms() {
+    let mut market = Market::new();
+    let maker = market.trader(50_000_000);
+    let taker = market.trader(0);
+    let now = market.svm.now();
+    let mint = market.mint;
+
+    let invalid = [
+        (taker.wallet, vec![], vec![22], now + 60),
+        (taker.wallet, vec![11], vec![0], now + 60),
+        (taker.wallet, vec![1, 2, 3, 4, 5], vec![22], now + 60),
+        (taker.wallet, vec![11], vec![22], now),
+        (maker.wallet, vec![11], vec![22], now + 60),
+    ];
+    for (counterparty, maker_items, taker_items, expiry) in invalid {
+        let ix = instructions::propose_swap(
+            &maker.wallet,
+            &counterparty,
+            &mint,
+            &maker.tokens,
+            1,
+            maker_items,
+            taker_items,
+            0,
+            0,
+            expiry,
+            300,
+        );
+        assert_error(
+            market.send(&[ix], &[maker.wallet]),
+            TradeEscrowError::InvalidSwap,
+        );
+    }
+    let ix = instructions::propose_swap(
+        &maker.wallet,
+        &taker.wallet,
+        &mint,
+        &maker.tokens,
+        1,
+        vec![11],
+        vec![22],
+        0,
+        0,
+        now + 60,
+        0,
+    );
+    assert_error(
+        market.send(&[ix], &[maker.wallet]),
+        TradeEscrowError::InvalidDeadline,
+    );
+
+    let guardian = market.guardian;
+    market
+        .send(&[instructions::pause(&guardian)], &[guardian])
+        .unwrap();
+    let ix = instructions::propose_swap(
+        &maker.wallet,
+        &taker.wallet,
+        &mint,
+        &maker.tokens,
+        1,
+        vec![11],
+        vec![22],
+        0,
+        0,
+        now + 60,
+        300,
+    );
+    assert_error(
+        market.send(&[ix], &[maker.wallet]),
+        TradeEscrowError::ContractPaused,
+    );
+}
+
+#[test]
+fn underfunded_cash_legs_are_rejected() {
+    let mut market = Market::new();
+    let maker = market.trader(50_000_000);
+    let taker = market.trader(1_000_000);
+    let expiry = market.svm.now() + 3_600;
+    let mint = market.mint;
+    let propose_with = |maker_cash, taker_cash| {
+        instructions::propose_swap(
+            &maker.wallet,
+            &taker.wallet,
+            &mint,
+            &maker.tokens,
+            1,
+            vec![11],
+            vec![22],
+            maker_cash,
+            taker_cash,
+            expiry,
+            300,
+        )
+    };
+
+    let ix = propose_with(50_000_001, 0);
+    assert_error(
+        market.send(&[ix], &[maker.wallet]),
+        TradeEscrowError::InsufficientFunds,
+    );
+
+    let ix = propose_with(40_000_000, 5_000_000);
+    market.send(&[ix], &[maker.wallet]).unwrap();
+    let swap_key = pda::swap(&maker.wallet, 1);
+    assert_error(
+        accept(&mut market, &swap_key, &taker),
+        TradeEscrowError::InsufficientFunds,
+    );
+    assert_eq!(market.svm.balance(&taker.tokens), 1_000_000);
+}
+
+#[test]
+fn only_named_taker_accepts_before_expiry() {
+    let mut market = Market::new();
+    let maker = market.trader(50_000_000);
+    let taker = market.trader(0);
+    let stranger = market.trader(0);
+    let swap_key = propose(&mut market, &maker, &taker);
+    let swap: SwapEscrow = market.svm.get(&swap_key);
+
+    let ix = replace_account(
+        instructions::accept_swap(&swap_key, &swap, &stranger.tokens),
+        &taker.wallet,
+        stranger.wallet,
+    );
+    assert_error(
+        market.send(&[ix], &[stranger.wallet]),
+        TradeEscrowError::UnauthorizedTaker,
+    );
+
+    market.svm.warp(3_601);
+    assert_error(
+        accept(&mut market, &swap_key, &taker),
+        TradeEscrowError::SwapExpired,
+    );
+}
+
+#[test]
+fn maker_cancels_unaccepted_swap() {
+    let mut market = Market::new();
+    let maker = market.trader(50_000_000);
+    let taker = market.trader(0);
+    let swap_key = propose(&mut market, &maker, &taker);
+    let swap: SwapEscrow = market.svm.get(&swap_key);
+
+    let ix = replace_account(
+        instructions::cancel_swap(&swap_key, &swap, &taker.tokens),
+        &maker.wallet,
+        taker.wallet,
+    );
+    assert_error(
+        market.send(&[ix], &[taker.wallet]),
+        TradeEscrowError::UnauthorizedSeller,
+    );
+
+    market
+        .send(
+            &[instructions::cancel_swap(&swap_key, &swap, &maker.tokens)],
+            &[maker.wallet],
+        )
+        .unwrap();
+    assert_eq!(market.svm.balance(&maker.tokens), 50_000_000);
+    assert_eq!(market.config().total_locked, 0);
+    assert_eq!(market.svm.events::<SwapCancelled>()[0].swap, swap_key);
+    assert!(!market.svm.exists(&swap_key));
+}
+
+#[test]
+fn cash_legs_count_against_user_limits() {
+    let mut market = Market::new();
+    let admin = market.admin;
+    market
+        .admin(instructions::update_limits(
+            &admin, 0, 0, 50_000_000, 86_400,
+        ))
+        .unwrap();
+    let maker = market.trader(100_000_000);
+    let taker = market.trader(100_000_000);
+    let window_volume = |market: &Market, trader: &Trader| market.window_volume(&trader.wallet);
+    let offer = |market: &Market, swap_id, maker_cash, taker_cash| {
+        instructions::propose_swap(
+            &maker.wallet,
+            &taker.wallet,
+            &market.mint,
+            &maker.tokens,
+            swap_id,
+            vec![11],
+            vec![22],
+            maker_cash,
+            taker_cash,
+            market.svm.now() + 3_600,
+            300,
+        )
+    };
+
+    let swap_key = propose(&mut market, &maker, &taker);
+    assert_eq!(window_volume(&market, &maker), 40_000_000);
+    assert_error(
+        market.send(&[offer(&market, 2, 20_000_000, 0)], &[maker.wallet]),
+        TradeEscrowError::UserLimitExceeded,
+    );
+
+    // Cancelling an offer frees the maker's limit again
+    let swap: SwapEscrow = market.svm.get(&swap_key);
+    market
+        .send(
+            &[instructions::cancel_swap(&swap_key, &swap, &maker.tokens)],
+            &[maker.wallet],
+        )
+        .unwrap();
+    assert_eq!(window_volume(&market, &maker), 0);
+    market
+        .send(&[offer(&market, 2, 20_000_000, 0)], &[maker.wallet])
+        .unwrap();
+    assert_eq!(window_volume(&market, &maker), 20_000_000);
+
+    // The taker's leg is counted against the taker when they accept
+    market
+        .send(&[offer(&market, 3, 0, 60_000_000)], &[maker.wallet])
+        .unwrap();
+    assert_error(
+        accept(&mut market, &pda::swap(&maker.wallet, 3), &taker),
+        TradeEscrowError::UserLimitExceeded,
+    );
+    accept(&mut market, &pda::swap(&maker.wallet, 2), &taker).unwrap();
+    assert_eq!(window_volume(&market, &taker), 0);
+    assert_eq!(market.config().total_locked, 20_000_000);
+
+    // A refunded swap never traded, so its cash leg is released too
+    market.svm.warp(301);
+    refund(
+        &mut market,
+        &pda::swap(&maker.wallet, 2),
+        &maker,
+        &taker,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(window_volume(&market, &maker), 0);
+    assert_eq!(market.config().total_locked, 0);
+}
\ No newline at end of file